@@ -0,0 +1,135 @@
+//! Random path sets for property based testing and fuzzing.
+//!
+//! The generator is deterministic and dependency free, so it can be seeded
+//! from a number in a property test or from the raw input of a fuzzer.
+
+use std::collections::HashSet;
+
+use crate::node::*;
+
+/// Generates random path lists in the format of `paths.txt`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::arbitrary::*;
+///
+/// let mut generator = PathSetGenerator::new(42);
+/// for _ in 0..100 {
+///   let paths = generator.path_set(32, 6);
+///   assert!(check_insertion(&paths).is_ok());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathSetGenerator {
+  state: u64,
+}
+
+impl PathSetGenerator {
+  /// Creates a generator from `seed`, any seed giving a working generator.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::arbitrary::PathSetGenerator;
+  ///
+  /// let mut generator = PathSetGenerator::new(0x9E37_79B9_7F4A_7C15);
+  /// assert_ne!(generator.path_set(20, 4), generator.path_set(20, 4));
+  /// ```
+  pub fn new(seed: u64) -> PathSetGenerator {
+    // xorshift gets stuck on a zero state, which one seed mixes to.
+    let state = match seed ^ 0x9E37_79B9_7F4A_7C15 {
+      0 => 0x2545_F491_4F6C_DD1D,
+      state => state,
+    };
+    PathSetGenerator { state }
+  }
+
+  /// Creates a generator seeded from arbitrary bytes, like the input of a
+  /// fuzzer.
+  pub fn from_bytes(data: &[u8]) -> PathSetGenerator {
+    let seed = data.iter().fold(0xCBF2_9CE4_8422_2325u64, |hash, byte| {
+      (hash ^ *byte as u64).wrapping_mul(0x100_0000_01B3)
+    });
+    PathSetGenerator::new(seed)
+  }
+
+  fn next(&mut self) -> u64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 7;
+    self.state ^= self.state << 17;
+    self.state
+  }
+
+  fn below(&mut self, bound: usize) -> usize {
+    (self.next() % bound.max(1) as u64) as usize
+  }
+
+  /// Generates up to `max_paths` paths at most `max_depth` levels deep.
+  ///
+  /// Directories get a trailing slash and are listed before their
+  /// content, files get an extension. Some paths are prefixed with `./`.
+  pub fn path_set(&mut self, max_paths: usize, max_depth: usize) -> Vec<String> {
+    const NAMES: [&str; 6] = ["src", "lib", "tests", "docs", "a", "b"];
+    const EXTENSIONS: [&str; 4] = ["rs", "toml", "md", "json"];
+
+    let mut seen = HashSet::new();
+    let mut directories: Vec<String> = Vec::new();
+    let mut paths = Vec::new();
+    for _ in 0..self.below(max_paths + 1) {
+      let parent = match self.below(directories.len() + 1) {
+        0 => None,
+        i => Some(directories[i - 1].clone()),
+      };
+      let depth = parent.as_ref().map_or(0, |p| p.split('/').count());
+      if depth >= max_depth {
+        continue;
+      }
+
+      let name = NAMES[self.below(NAMES.len())];
+      let path = match &parent {
+        Some(parent) => format!("{}/{}", parent, name),
+        None => name.to_string(),
+      };
+      let prefix = if self.below(4) == 0 { "./" } else { "" };
+
+      if self.below(3) == 0 {
+        let path = format!("{}.{}", path, EXTENSIONS[self.below(EXTENSIONS.len())]);
+        if seen.insert(path.clone()) {
+          paths.push(format!("{}{}", prefix, path));
+        }
+      } else if seen.insert(path.clone()) {
+        paths.push(format!("{}{}/", prefix, path));
+        directories.push(path);
+      }
+    }
+    paths
+  }
+}
+
+/// Builds a tree from `paths`.
+fn build(paths: &[String]) -> Tree {
+  let mut tree = Tree::new();
+  for path in paths {
+    if let Some(node) = Node::from_path(path) {
+      tree.insert(node);
+    }
+  }
+  tree
+}
+
+/// Builds a tree from `paths` and checks its invariants.
+pub fn check_insertion(paths: &[String]) -> Result<(), String> {
+  build(paths).check_invariants()
+}
+
+/// Builds trees from `first` and `second`, merges them with `policy` and
+/// checks the invariants of the result.
+pub fn check_merge(first: &[String], second: &[String], policy: ConflictPolicy) -> Result<(), String> {
+  let mut tree = build(first);
+  // Fill the statistics caches so stale ones are caught.
+  tree.stats();
+  // A refused merge must leave the tree consistent too.
+  let _ = tree.merge(build(second), policy);
+  tree.check_invariants()
+}
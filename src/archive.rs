@@ -0,0 +1,402 @@
+//! Listing the members of archives without extracting them.
+//!
+//! Tar archives are read header by header, the compressed ones streamed
+//! through the `gzip`, `bzip2`, `xz` or `zstd` command, so a large archive
+//! is never decompressed on disk. Zip archives are read from their central directory, which
+//! lists the members without any decompression. The members are listed
+//! like the lines of a path list, so they are built into a tree the same
+//! way.
+
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::date::utc_time;
+use crate::node::*;
+
+/// Represents a member of an archive.
+#[derive(Clone, Debug)]
+pub struct ArchiveEntry {
+  /// The path of the member, ending with a slash for directories.
+  pub path: String,
+  /// The size, modification time, mode and owner of the member.
+  pub meta: NodeMeta,
+}
+
+/// The extensions of tar archives and the commands decompressing them.
+const TAR_EXTENSIONS: [(&str, Option<&str>); 9] = [
+  (".tar", None),
+  (".tar.gz", Some("gzip")),
+  (".tgz", Some("gzip")),
+  (".tar.bz2", Some("bzip2")),
+  (".tbz2", Some("bzip2")),
+  (".tar.xz", Some("xz")),
+  (".txz", Some("xz")),
+  (".tar.zst", Some("zstd")),
+  (".tzst", Some("zstd")),
+];
+
+/// Gets the decompressing command of the tar archive at `path` from its
+/// extension, `Some(None)` for an uncompressed archive and `None` if it is
+/// not a tar archive.
+fn tar_compression(path: &Path) -> Option<Option<&'static str>> {
+  let name = path.file_name()?.to_str()?.to_lowercase();
+  TAR_EXTENSIONS.iter()
+    .find(|(extension, _)| name.ends_with(extension))
+    .map(|(_, command)| *command)
+}
+
+/// Checks whether `path` is a zip archive, from its extension. Java, Python
+/// and Android packages are zip archives too.
+fn is_zip(path: &Path) -> bool {
+  path.extension()
+    .and_then(|extension| extension.to_str())
+    .is_some_and(|extension| ["zip", "jar", "war", "whl", "apk"].contains(&extension.to_lowercase().as_str()))
+}
+
+/// Checks whether `path` is an archive whose members can be listed, from its
+/// extension.
+pub fn is_archive(path: &Path) -> bool {
+  tar_compression(path).is_some() || is_zip(path)
+}
+
+/// Lists the members of the archive at `path`, in their order in the
+/// archive.
+pub fn read_archive(path: &Path) -> Result<Vec<ArchiveEntry>, String> {
+  if is_zip(path) {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    return read_zip(BufReader::new(file));
+  }
+  match tar_compression(path) {
+    Some(None) => {
+      let file = File::open(path).map_err(|e| e.to_string())?;
+      read_tar(BufReader::new(file))
+    },
+    Some(Some(program)) => {
+      let file = File::open(path).map_err(|e| e.to_string())?;
+      let mut child = Command::new(program).arg("-dc").stdin(file).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+      let entries = read_tar(BufReader::new(child.stdout.take().unwrap()));
+      let output = child.wait_with_output().map_err(|e| format!("{}: {}", program, e))?;
+      if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+      }
+      entries
+    },
+    None => Err("unknown archive format".to_string()),
+  }
+}
+
+/// Parses a numeric field of a tar header, in octal or, for the values too
+/// large for it, in the base-256 encoding of GNU tar.
+fn tar_number(field: &[u8]) -> Option<u64> {
+  if field.first().is_some_and(|byte| byte & 0x80 != 0) {
+    return Some(field[1..].iter().fold(u64::from(field[0] & 0x7f), |value, &byte| value << 8 | u64::from(byte)));
+  }
+  let text = std::str::from_utf8(field).ok()?.trim_matches(|c: char| c == '\0' || c == ' ');
+  match text {
+    "" => Some(0),
+    text => u64::from_str_radix(text, 8).ok(),
+  }
+}
+
+/// Reads a string field of a tar header, which ends at its first NUL.
+fn tar_string(field: &[u8]) -> String {
+  let end = field.iter().position(|&byte| byte == 0).unwrap_or(field.len());
+  String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Reads the data of a member, rounded up to whole blocks.
+fn read_data<R: Read>(input: &mut R, size: u64) -> io::Result<Vec<u8>> {
+  let mut data = Vec::new();
+  input.take(size.div_ceil(512) * 512).read_to_end(&mut data)?;
+  if (data.len() as u64) < size {
+    return Err(io::Error::new(ErrorKind::UnexpectedEof, "truncated member"));
+  }
+  data.truncate(size as usize);
+  Ok(data)
+}
+
+/// Lists the members of the tar archive read from `input`: ustar, GNU and
+/// pax archives, with the long names of GNU tar and the `path` and `size`
+/// records of pax headers.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::archive::read_tar;
+/// use rust_tree::prelude::*;
+///
+/// let mut header = [0u8; 512];
+/// header[..12].copy_from_slice(b"src/main.rs\0");
+/// header[100..108].copy_from_slice(b"0000644\0");
+/// header[124..136].copy_from_slice(b"00000000005\0");
+/// header[136..148].copy_from_slice(b"00000000000\0");
+/// header[156] = b'0';
+/// header[148..156].copy_from_slice(b"        ");
+/// let sum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+/// header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+///
+/// let mut archive = header.to_vec();
+/// archive.extend(b"main\n".iter().chain([0; 507].iter()));
+/// archive.extend([0; 1024]);
+///
+/// let entries = read_tar(archive.as_slice()).unwrap();
+/// assert_eq!(entries[0].path, "src/main.rs");
+/// assert_eq!(entries[0].meta.size, Some(5));
+///
+/// // The size of the header is printed with the member.
+/// let mut tree = Tree::new();
+/// for entry in ["src/".to_string(), entries[0].path.clone()] {
+///   let mut node = Node::from_path(&entry).unwrap();
+///   if entry == entries[0].path {
+///     node.meta = entries[0].meta.clone();
+///   }
+///   tree.insert(node);
+/// }
+/// let mut out = Vec::new();
+/// tree.write_to(&mut out).unwrap();
+/// assert!(String::from_utf8(out).unwrap().contains("Path: src/main.rs\n     Depth: 2\n     Size: 5\n"));
+/// ```
+pub fn read_tar<R: Read>(mut input: R) -> Result<Vec<ArchiveEntry>, String> {
+  let mut entries = Vec::new();
+  // The name and size of the next member given by a GNU or pax header.
+  let mut long_name: Option<String> = None;
+  let mut pax_size: Option<u64> = None;
+  loop {
+    let mut header = [0u8; 512];
+    match input.read_exact(&mut header) {
+      Ok(()) => {},
+      // Some archivers leave the end blocks out.
+      Err(e) if e.kind() == ErrorKind::UnexpectedEof && !entries.is_empty() => break,
+      Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Err("not a tar archive".to_string()),
+      Err(e) => return Err(e.to_string()),
+    }
+    if header.iter().all(|&byte| byte == 0) {
+      break;
+    }
+
+    // The checksum is computed with its own field as spaces.
+    let sum: u64 = header.iter().enumerate()
+      .map(|(i, &byte)| if (148..156).contains(&i) { u64::from(b' ') } else { u64::from(byte) })
+      .sum();
+    if tar_number(&header[148..156]) != Some(sum) {
+      return Err(match entries.is_empty() && long_name.is_none() {
+        true => "not a tar archive".to_string(),
+        false => "corrupt tar header".to_string(),
+      });
+    }
+
+    let size = tar_number(&header[124..136]).ok_or("invalid size in tar header")?;
+    let kind = header[156];
+    match kind {
+      // The long name of the next member.
+      b'L' => {
+        let data = read_data(&mut input, size).map_err(|e| e.to_string())?;
+        long_name = Some(tar_string(&data));
+        continue;
+      },
+      // The `length key=value\n` records of the next member.
+      b'x' => {
+        let data = read_data(&mut input, size).map_err(|e| e.to_string())?;
+        for record in String::from_utf8_lossy(&data).lines() {
+          match record.split_once(' ').and_then(|(_, record)| record.split_once('=')) {
+            Some(("path", path)) => long_name = Some(path.to_string()),
+            Some(("size", size)) => pax_size = size.parse().ok(),
+            _ => {},
+          }
+        }
+        continue;
+      },
+      // Long link names, global pax headers and volume labels.
+      b'K' | b'g' | b'V' => {
+        read_data(&mut input, size).map_err(|e| e.to_string())?;
+        continue;
+      },
+      _ => {},
+    }
+
+    let name = match long_name.take() {
+      Some(name) => name,
+      None => {
+        let name = tar_string(&header[..100]);
+        let prefix = tar_string(&header[345..500]);
+        match &header[257..262] == b"ustar" && !prefix.is_empty() {
+          true => format!("{}/{}", prefix, name),
+          false => name,
+        }
+      },
+    };
+    let size = pax_size.take().unwrap_or(size);
+    let is_dir = kind == b'5' || kind == b'D' || name.ends_with('/');
+    // Links, devices, fifos and directories have no data.
+    let has_data = !matches!(kind, b'1'..=b'6');
+    if has_data {
+      read_data(&mut input, size).map_err(|e| e.to_string())?;
+    }
+
+    let permissions = tar_number(&header[100..108]).unwrap_or(0) as u32 & 0o7777;
+    let file_type = match kind {
+      _ if is_dir => 0o040000,
+      b'2' => 0o120000,
+      b'3' => 0o020000,
+      b'4' => 0o060000,
+      b'6' => 0o010000,
+      _ => 0o100000,
+    };
+    let owner = tar_number(&header[108..116]).zip(tar_number(&header[116..124]));
+    let meta = NodeMeta {
+      size: Some(if has_data && !is_dir { size } else { 0 }),
+      modified: tar_number(&header[136..148]).map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+      mode: Some(file_type | permissions),
+      owner: owner.map(|(uid, gid)| (uid as u32, gid as u32)),
+      ..NodeMeta::default()
+    };
+    let path = match is_dir && !name.ends_with('/') {
+      true => format!("{}/", name),
+      false => name,
+    };
+    entries.push(ArchiveEntry { path, meta });
+  }
+  Ok(entries)
+}
+
+/// Reads a little-endian integer of `N` bytes at `offset` of `bytes`.
+fn le<const N: usize>(bytes: &[u8], offset: usize) -> u64 {
+  bytes[offset..offset + N].iter().rev().fold(0, |value, &byte| value << 8 | u64::from(byte))
+}
+
+/// The signatures of the records of zip archives.
+const ZIP_END: u64 = 0x06054b50;
+const ZIP64_END: u64 = 0x06064b50;
+const ZIP64_LOCATOR: u64 = 0x07064b50;
+const ZIP_ENTRY: u64 = 0x02014b50;
+
+/// Finds the offset and the size of the central directory of a zip
+/// archive from its end records, zip64 ones included.
+fn zip_directory<R: Read + Seek>(input: &mut R) -> io::Result<Option<(u64, u64, u64)>> {
+  // The end record is followed by a comment of at most 64 KiB.
+  let length = input.seek(SeekFrom::End(0))?;
+  let start = length.saturating_sub(22 + 0xffff);
+  input.seek(SeekFrom::Start(start))?;
+  let mut tail = Vec::new();
+  input.read_to_end(&mut tail)?;
+  let Some(end) = (0..tail.len().saturating_sub(21)).rev().find(|&i| le::<4>(&tail, i) == ZIP_END) else {
+    return Ok(None);
+  };
+  let (mut count, mut size, mut offset) = (le::<2>(&tail, end + 10), le::<4>(&tail, end + 12), le::<4>(&tail, end + 16));
+
+  // The values too large for the fields are in the zip64 end record.
+  if end >= 20 && le::<4>(&tail, end - 20) == ZIP64_LOCATOR {
+    let mut record = [0u8; 56];
+    input.seek(SeekFrom::Start(le::<8>(&tail, end - 12)))?;
+    input.read_exact(&mut record)?;
+    if le::<4>(&record, 0) == ZIP64_END {
+      (count, size, offset) = (le::<8>(&record, 32), le::<8>(&record, 40), le::<8>(&record, 48));
+    }
+  }
+  Ok(Some((count, size, offset)))
+}
+
+/// Lists the members of the zip archive read from `input` from its central
+/// directory, with their uncompressed and compressed sizes, their
+/// modification times and, for the archives made on Unix, their modes.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use rust_tree::archive::read_zip;
+///
+/// let name = b"docs/index.md";
+/// let mut archive = Vec::new();
+/// // The central directory entry, compressed from 120 to 80 bytes.
+/// archive.extend(0x02014b50u32.to_le_bytes());
+/// archive.extend([0; 16]);
+/// archive.extend(80u32.to_le_bytes());
+/// archive.extend(120u32.to_le_bytes());
+/// archive.extend((name.len() as u16).to_le_bytes());
+/// archive.extend([0; 16]);
+/// archive.extend(name);
+/// // The end record.
+/// let size = archive.len() as u32;
+/// archive.extend(0x06054b50u32.to_le_bytes());
+/// archive.extend([0, 0, 0, 0, 1, 0, 1, 0]);
+/// archive.extend(size.to_le_bytes());
+/// archive.extend([0; 6]);
+///
+/// let entries = read_zip(Cursor::new(archive)).unwrap();
+/// assert_eq!(entries[0].path, "docs/index.md");
+/// assert_eq!(entries[0].meta.size, Some(120));
+/// assert_eq!(entries[0].meta.compressed_size, Some(80));
+/// ```
+pub fn read_zip<R: Read + Seek>(mut input: R) -> Result<Vec<ArchiveEntry>, String> {
+  let Some((count, size, offset)) = zip_directory(&mut input).map_err(|e| e.to_string())? else {
+    return Err("not a zip archive".to_string());
+  };
+  let mut directory = Vec::new();
+  input.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+  input.take(size).read_to_end(&mut directory).map_err(|e| e.to_string())?;
+
+  let mut entries = Vec::new();
+  let mut at = 0;
+  for _ in 0..count {
+    if at + 46 > directory.len() || le::<4>(&directory, at) != ZIP_ENTRY {
+      return Err("corrupt zip central directory".to_string());
+    }
+    let header = &directory[at..];
+    let name_length = le::<2>(header, 28) as usize;
+    let extra_length = le::<2>(header, 30) as usize;
+    let comment_length = le::<2>(header, 32) as usize;
+    if 46 + name_length + extra_length > header.len() {
+      return Err("corrupt zip central directory".to_string());
+    }
+    let name = String::from_utf8_lossy(&header[46..46 + name_length]).into_owned();
+    let extra = &header[46 + name_length..46 + name_length + extra_length];
+    at += 46 + name_length + extra_length + comment_length;
+
+    let (mut compressed, mut uncompressed) = (le::<4>(header, 20), le::<4>(header, 24));
+    // DOS times are local times without a time zone, taken as UTC.
+    let (time, date) = (le::<2>(header, 12) as u32, le::<2>(header, 14) as u32);
+    let mut modified = utc_time(1980 + i64::from(date >> 9), (date >> 5 & 15).max(1), (date & 31).max(1), time >> 11, time >> 5 & 63, (time & 31) * 2);
+
+    let mut field = 0;
+    while field + 4 <= extra.len() {
+      let (id, length) = (le::<2>(extra, field), le::<2>(extra, field + 2) as usize);
+      let data = &extra[field + 4..(field + 4 + length).min(extra.len())];
+      match id {
+        // The zip64 sizes, for the fields full of ones, in this order.
+        0x0001 => {
+          let mut values = data.chunks_exact(8).map(|chunk| le::<8>(chunk, 0));
+          if uncompressed == 0xffffffff {
+            uncompressed = values.next().unwrap_or(uncompressed);
+          }
+          if compressed == 0xffffffff {
+            compressed = values.next().unwrap_or(compressed);
+          }
+        },
+        // The extended timestamp, starting with the modification time.
+        0x5455 if data.len() >= 5 && data[0] & 1 != 0 => {
+          modified = UNIX_EPOCH + Duration::from_secs(le::<4>(data, 1));
+        },
+        _ => {},
+      }
+      field += 4 + length;
+    }
+
+    let is_dir = name.ends_with('/');
+    // The external attributes of Unix archivers hold the mode.
+    let mode = (le::<2>(header, 4) >> 8 == 3).then(|| (le::<4>(header, 38) >> 16) as u32).filter(|&mode| mode != 0);
+    let meta = NodeMeta {
+      size: Some(if is_dir { 0 } else { uncompressed }),
+      compressed_size: (!is_dir).then_some(compressed),
+      modified: Some(modified),
+      mode,
+      ..NodeMeta::default()
+    };
+    entries.push(ArchiveEntry { path: name, meta });
+  }
+  Ok(entries)
+}
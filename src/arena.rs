@@ -0,0 +1,481 @@
+//! An arena representation of trees, with parent links and stable handles.
+//!
+//! `Tree` owns its nodes through nested `children` vectors, which is simple
+//! to read but cannot point back from a node to its parent. An `ArenaTree`
+//! stores the nodes side by side in slots addressed by `NodeId`, each slot
+//! holding the links to its parent and its children, so walking up, moving
+//! a subtree or keeping a handle across mutations is cheap.
+//!
+//! `Tree` stays the main API: a tree is converted to an arena when these
+//! operations are needed, and back when done. Mounted trees are grafted
+//! under their mount points by the conversion.
+//!
+//! ```
+//! use rust_tree::arena::ArenaTree;
+//! use rust_tree::prelude::*;
+//!
+//! let mut tree = Tree::new();
+//! tree.insert(Node::from_path("src/").unwrap());
+//! tree.insert(Node::from_path("src/main.rs").unwrap());
+//!
+//! let arena = ArenaTree::from(tree);
+//! let main = arena.find_by_path("src/main.rs").unwrap();
+//! let src = arena.parent(main).unwrap();
+//! assert_eq!(arena.get(src).unwrap().name, "src");
+//!
+//! let tree = Tree::from(arena);
+//! assert!(tree.find_by_path("src/main.rs").is_some());
+//! ```
+
+use std::collections::HashMap;
+use std::mem;
+use std::path::PathBuf;
+
+use crate::log::warning;
+use crate::node::*;
+
+/// A handle to a node of an `ArenaTree`.
+///
+/// Handles stay valid until their node is removed. The slot of a removed
+/// node is reused by new nodes, but with a new generation, so that the old
+/// handles no longer resolve to anything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId {
+  index: usize,
+  generation: u32,
+}
+
+/// A slot of an `ArenaTree`, holding a node and its links.
+#[derive(Debug)]
+struct NodeSlot {
+  /// The node, whose `children` are always empty: they are linked by
+  /// `NodeSlot::children` instead.
+  node: Node,
+  parent: Option<NodeId>,
+  children: Vec<NodeId>,
+}
+
+/// Represents a tree whose nodes are stored in an arena.
+#[derive(Debug, Default)]
+pub struct ArenaTree {
+  slots: Vec<Option<NodeSlot>>,
+  /// The generation of the handles of every slot, bumped when it is freed.
+  generations: Vec<u32>,
+  /// The generation of the slots added past the end, newer than the one of
+  /// any handle given out before the last compaction.
+  epoch: u32,
+  /// The indexes of the empty slots, reused first.
+  free: Vec<usize>,
+  root: Option<NodeId>,
+  base: Option<PathBuf>,
+}
+
+impl ArenaTree {
+  pub fn new() -> ArenaTree {
+    ArenaTree::default()
+  }
+
+  /// Gets the root node, if any.
+  pub fn root(&self) -> Option<NodeId> {
+    self.root
+  }
+
+  /// Gets the number of nodes.
+  pub fn len(&self) -> usize {
+    self.slots.len() - self.free.len()
+  }
+
+  /// Checks whether the arena holds no node.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Gets the node of `id`, without its children: `Node::stats` only
+  /// covers the node itself.
+  pub fn get(&self, id: NodeId) -> Option<&Node> {
+    self.slot(id).map(|slot| &slot.node)
+  }
+
+  /// Gets the node of `id`, allowing its metadata to be updated in place.
+  ///
+  /// The name, path and depth must not be changed through it, use
+  /// `ArenaTree::move_node` to relocate a node.
+  pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+    let node = &mut self.slot_mut(id)?.node;
+    node.invalidate_stats();
+    Some(node)
+  }
+
+  /// Gets the parent of `id`, `None` for the root or removed nodes.
+  pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+    self.slot(id)?.parent
+  }
+
+  /// Gets the children of `id`, in insertion order.
+  pub fn children(&self, id: NodeId) -> &[NodeId] {
+    self.slot(id).map_or(&[], |slot| &slot.children)
+  }
+
+  /// Iterates over the ancestors of `id`, from its parent to the root.
+  pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    let mut current = self.parent(id);
+    std::iter::from_fn(move || {
+      let id = current?;
+      current = self.parent(id);
+      Some(id)
+    })
+  }
+
+  /// Gets the nodes of the subtree of `id` in pre-order, `id` first.
+  pub fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+    let mut ids = Vec::new();
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+      if let Some(slot) = self.slot(id) {
+        ids.push(id);
+        stack.extend(slot.children.iter().rev());
+      }
+    }
+    ids
+  }
+
+  /// Finds a node from its path, walking down from the root.
+  pub fn find_by_path(&self, path: &str) -> Option<NodeId> {
+    let path = Tree::normalize_path(path);
+    let mut id = self.root?;
+    if path == "/" {
+      return Some(id);
+    }
+    for name in path.split('/') {
+      id = *self.children(id).iter().find(|child| {
+        self.get(**child).is_some_and(|node| node.name == name)
+      })?;
+    }
+    Some(id)
+  }
+
+  /// Adds `node` and its children under the directory `parent`, updating
+  /// their depth and path.
+  ///
+  /// # Returns
+  ///
+  /// The handle of the node, or a description of the problem if `parent`
+  /// is not a directory of the arena or already has a child with the same
+  /// name.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::arena::ArenaTree;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut arena = ArenaTree::new();
+  /// let root = arena.insert_root(Node::new_root());
+  /// let src = arena.insert(root, Node::from_path("src/").unwrap()).unwrap();
+  /// let main = arena.insert(src, Node::from_path("main.rs").unwrap()).unwrap();
+  /// assert_eq!(arena.get(main).unwrap().path, "src/main.rs");
+  /// ```
+  pub fn insert(&mut self, parent: NodeId, node: Node) -> Result<NodeId, String> {
+    let parent_node = self.get(parent).ok_or("no such parent")?;
+    if parent_node.node_type != NodeType::Directory() {
+      return Err(format!("{}: not a directory", parent_node.path));
+    }
+    if let Some(sibling) = self.child_named(parent, &node.name) {
+      return Err(format!("{}: already exists", self.get(sibling).map_or("", |node| &node.path)));
+    }
+
+    let mut node = node;
+    node.rebase(&parent_node.path, parent_node.depth);
+    Ok(self.link_subtree(node, Some(parent)))
+  }
+
+  /// Replaces the whole content of the arena by `root` and its children,
+  /// the handles of the nodes replaced no longer resolving.
+  pub fn insert_root(&mut self, root: Node) -> NodeId {
+    for index in (0..self.slots.len()).rev() {
+      if self.slots[index].take().is_some() {
+        self.release(index);
+      }
+    }
+    let id = self.link_subtree(root, None);
+    self.root = Some(id);
+    id
+  }
+
+  /// Detaches the node `id` and its subtree, freeing their slots.
+  ///
+  /// # Returns
+  ///
+  /// The removed node, with its children nested again, if `id` was in the
+  /// arena.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::arena::ArenaTree;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut arena = ArenaTree::new();
+  /// let root = arena.insert_root(Node::new_root());
+  /// let old = arena.insert(root, Node::from_path("old.txt").unwrap()).unwrap();
+  /// arena.remove(old);
+  ///
+  /// // The slot is reused, the old handle does not resolve to the new node.
+  /// let new = arena.insert(root, Node::from_path("new.txt").unwrap()).unwrap();
+  /// assert!(arena.get(old).is_none());
+  /// assert_eq!(arena.get(new).unwrap().name, "new.txt");
+  /// ```
+  pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+    let parent = self.slot(id)?.parent;
+    match parent {
+      Some(parent) => {
+        if let Some(slot) = self.slot_mut(parent) {
+          slot.children.retain(|child| *child != id);
+        }
+      },
+      None => self.root = None,
+    }
+    Some(self.unlink_subtree(id))
+  }
+
+  /// Moves the node `id` and its subtree under the directory `parent`.
+  ///
+  /// Only the links of the moved node change, then the depth and path of
+  /// the nodes of its subtree are updated.
+  ///
+  /// # Returns
+  ///
+  /// A description of the problem if one of the nodes is not in the arena,
+  /// if `id` is the root, if `parent` is not a directory, is inside the
+  /// moved subtree or already has a child with the same name.
+  pub fn move_node(&mut self, id: NodeId, parent: NodeId) -> Result<(), String> {
+    let node = self.get(id).ok_or("no such node")?;
+    let old_parent = self.parent(id).ok_or("cannot move the root")?;
+    let parent_node = self.get(parent).ok_or("no such parent")?;
+    if parent_node.node_type != NodeType::Directory() {
+      return Err(format!("{}: not a directory", parent_node.path));
+    }
+    if parent == id || self.ancestors(parent).any(|ancestor| ancestor == id) {
+      return Err(format!("cannot move {} inside itself", node.path));
+    }
+    if self.child_named(parent, &node.name).is_some_and(|sibling| sibling != id) {
+      return Err(format!("{}: already has a child named {}", parent_node.path, node.name));
+    }
+
+    if let Some(slot) = self.slot_mut(old_parent) {
+      slot.children.retain(|child| *child != id);
+    }
+    if let Some(slot) = self.slot_mut(parent) {
+      slot.children.push(id);
+    }
+    if let Some(slot) = self.slot_mut(id) {
+      slot.parent = Some(parent);
+    }
+
+    for id in self.descendants(id) {
+      let (parent_path, parent_depth) = match self.parent(id).and_then(|parent| self.get(parent)) {
+        Some(parent) => (parent.path.clone(), parent.depth),
+        None => continue,
+      };
+      if let Some(node) = self.get_mut(id) {
+        node.rebase_entry(&parent_path, parent_depth);
+      }
+    }
+    Ok(())
+  }
+
+  /// Rewrites the arena without the slots freed by removed nodes, the
+  /// nodes being stored in pre-order, and shrinks its buffers to fit.
+  /// Useful for long lived arenas after many removals.
+  ///
+  /// The handles held before no longer resolve, the nodes getting new
+  /// ones.
+  ///
+  /// # Returns
+  ///
+  /// The number of bytes reclaimed.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::arena::ArenaTree;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["src/", "src/main.rs", "target/", "target/main.o"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  /// let mut arena = ArenaTree::from(tree);
+  /// let target = arena.find_by_path("target").unwrap();
+  /// arena.remove(target);
+  ///
+  /// assert!(arena.compact() > 0);
+  /// assert_eq!(arena.len(), 3);
+  /// let main = arena.find_by_path("src/main.rs").unwrap();
+  /// assert_eq!(arena.get(arena.parent(main).unwrap()).unwrap().name, "src");
+  /// ```
+  pub fn compact(&mut self) -> usize {
+    let slot_size = mem::size_of::<Option<NodeSlot>>();
+    let id_size = mem::size_of::<NodeId>();
+    let before = self.slots.capacity() * slot_size + self.free.capacity() * mem::size_of::<usize>()
+      + self.generations.capacity() * mem::size_of::<u32>()
+      + self.slots.iter().flatten().map(|slot| slot.children.capacity() * id_size).sum::<usize>();
+
+    let ids = self.root.map_or(Vec::new(), |root| self.descendants(root));
+    // Every handle given out so far is older than the new ones.
+    let generation = self.generations.iter().copied().max().unwrap_or(0).max(self.epoch).wrapping_add(1);
+    // The new index of every slot kept.
+    let mut moved: Vec<Option<usize>> = vec![None; self.slots.len()];
+    for (index, id) in ids.iter().enumerate() {
+      moved[id.index] = Some(index);
+    }
+    let renumber = |id: NodeId| NodeId { index: moved[id.index].unwrap(), generation };
+    let mut slots = Vec::with_capacity(ids.len());
+    for id in &ids {
+      let mut slot = self.slots[id.index].take().unwrap();
+      slot.parent = slot.parent.map(renumber);
+      slot.children = slot.children.iter().map(|child| renumber(*child)).collect();
+      slots.push(Some(slot));
+    }
+    self.root = self.root.map(renumber);
+    self.slots = slots;
+    self.generations = vec![generation; ids.len()];
+    self.epoch = generation;
+    self.free = Vec::new();
+
+    let after = self.slots.capacity() * slot_size + self.generations.capacity() * mem::size_of::<u32>()
+      + self.slots.iter().flatten().map(|slot| slot.children.capacity() * id_size).sum::<usize>();
+    before.saturating_sub(after)
+  }
+
+  fn slot(&self, id: NodeId) -> Option<&NodeSlot> {
+    match self.generations.get(id.index) {
+      Some(generation) if *generation == id.generation => self.slots.get(id.index)?.as_ref(),
+      _ => None,
+    }
+  }
+
+  fn slot_mut(&mut self, id: NodeId) -> Option<&mut NodeSlot> {
+    match self.generations.get(id.index) {
+      Some(generation) if *generation == id.generation => self.slots.get_mut(id.index)?.as_mut(),
+      _ => None,
+    }
+  }
+
+  /// Frees the slot at `index`, already emptied, for its handles to no
+  /// longer resolve.
+  fn release(&mut self, index: usize) {
+    self.generations[index] = self.generations[index].wrapping_add(1);
+    self.free.push(index);
+  }
+
+  fn child_named(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+    self.children(parent).iter().copied().find(|child| {
+      self.get(*child).is_some_and(|node| node.name == name)
+    })
+  }
+
+  fn allocate(&mut self, slot: NodeSlot) -> NodeId {
+    let index = match self.free.pop() {
+      Some(index) => {
+        self.slots[index] = Some(slot);
+        index
+      },
+      None => {
+        self.slots.push(Some(slot));
+        self.generations.push(self.epoch);
+        self.slots.len() - 1
+      },
+    };
+    NodeId { index, generation: self.generations[index] }
+  }
+
+  /// Stores `node` and its nested children in slots, linking the node to
+  /// `parent`.
+  fn link_subtree(&mut self, node: Node, parent: Option<NodeId>) -> NodeId {
+    let mut first = None;
+    let mut stack = vec![(node, parent)];
+    while let Some((mut node, parent)) = stack.pop() {
+      let children = mem::take(&mut node.children);
+      // The cached statistics covered the nested children.
+      node.invalidate_stats();
+      let id = self.allocate(NodeSlot { node, parent, children: Vec::new() });
+      first.get_or_insert(id);
+      if let Some(slot) = parent.and_then(|parent| self.slot_mut(parent)) {
+        slot.children.push(id);
+      }
+      stack.extend(children.into_iter().rev().map(|child| (child, Some(id))));
+    }
+    first.unwrap()
+  }
+
+  /// Takes the subtree of `id` out of its slots, nesting the children of
+  /// the nodes again.
+  fn unlink_subtree(&mut self, id: NodeId) -> Node {
+    let ids = self.descendants(id);
+    let mut built: HashMap<NodeId, Node> = HashMap::new();
+    // In reverse pre-order, the children of a node are built before it.
+    for id in ids.into_iter().rev() {
+      let slot = self.slots[id.index].take().unwrap();
+      self.release(id.index);
+      let mut node = slot.node;
+      node.children = slot.children.iter().filter_map(|child| built.remove(child)).collect();
+      node.invalidate_stats();
+      built.insert(id, node);
+    }
+    built.remove(&id).unwrap()
+  }
+
+  /// Stores the nodes of the trees mounted on `tree` under their mount
+  /// points, `prefix` being the path `tree` is mounted at.
+  fn graft_mounts(&mut self, mounts: HashMap<String, Tree>, prefix: &str) {
+    let mut mounts: Vec<(String, Tree)> = mounts.into_iter().collect();
+    mounts.sort_by(|a, b| a.0.cmp(&b.0));
+    for (path, mut mounted) in mounts {
+      let path = match (prefix, path.as_str()) {
+        (prefix, "/") => prefix.to_string(),
+        ("", path) => path.to_string(),
+        (prefix, path) => format!("{}/{}", prefix, path),
+      };
+      let point = match self.find_by_path(&path) {
+        Some(point) => point,
+        None => continue,
+      };
+
+      if let Some(mut root) = mounted.root.take() {
+        for child in mem::take(&mut root.children) {
+          if let Err(e) = self.insert(point, child) {
+            warning!("Could not graft the tree mounted at {}: {}", path, e);
+          }
+        }
+      }
+      self.graft_mounts(mem::take(&mut mounted.mounts), &path);
+    }
+  }
+}
+
+impl From<Tree> for ArenaTree {
+  /// Moves the nodes of `tree` to an arena, grafting the mounted trees
+  /// under their mount points.
+  fn from(mut tree: Tree) -> ArenaTree {
+    let mut arena = ArenaTree::new();
+    arena.base = tree.base.take();
+    if let Some(root) = tree.root.take() {
+      arena.insert_root(root);
+      arena.graft_mounts(mem::take(&mut tree.mounts), "");
+    }
+    arena
+  }
+}
+
+impl From<ArenaTree> for Tree {
+  /// Moves the nodes of `arena` back to a tree, without mounts.
+  fn from(mut arena: ArenaTree) -> Tree {
+    let mut tree = Tree::new();
+    tree.base = arena.base.take();
+    if let Some(root) = arena.root {
+      tree.root = Some(arena.unlink_subtree(root));
+    }
+    tree
+  }
+}
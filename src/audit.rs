@@ -0,0 +1,218 @@
+use std::io::{self, Write};
+
+use crate::node::*;
+use crate::render::NameStyle;
+
+/// The type bits of a unix mode.
+const TYPE_MASK: u32 = 0o170000;
+/// The type bits of a symbolic link, whose permissions are meaningless.
+const SYMLINK: u32 = 0o120000;
+const SETUID: u32 = 0o4000;
+const SETGID: u32 = 0o2000;
+const STICKY: u32 = 0o1000;
+const WORLD_WRITABLE: u32 = 0o002;
+
+/// Represents a permission issue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditKind {
+  /// Anyone can write to the entry. Directories with the sticky bit, like
+  /// `/tmp`, are not reported.
+  WorldWritable,
+  /// The file runs as its owner.
+  Setuid,
+  /// The file runs as its group.
+  Setgid,
+  /// The entry is not owned by the owner of its directory.
+  OwnerMismatch { owner: u32, directory_owner: u32 },
+}
+
+impl AuditKind {
+  /// Describes the issue.
+  pub fn description(&self) -> String {
+    match self {
+      AuditKind::WorldWritable => "world-writable".to_string(),
+      AuditKind::Setuid => "setuid".to_string(),
+      AuditKind::Setgid => "setgid".to_string(),
+      AuditKind::OwnerMismatch { owner, directory_owner } => {
+        format!("owned by {} instead of {}", owner, directory_owner)
+      },
+    }
+  }
+}
+
+/// Represents a permission issue of an entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditFinding {
+  pub kind: AuditKind,
+  /// The name of the entry in its directory.
+  pub name: String,
+  pub node_type: NodeType,
+}
+
+/// Represents the permission issues of the entries of a directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryAudit {
+  /// The path of the directory, `.` for the root.
+  pub path: String,
+  pub findings: Vec<AuditFinding>,
+}
+
+/// Represents the permission issues of a tree, grouped by directory.
+#[derive(Clone, Debug, Default)]
+pub struct AuditReport {
+  /// The directories with issues, in pre-order.
+  pub directories: Vec<DirectoryAudit>,
+  /// How the paths are written, see `RenderOptions::names`.
+  pub names: NameStyle,
+}
+
+impl AuditReport {
+  /// Checks whether no issue was found.
+  pub fn is_empty(&self) -> bool {
+    self.directories.is_empty()
+  }
+
+  /// Writes every directory with issues, followed by one line per issue.
+  ///
+  /// # Output
+  ///
+  /// ```text
+  /// bin/
+  ///   setuid: helper
+  /// shared/
+  ///   world-writable: notes.txt
+  ///   owned by 1000 instead of 0: cache/
+  /// ```
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    for directory in &self.directories {
+      writeln!(out, "{}/", self.names.apply(&directory.path))?;
+      for finding in &directory.findings {
+        let slash = match finding.node_type {
+          NodeType::Directory() => "/",
+          NodeType::File() => "",
+        };
+        writeln!(out, "  {}: {}{}", finding.kind.description(), self.names.apply(&finding.name), slash)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Displays the permission issues.
+  pub fn display(&self) {
+    self.write_to(&mut io::stdout()).expect("Could not write to stdout");
+  }
+}
+
+/// Gets the permission issues of `node`, in the directory owned by
+/// `directory_owner`.
+fn node_findings(node: &Node, directory_owner: Option<u32>) -> Vec<AuditKind> {
+  let mut kinds = Vec::new();
+  if let Some(mode) = node.meta.mode {
+    if mode & TYPE_MASK == SYMLINK {
+      return kinds;
+    }
+
+    let is_dir = node.node_type == NodeType::Directory();
+    if mode & WORLD_WRITABLE != 0 && !(is_dir && mode & STICKY != 0) {
+      kinds.push(AuditKind::WorldWritable);
+    }
+    if !is_dir && mode & SETUID != 0 {
+      kinds.push(AuditKind::Setuid);
+    }
+    if !is_dir && mode & SETGID != 0 {
+      kinds.push(AuditKind::Setgid);
+    }
+  }
+
+  if let (Some((owner, _)), Some(directory_owner)) = (node.meta.owner, directory_owner) {
+    if owner != directory_owner {
+      kinds.push(AuditKind::OwnerMismatch { owner, directory_owner });
+    }
+  }
+  kinds
+}
+
+/// Joins the path of a node to the path its tree is mounted at.
+fn join(prefix: &str, path: &str) -> String {
+  match (prefix, path) {
+    (prefix, "/") => prefix.to_string(),
+    ("", path) => path.to_string(),
+    (prefix, path) => format!("{}/{}", prefix, path),
+  }
+}
+
+impl Tree {
+  /// Looks for world-writable entries, setuid and setgid files and entries
+  /// not owned by the owner of their directory, from the mode and owner
+  /// of the nodes. Nodes without them, like the ones of a path list, have
+  /// no issues.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::audit::AuditKind;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut meta = NodeMeta::default();
+  /// meta.mode = Some(0o100666);
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add("shared/notes.txt", NodeType::File(), meta);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  ///
+  /// let report = tree.audit_permissions();
+  /// assert_eq!(report.directories[0].path, "shared");
+  /// assert_eq!(report.directories[0].findings[0].kind, AuditKind::WorldWritable);
+  /// ```
+  pub fn audit_permissions(&self) -> AuditReport {
+    let mut report = AuditReport::default();
+    let root = match &self.root {
+      Some(root) => root,
+      None => return report,
+    };
+
+    // Every entry holds the tree of a directory and the path of the tree
+    // in the displayed tree.
+    let mut stack = vec![(self, root, String::new())];
+    while let Some((tree, directory, prefix)) = stack.pop() {
+      let mut children: Vec<&Node> = directory.children.iter().collect();
+      let mounted = tree.mounts.get(&directory.path)
+        .and_then(|mounted| Some((mounted, mounted.root.as_ref()?)));
+      if let Some((_, root)) = mounted {
+        children.extend(&root.children);
+      }
+
+      let directory_owner = directory.meta.owner.map(|(owner, _)| owner);
+      let findings: Vec<AuditFinding> = children.iter()
+        .flat_map(|child| node_findings(child, directory_owner).into_iter().map(|kind| AuditFinding {
+          kind,
+          name: child.name.clone(),
+          node_type: child.node_type.clone(),
+        }))
+        .collect();
+      if !findings.is_empty() {
+        let path = match join(&prefix, &directory.path) {
+          path if path.is_empty() => ".".to_string(),
+          path => path,
+        };
+        report.directories.push(DirectoryAudit { path, findings });
+      }
+
+      if let Some((mounted, root)) = mounted {
+        let prefix = join(&prefix, &directory.path);
+        for child in root.children.iter().rev() {
+          if child.node_type == NodeType::Directory() {
+            stack.push((mounted, child, prefix.clone()));
+          }
+        }
+      }
+      for child in directory.children.iter().rev() {
+        if child.node_type == NodeType::Directory() {
+          stack.push((tree, child, prefix.clone()));
+        }
+      }
+    }
+    report
+  }
+}
@@ -0,0 +1,79 @@
+use crate::node::*;
+use crate::size::parse_size;
+
+/// Represents the maximum size of a subtree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Budget {
+  /// The path of the subtree.
+  pub path: String,
+  /// The maximum size in bytes.
+  pub limit: u64,
+}
+
+impl Budget {
+  /// Parses a comma separated list of `path=size` budgets.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::budget::Budget;
+  ///
+  /// let budgets = Budget::parse_list("target/=5G,node_modules/=2G").unwrap();
+  /// assert_eq!(budgets[0].path, "target/");
+  /// assert_eq!(budgets[1].limit, 2 << 30);
+  /// ```
+  pub fn parse_list(list: &str) -> Result<Vec<Budget>, String> {
+    list.split(',')
+      .filter(|budget| !budget.trim().is_empty())
+      .map(|budget| match budget.split_once('=') {
+        Some((path, size)) => Ok(Budget {
+          path: path.trim().to_string(),
+          limit: parse_size(size)?,
+        }),
+        None => Err(format!("{}: expected path=size", budget)),
+      })
+      .collect()
+  }
+}
+
+/// Represents the result of checking a budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BudgetReport {
+  pub budget: Budget,
+  /// The aggregated size of the subtree, `None` if it is not in the tree.
+  pub size: Option<u64>,
+}
+
+impl BudgetReport {
+  /// Checks whether the subtree is bigger than its budget.
+  pub fn is_over(&self) -> bool {
+    self.size.is_some_and(|size| size > self.budget.limit)
+  }
+}
+
+impl Tree {
+  /// Checks the aggregated size of subtrees against their budgets.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::budget::Budget;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add_file("target/debug/app", 6 << 30);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  ///
+  /// let budgets = Budget::parse_list("target/=5G").unwrap();
+  /// assert!(tree.check_budgets(&budgets)[0].is_over());
+  /// ```
+  pub fn check_budgets(&self, budgets: &[Budget]) -> Vec<BudgetReport> {
+    budgets.iter()
+      .map(|budget| BudgetReport {
+        budget: budget.clone(),
+        size: self.find_by_path(&budget.path).map(|node| node.stats().size),
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,384 @@
+//! A compact binary format to persist large trees.
+//!
+//! A cache file starts with the `MAGIC` bytes and the format `VERSION`,
+//! followed by the tree. Integers are written as LEB128 varints and strings
+//! as their length followed by their UTF-8 bytes. Every node starts with a
+//! varint of flags telling its type and which of its metadata follow, so a
+//! loaded tree can be audited like a scanned one. Like the JSON format, the
+//! paths and depths of the nodes are derived from the nesting when loading.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::node::*;
+
+/// The bytes a cache file starts with.
+pub const MAGIC: &[u8; 4] = b"RTC\0";
+
+/// The version of the format, bumped on incompatible changes.
+pub const VERSION: u8 = 2;
+
+const FILE: u64 = 0;
+const DIRECTORY: u64 = 1;
+const HAS_SIZE: u64 = 1 << 1;
+const HAS_MODIFIED: u64 = 1 << 2;
+const HAS_DEFAULT_PATH: u64 = 1 << 3;
+const HAS_COMPRESSED_SIZE: u64 = 1 << 4;
+const HAS_INODE: u64 = 1 << 5;
+const HAS_LINKS: u64 = 1 << 6;
+const HAS_MODE: u64 = 1 << 7;
+const HAS_OWNER: u64 = 1 << 8;
+const HAS_CHECKSUM: u64 = 1 << 9;
+const HAS_LINK: u64 = 1 << 10;
+const LINK_BROKEN: u64 = 1 << 11;
+const HAS_ERROR: u64 = 1 << 12;
+const HAS_XATTRS: u64 = 1 << 13;
+const HAS_ATTRS: u64 = 1 << 14;
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn write_varint<W: Write>(out: &mut W, mut value: u64) -> io::Result<()> {
+  loop {
+    let byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if value == 0 {
+      return out.write_all(&[byte]);
+    }
+    out.write_all(&[byte | 0x80])?;
+  }
+}
+
+fn read_varint<R: Read>(input: &mut R) -> io::Result<u64> {
+  let mut value = 0u64;
+  for shift in (0..64).step_by(7) {
+    let mut byte = [0u8];
+    input.read_exact(&mut byte)?;
+    value |= u64::from(byte[0] & 0x7f) << shift;
+    if byte[0] & 0x80 == 0 {
+      return Ok(value);
+    }
+  }
+  Err(invalid("varint is too long"))
+}
+
+fn write_str<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+  write_varint(out, s.len() as u64)?;
+  out.write_all(s.as_bytes())
+}
+
+fn read_u32<R: Read>(input: &mut R) -> io::Result<u32> {
+  u32::try_from(read_varint(input)?).map_err(|_| invalid("number is too large"))
+}
+
+fn read_str<R: Read>(input: &mut R) -> io::Result<String> {
+  let len = read_varint(input)?;
+  let mut bytes = Vec::new();
+  input.take(len).read_to_end(&mut bytes)?;
+  if bytes.len() as u64 != len {
+    return Err(ErrorKind::UnexpectedEof.into());
+  }
+  String::from_utf8(bytes).map_err(|_| invalid("string is not UTF-8"))
+}
+
+impl Node {
+  fn write_cache<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let since_epoch = self.meta.modified.and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+    let mut flags = match self.node_type {
+      NodeType::File() => FILE,
+      NodeType::Directory() => DIRECTORY,
+    };
+    if self.meta.size.is_some() {
+      flags |= HAS_SIZE;
+    }
+    if since_epoch.is_some() {
+      flags |= HAS_MODIFIED;
+    }
+    if self.data.default_path != self.path {
+      flags |= HAS_DEFAULT_PATH;
+    }
+    let meta = &self.meta;
+    let fields = [
+      (meta.compressed_size.is_some(), HAS_COMPRESSED_SIZE),
+      (meta.inode.is_some(), HAS_INODE),
+      (meta.links.is_some(), HAS_LINKS),
+      (meta.mode.is_some(), HAS_MODE),
+      (meta.owner.is_some(), HAS_OWNER),
+      (meta.checksum.is_some(), HAS_CHECKSUM),
+      (meta.link.is_some(), HAS_LINK),
+      (meta.link.as_ref().is_some_and(|link| link.broken), LINK_BROKEN),
+      (meta.error.is_some(), HAS_ERROR),
+      (!meta.xattrs.is_empty(), HAS_XATTRS),
+      (!meta.attrs.is_empty(), HAS_ATTRS),
+    ];
+    for (present, flag) in fields {
+      if present {
+        flags |= flag;
+      }
+    }
+
+    write_varint(out, flags)?;
+    write_str(out, &self.name)?;
+    if flags & HAS_DEFAULT_PATH != 0 {
+      write_str(out, &self.data.default_path)?;
+    }
+    if let Some(size) = self.meta.size {
+      write_varint(out, size)?;
+    }
+    if let Some(since_epoch) = since_epoch {
+      write_varint(out, since_epoch.as_secs())?;
+      write_varint(out, u64::from(since_epoch.subsec_nanos()))?;
+    }
+    if let Some(compressed_size) = meta.compressed_size {
+      write_varint(out, compressed_size)?;
+    }
+    if let Some((device, inode)) = meta.inode {
+      write_varint(out, device)?;
+      write_varint(out, inode)?;
+    }
+    if let Some(links) = meta.links {
+      write_varint(out, links)?;
+    }
+    if let Some(mode) = meta.mode {
+      write_varint(out, u64::from(mode))?;
+    }
+    if let Some((uid, gid)) = meta.owner {
+      write_varint(out, u64::from(uid))?;
+      write_varint(out, u64::from(gid))?;
+    }
+    if let Some(checksum) = &meta.checksum {
+      write_str(out, checksum)?;
+    }
+    if let Some(link) = &meta.link {
+      write_str(out, &link.target)?;
+    }
+    if let Some(error) = &meta.error {
+      write_str(out, error)?;
+    }
+    if !meta.xattrs.is_empty() {
+      write_varint(out, meta.xattrs.len() as u64)?;
+      for name in &meta.xattrs {
+        write_str(out, name)?;
+      }
+    }
+    if !meta.attrs.is_empty() {
+      write_varint(out, meta.attrs.len() as u64)?;
+      for (key, value) in &meta.attrs {
+        write_str(out, key)?;
+        write_str(out, value)?;
+      }
+    }
+    write_varint(out, self.children.len() as u64)?;
+    for child in &self.children {
+      child.write_cache(out)?;
+    }
+    Ok(())
+  }
+
+  fn read_cache<R: Read>(input: &mut R, parent_path: &str, depth: u64) -> io::Result<Node> {
+    let flags = read_varint(input)?;
+
+    let name = read_str(input)?;
+    if depth > 0 && (name.is_empty() || name.contains('/')) {
+      return Err(invalid("invalid node name"));
+    }
+    let path = match (depth, parent_path) {
+      (0, _) => "/".to_string(),
+      (_, "/") => name.clone(),
+      _ => format!("{}/{}", parent_path, name),
+    };
+    let default_path = match flags & HAS_DEFAULT_PATH {
+      0 => path.clone(),
+      _ => read_str(input)?,
+    };
+    let data = NodeData {
+      length: default_path.len() as u64,
+      default_path,
+    };
+    let mut node = match flags & DIRECTORY {
+      0 => Node::new_file(data, depth, path, name),
+      _ => Node::new_directory(data, depth, path, name),
+    };
+
+    if flags & HAS_SIZE != 0 {
+      node.meta.size = Some(read_varint(input)?);
+    }
+    if flags & HAS_MODIFIED != 0 {
+      let secs = read_varint(input)?;
+      let nanos = read_varint(input)?;
+      if nanos >= 1_000_000_000 {
+        return Err(invalid("invalid modification time"));
+      }
+      node.meta.modified = UNIX_EPOCH.checked_add(Duration::new(secs, nanos as u32));
+    }
+    let meta = &mut node.meta;
+    if flags & HAS_COMPRESSED_SIZE != 0 {
+      meta.compressed_size = Some(read_varint(input)?);
+    }
+    if flags & HAS_INODE != 0 {
+      meta.inode = Some((read_varint(input)?, read_varint(input)?));
+    }
+    if flags & HAS_LINKS != 0 {
+      meta.links = Some(read_varint(input)?);
+    }
+    if flags & HAS_MODE != 0 {
+      meta.mode = Some(read_u32(input)?);
+    }
+    if flags & HAS_OWNER != 0 {
+      meta.owner = Some((read_u32(input)?, read_u32(input)?));
+    }
+    if flags & HAS_CHECKSUM != 0 {
+      meta.checksum = Some(read_str(input)?);
+    }
+    if flags & HAS_LINK != 0 {
+      meta.link = Some(Link { target: read_str(input)?, broken: flags & LINK_BROKEN != 0 });
+    }
+    if flags & HAS_ERROR != 0 {
+      meta.error = Some(read_str(input)?);
+    }
+    if flags & HAS_XATTRS != 0 {
+      for _ in 0..read_varint(input)? {
+        meta.xattrs.push(read_str(input)?);
+      }
+    }
+    if flags & HAS_ATTRS != 0 {
+      for _ in 0..read_varint(input)? {
+        let key = read_str(input)?;
+        meta.attrs.insert(key, read_str(input)?);
+      }
+    }
+
+    let children = read_varint(input)?;
+    if children > 0 && node.node_type == NodeType::File() {
+      return Err(invalid("a file cannot have children"));
+    }
+    for _ in 0..children {
+      let child = Node::read_cache(input, &node.path, depth + 1)?;
+      node.children.push(child);
+    }
+    Ok(node)
+  }
+}
+
+impl Tree {
+  /// Saves the tree, including mounted trees, to the cache file at `path`,
+  /// with the metadata of every node.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// let main = tree.find_by_path_mut("src/main.rs").unwrap();
+  /// main.meta.mode = Some(0o100644);
+  /// main.meta.link = Some(Link { target: "lib.rs".to_string(), broken: true });
+  ///
+  /// let path = std::env::temp_dir().join("rust-tree-doc.cache");
+  /// tree.save_cache(&path).unwrap();
+  /// let loaded = Tree::load_cache(&path).unwrap();
+  /// assert!(tree.diff(&loaded).is_empty());
+  /// let main = loaded.find_by_path("src/main.rs").unwrap();
+  /// assert_eq!(main.meta.mode, Some(0o100644));
+  /// assert!(main.meta.link.as_ref().unwrap().broken);
+  /// # std::fs::remove_file(&path).unwrap();
+  /// ```
+  pub fn save_cache<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    self.write_cache(&mut out)?;
+    out.flush()
+  }
+
+  /// Loads a tree saved with `Tree::save_cache`.
+  pub fn load_cache<P: AsRef<Path>>(path: P) -> io::Result<Tree> {
+    Tree::read_cache(&mut BufReader::new(File::open(path)?))
+  }
+
+  /// Checks whether the file at `path` is a cache file, from its first
+  /// bytes.
+  pub fn is_cache<P: AsRef<Path>>(path: P) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+      .and_then(|mut file| file.read_exact(&mut magic))
+      .is_ok_and(|_| &magic == MAGIC)
+  }
+
+  /// Writes the tree to `out` in the cache format.
+  pub fn write_cache<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    self.write_cache_body(out)
+  }
+
+  /// Reads a tree written with `Tree::write_cache`.
+  pub fn read_cache<R: Read>(input: &mut R) -> io::Result<Tree> {
+    let mut header = [0u8; 5];
+    input.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+      return Err(invalid("not a cache file"));
+    }
+    if header[4] != VERSION {
+      return Err(invalid(&format!("unsupported cache version {}", header[4])));
+    }
+    Tree::read_cache_body(input)
+  }
+
+  fn write_cache_body<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    match self.base() {
+      Some(base) => {
+        out.write_all(&[1])?;
+        write_str(out, &base.to_string_lossy())?;
+      },
+      None => out.write_all(&[0])?,
+    }
+    match &self.root {
+      Some(root) => {
+        out.write_all(&[1])?;
+        root.write_cache(out)?;
+      },
+      None => out.write_all(&[0])?,
+    }
+
+    let mut mounts: Vec<(&String, &Tree)> = self.mounts.iter().collect();
+    mounts.sort_by(|a, b| a.0.cmp(b.0));
+    write_varint(out, mounts.len() as u64)?;
+    for (path, tree) in mounts {
+      write_str(out, path)?;
+      tree.write_cache_body(out)?;
+    }
+    Ok(())
+  }
+
+  fn read_cache_body<R: Read>(input: &mut R) -> io::Result<Tree> {
+    let mut tree = Tree::new();
+    let mut present = [0u8];
+
+    input.read_exact(&mut present)?;
+    if present[0] != 0 {
+      tree.base = Some(PathBuf::from(read_str(input)?));
+    }
+    input.read_exact(&mut present)?;
+    if present[0] != 0 {
+      let root = Node::read_cache(input, "/", 0)?;
+      if root.node_type != NodeType::Directory() {
+        return Err(invalid("the root node is not a directory"));
+      }
+      tree.root = Some(root);
+    }
+
+    let mounts = read_varint(input)?;
+    let mut mounted = HashMap::new();
+    for _ in 0..mounts {
+      let path = read_str(input)?;
+      mounted.insert(path, Tree::read_cache_body(input)?);
+    }
+    tree.mounts = mounted;
+    Ok(tree)
+  }
+}
@@ -0,0 +1,208 @@
+//! Hashing the content of the files of a tree, so trees can be used as
+//! integrity manifests.
+//!
+//! Checksums are written like `sha256:<hex digest>`, so the algorithm goes
+//! along with the digest in the exports.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::thread;
+
+use crate::node::*;
+
+/// Represents an algorithm hashing the content of files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+  Sha256,
+}
+
+impl ChecksumAlgorithm {
+  /// Parses the name of an algorithm: `sha256`.
+  pub fn parse(name: &str) -> Result<ChecksumAlgorithm, String> {
+    match name {
+      "sha256" => Ok(ChecksumAlgorithm::Sha256),
+      _ => Err(format!("unknown checksum algorithm '{}', expected sha256", name)),
+    }
+  }
+
+  /// Gets the name of the algorithm, which prefixes its checksums.
+  pub fn name(&self) -> &'static str {
+    match self {
+      ChecksumAlgorithm::Sha256 => "sha256",
+    }
+  }
+
+  /// Hashes what `input` reads.
+  ///
+  /// # Returns
+  ///
+  /// The checksum, like `sha256:ba7816bf...`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::checksum::ChecksumAlgorithm;
+  ///
+  /// assert_eq!(
+  ///   ChecksumAlgorithm::Sha256.checksum(&b"abc"[..]).unwrap(),
+  ///   "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+  /// );
+  /// ```
+  pub fn checksum<R: Read>(&self, mut input: R) -> io::Result<String> {
+    let digest = match self {
+      ChecksumAlgorithm::Sha256 => {
+        let mut sha = Sha256::new();
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+          match input.read(&mut buffer)? {
+            0 => break,
+            read => sha.update(&buffer[..read]),
+          }
+        }
+        sha.finish()
+      },
+    };
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!("{}:{}", self.name(), hex))
+  }
+}
+
+/// The round constants of SHA-256.
+const K: [u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Computes SHA-256 digests, as specified by FIPS 180-4.
+#[derive(Clone, Debug)]
+struct Sha256 {
+  state: [u32; 8],
+  /// The bytes not hashed yet, less than a block.
+  pending: Vec<u8>,
+  length: u64,
+}
+
+impl Sha256 {
+  fn new() -> Sha256 {
+    Sha256 {
+      state: [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19],
+      pending: Vec::with_capacity(64),
+      length: 0,
+    }
+  }
+
+  fn update(&mut self, mut bytes: &[u8]) {
+    self.length += bytes.len() as u64;
+    if !self.pending.is_empty() {
+      let missing = (64 - self.pending.len()).min(bytes.len());
+      self.pending.extend_from_slice(&bytes[..missing]);
+      bytes = &bytes[missing..];
+      if self.pending.len() < 64 {
+        return;
+      }
+      let block: [u8; 64] = self.pending[..].try_into().unwrap();
+      self.compress(&block);
+      self.pending.clear();
+    }
+    let mut blocks = bytes.chunks_exact(64);
+    for block in &mut blocks {
+      self.compress(block.try_into().unwrap());
+    }
+    self.pending.extend_from_slice(blocks.remainder());
+  }
+
+  fn finish(mut self) -> [u8; 32] {
+    let bits = self.length.wrapping_mul(8);
+    let mut padding = vec![0x80];
+    padding.resize((119 - self.pending.len()) % 64 + 1, 0);
+    padding.extend_from_slice(&bits.to_be_bytes());
+    self.update(&padding);
+
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+      bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+  }
+
+  fn compress(&mut self, block: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks_exact(4).enumerate() {
+      w[i] = u32::from_be_bytes(word.try_into().unwrap());
+    }
+    for i in 16..64 {
+      let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+      let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+      w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+    for i in 0..64 {
+      let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+      let ch = (e & f) ^ (!e & g);
+      let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+      let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+      let maj = (a & b) ^ (a & c) ^ (b & c);
+      let t2 = s0.wrapping_add(maj);
+      (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+    }
+    for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+      *state = state.wrapping_add(value);
+    }
+  }
+}
+
+impl Tree {
+  /// Hashes the content of every file of the tree, including mounted trees,
+  /// on as many threads as the machine runs in parallel, and stores the
+  /// checksums in the metadata of the files. The files are read under
+  /// the base of their tree.
+  ///
+  /// # Returns
+  ///
+  /// The errors of the files which could not be read, like
+  /// `src/main.rs: Permission denied`, whose checksums are left unknown.
+  pub fn compute_checksums(&mut self, algorithm: ChecksumAlgorithm) -> Vec<String> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    if let Some(root) = &self.root {
+      let mut stack = vec![root];
+      while let Some(node) = stack.pop() {
+        if node.node_type == NodeType::File() {
+          files.push((node.path.clone(), self.absolute_path(node)));
+        }
+        stack.extend(node.children.iter());
+      }
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get()).min(files.len().max(1));
+    let chunk = files.len().div_ceil(threads).max(1);
+    let checksums: Vec<io::Result<String>> = thread::scope(|scope| {
+      let workers: Vec<_> = files.chunks(chunk).map(|files| {
+        scope.spawn(move || {
+          files.iter().map(|(_, path)| File::open(path).and_then(|file| algorithm.checksum(file))).collect::<Vec<_>>()
+        })
+      }).collect();
+      workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+
+    let mut errors = Vec::new();
+    for ((path, _), checksum) in files.iter().zip(checksums) {
+      match (checksum, self.find_by_path_mut(path)) {
+        (Ok(checksum), Some(node)) => node.meta.checksum = Some(checksum),
+        (Ok(_), None) => {},
+        (Err(e), _) => errors.push(format!("{}: {}", path, e)),
+      }
+    }
+    for mounted in self.mounts.values_mut() {
+      errors.extend(mounted.compute_checksums(algorithm));
+    }
+    errors
+  }
+}
@@ -0,0 +1,739 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use rust_tree::budget::Budget;
+use rust_tree::checksum::ChecksumAlgorithm;
+use rust_tree::config::Config;
+use rust_tree::date::{parse_time, DateStyle};
+use rust_tree::exec::parse_command;
+use rust_tree::filetype::FileKind;
+use rust_tree::filter::Filter;
+use rust_tree::output::{Format, Output};
+use rust_tree::render::NameStyle;
+use rust_tree::sort::SortOrder;
+use rust_tree::{parse_size, warning, Level, Regex};
+
+/// The usage message printed by `-h` and on bad arguments.
+pub const USAGE: &str = "\
+Usage: rust-tree [OPTIONS] [ROOT]...
+       rust-tree diff [OPTIONS] <OLD> <NEW>
+       rust-tree scaffold [OPTIONS] <TREE> <DIR>
+
+ROOT is a directory to scan, a tree saved as JSON, a cache file, a tar or
+zip archive, a path list, the output of tree, git:<REV> for a revision of
+the current repository, ssh://[USER@]HOST[:PORT]/PATH for a remote
+directory or s3://BUCKET/PREFIX for the objects of a bucket, paths.txt by
+default.
+Several roots are printed one after the other with a header and a summary
+of them all.
+
+Commands:
+  diff      Compare two directories or path lists, marking entries
+            with + (added), - (removed) or ~ (changed)
+  scaffold  Create the directories and the empty files of TREE, any
+            ROOT but a directory, in DIR, leaving the existing ones
+
+Options:
+      --attrs          Read the extended attributes of the entries,
+                       marking those with an ACL with + and the others
+                       with @, like ls -l
+      --audit-perms    Print the world-writable entries, setuid and setgid
+                       files and entries not owned by the owner of their
+                       directory instead of the tree
+      --auto-collapse <N>
+                       Summarize the content of the directories N levels
+                       deep, with their number of entries and their size
+      --base <DIR>     Anchor the relative paths under DIR
+      --broken-only    Only print the broken symbolic links, and the
+                       directories leading to them
+      --budget <LIST>  Check subtree sizes against budgets, like
+                       \"target/=5G,node_modules/=2G\", exiting with 3
+                       when one is exceeded
+      --checksum <ALGORITHM>
+                       Hash the content of every file with ALGORITHM,
+                       sha256, to print and export the checksums
+      --color <WHEN>   Color the marks always, never or on terminals only
+                       (auto), the default
+      --columns        Print the files of every directory in columns
+                       fitting the terminal width, like ls -C
+      --compact        Print the chains of directories holding a single
+                       directory on one line, like src/main/java
+      --count-links    Count every hard link to a file in --du mode
+      --csv            Print one CSV row per entry with its path, type,
+                       depth, size and time, the same as --output csv
+      --date-style <STYLE>
+                       Show the modification times as iso, relative (like
+                       \"3 days ago\") or locale dates
+      --device         Print the device number of every entry, and mark
+                       the mount points
+      --dot            Print the tree as a Graphviz DOT digraph, the same
+                       as --output dot
+      --du             Print the size of every directory like du instead
+                       of the tree, counting hard-linked files once
+      --emit-script    Print a shell script creating the directories and
+                       the files of the tree with mkdir -p and touch, the
+                       same as --output script
+      --exclude <PATTERN>
+                       Do not print the entries matching PATTERN, with
+                       the syntax of .treeignore lines
+      --exec <COMMAND> Run COMMAND for every file instead of printing the
+                       tree, with {} replaced by the path of the file or
+                       the path appended without {}
+      --export-exclude <PATTERN>
+                       Like --exclude, for --save-cache and the outputs
+                       written to files
+      --export-include <PATTERN>
+                       Like --include, for --save-cache and the outputs
+                       written to files
+      --ext-stats      Print the number of files and their total size per
+                       extension instead of the tree, the largest first
+      --filelimit <N>  Do not print the content of the directories with
+                       more than N entries, marking them instead
+      --find-duplicates
+                       Print the sets of files with the same content after
+                       the tree, with the space they waste
+      --git-status     Mark the entries with their git status: M for
+                       modified, A for added, ? for untracked and ! for
+                       ignored
+      --grep-name <REGEX>
+                       Only print the entries whose name or path matches
+                       the regular expression REGEX, and the directories
+                       leading to them
+      --hide-control-chars
+                       Print the control characters of the names as ?,
+                       so that hostile names cannot mess up the terminal
+      --highlight <PATTERN>
+                       Highlight the entries matching PATTERN in the tree
+                       rather than filtering them
+      --ignore-case    Match the patterns of --include, --exclude,
+                       --highlight and the export ones whatever the case
+      --include <PATTERN>
+                       Only print the entries matching PATTERN and the
+                       directories leading to them
+      --inodes         Print the inode number of every entry
+      --interactive    Browse the tree in the terminal and print the path
+                       of the selected entry
+      --jobs <N>       Run N commands of --exec at a time, 1 by default
+      --jsonl          Print one JSON object per entry, as JSON Lines, the
+                       same as --output jsonl
+      --leaves         Print the path of every file, one per line like
+                       find, instead of the tree
+      --matches-only   Only print the entries matching --highlight and
+                       the directories leading to them
+      --max-depth <N>  Only print the entries at most N levels below the
+                       root
+      --mermaid        Print the tree as a Mermaid flowchart, the same as
+                       --output mermaid
+      --min-depth <N>  Only print the entries at least N levels below the
+                       root, the shallowest ones named by their path
+      --newer <AGE|DATE>
+                       Only print the files modified after AGE ago, like
+                       7d, 12h or 2w, or after DATE, like 2023-01-01
+      --nfc            Match the patterns against the composed names, so
+                       the decomposed names of macOS match, and print
+                       with --find-duplicates the entries of a directory
+                       with the same name once composed
+      --nlink          Print the number of hard links of every entry, and
+                       mark the files met more than once in the tree
+      --older <AGE|DATE>
+                       Only print the files modified before AGE ago or
+                       before DATE
+      --only <PATHS>   Only scan these subpaths of the ROOT directory,
+                       separated by commas; can be repeated
+      --output <FORMAT[:FILE]>
+                       Write the tree as text, json, cache, xml, html,
+                       yaml, dot, mermaid, csv, jsonl, paths or script to
+                       FILE, or to stdout without FILE, instead of
+                       printing it; can be repeated to write several
+                       outputs from one scan
+      --prune          Do not print the directories without any file
+                       below them, once --include and --exclude apply
+      --save-cache <FILE>
+                       Save the tree to a cache file, which can be given
+                       as ROOT later to skip the scan
+      --size <[+|-]SIZE>
+                       Only print the files of at least SIZE, like +10M,
+                       or of less than SIZE with a minus, like -1K
+      --sort <ORDER>   Order the entries of every directory by name, by
+                       version, numbers in names being compared by value,
+                       or collate, ignoring case and accents first
+      --stream         Print the entries of a scan as soon as they are
+                       scanned rather than after the whole scan
+      --top <N>        Print the N largest files and directories instead
+                       of the tree, the largest first
+      --type <KIND>    Only print the files of KIND: image, video, text or
+                       binary, from their extension or their content; can
+                       be repeated
+      --virtual-root   Do not anchor the relative paths anywhere
+      --xattr          Like --attrs, printing the names of the attributes
+      --yaml           Print the tree as YAML with the structure of the
+                       JSON, the same as --output yaml
+  -X, --xml            Print the tree as XML like tree -X, the same as
+                       --output xml
+  -f                   Name every entry by its full path rather than its
+                       name
+  -N, --literal        Print the names as they are, the default
+  -Q, --quote-name     Print the names between double quotes, escaping
+                       the quotes and the control characters like C
+  -0, --print0         End the paths of --leaves and --output paths with
+                       a NUL byte rather than a line break, for xargs -0
+  -H <BASEURL>         Print the tree as an HTML page linking every entry
+                       to BASEURL followed by its path
+  -x                   Stay on the device of the ROOT directory, listing
+                       the mount points without reading them
+  -o <FILE>            Write what would be printed to FILE instead of
+                       stdout
+  -v, --verbose        Print more diagnostics, repeat for debug output
+  -q, --quiet          Only print errors
+  -h, --help           Print this help
+
+Relative paths are anchored under the current directory by default.
+
+Default options are read from ~/.config/rust-tree/config.toml, or from the
+file named by RUST_TREE_CONFIG, whose keys are the long options, like
+sort = \"version\", then from RUST_TREE_* variables, like RUST_TREE_SORT.
+The options given on the command line override both.
+
+Exit status:
+  0  Success
+  1  Some entries could not be read, the others were printed
+  2  Invalid arguments
+  3  A subtree is over its budget
+  4  An error stopped the program, like a root which could not be loaded";
+
+/// Represents where relative input paths are anchored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Anchor {
+  /// Under the current directory.
+  Cwd,
+  /// Under the given directory.
+  Base(PathBuf),
+  /// Under a virtual root.
+  Virtual,
+}
+
+/// Represents what the program does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+  /// Display the trees of one or more roots.
+  Show(Vec<PathBuf>),
+  /// Display the differences between two roots.
+  Diff(PathBuf, PathBuf),
+  /// Create the entries of a tree in a directory.
+  Scaffold(PathBuf, PathBuf),
+}
+
+/// Represents the command line options.
+#[derive(Clone, Debug)]
+pub struct Options {
+  /// The most verbose level of the diagnostics printed to stderr.
+  pub verbosity: Level,
+  /// The depth of the directories whose content is summarized.
+  pub auto_collapse: Option<u64>,
+  /// The depth of the shallowest entries printed.
+  pub min_depth: u64,
+  /// The depth of the deepest entries printed, `None` for no limit.
+  pub max_depth: Option<u64>,
+  /// Where relative input paths are anchored.
+  pub anchor: Anchor,
+  /// What the program does.
+  pub command: Command,
+  /// The maximum sizes of subtrees.
+  pub budgets: Vec<Budget>,
+  /// Whether the permission issues are printed instead of the tree.
+  pub audit_perms: bool,
+  /// How the modification times are shown, `None` to hide them.
+  pub date_style: Option<DateStyle>,
+  /// Whether the disk usage is printed instead of the tree.
+  pub du: bool,
+  /// Whether the files of every directory are printed in columns.
+  pub columns: bool,
+  /// Whether the chains of directories with a single directory are printed
+  /// on one line.
+  pub compact: bool,
+  /// Whether the marks are colored, `None` to only color them on
+  /// terminals.
+  pub color: Option<bool>,
+  /// The algorithm hashing the content of the files, `None` for no
+  /// checksums.
+  pub checksum: Option<ChecksumAlgorithm>,
+  /// The most entries of a directory whose content is printed.
+  pub file_limit: Option<usize>,
+  /// Whether the entries are named by their full path.
+  pub full_path: bool,
+  /// Whether the files with the same content are reported.
+  pub find_duplicates: bool,
+  /// Whether the files are counted by extension instead of printed.
+  pub ext_stats: bool,
+  /// Whether the entries are marked with their git status.
+  pub git_status: bool,
+  /// Whether the tree is browsed in the terminal rather than printed.
+  pub interactive: bool,
+  /// The words of the command run for every file instead of printing the
+  /// tree.
+  pub exec: Option<Vec<String>>,
+  /// The most commands of `exec` run at a time.
+  pub jobs: usize,
+  /// Whether the paths of the files are printed instead of the tree.
+  pub leaves: bool,
+  /// How the names of the entries are printed.
+  pub names: NameStyle,
+  /// The patterns of the entries highlighted.
+  pub highlight: Vec<String>,
+  /// Whether the entries not matching the highlight patterns are hidden.
+  pub matches_only: bool,
+  /// Whether the patterns are matched whatever the case.
+  pub ignore_case: bool,
+  /// Whether the names are composed for matching and duplicates.
+  pub nfc: bool,
+  /// Whether the paths of the flat listings end with a NUL byte.
+  pub print0: bool,
+  /// Whether the extended attributes of the entries are read.
+  pub attrs: bool,
+  /// Whether the names of the extended attributes are printed.
+  pub xattr: bool,
+  /// Whether the inode numbers of the entries are printed.
+  pub inodes: bool,
+  /// Whether the device numbers of the entries are printed.
+  pub devices: bool,
+  /// Whether the numbers of hard links of the entries are printed.
+  pub links: bool,
+  /// Whether every hard link to a file is counted by the disk usage.
+  pub count_links: bool,
+  /// The subpaths of a scanned directory to restrict the scan to.
+  pub only: Vec<PathBuf>,
+  /// Whether a scan stays on the device of the scanned directory.
+  pub one_file_system: bool,
+  /// How the entries of every directory are ordered, `None` to keep the
+  /// order of the scan or of the path list.
+  pub sort: Option<SortOrder>,
+  /// The number of the largest entries to print instead of the tree.
+  pub top: Option<usize>,
+  /// Whether the entries of a scan are printed as they are scanned.
+  pub stream: bool,
+  /// The cache file to save the tree to.
+  pub save_cache: Option<PathBuf>,
+  /// The outputs to write instead of printing the tree.
+  pub outputs: Vec<Output>,
+  /// The file to write what is printed to, `None` for stdout.
+  pub output_file: Option<PathBuf>,
+  /// The entries printed.
+  pub filter: Filter,
+  /// The entries saved to the cache file.
+  pub export_filter: Filter,
+  /// Whether the help was requested.
+  pub help: bool,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Options {
+      verbosity: Level::Warn,
+      auto_collapse: None,
+      min_depth: 0,
+      max_depth: None,
+      anchor: Anchor::Cwd,
+      command: Command::Show(vec![PathBuf::from("paths.txt")]),
+      budgets: Vec::new(),
+      audit_perms: false,
+      date_style: None,
+      du: false,
+      columns: false,
+      compact: false,
+      color: None,
+      checksum: None,
+      file_limit: None,
+      full_path: false,
+      find_duplicates: false,
+      ext_stats: false,
+      git_status: false,
+      interactive: false,
+      exec: None,
+      jobs: 1,
+      leaves: false,
+      names: NameStyle::Literal,
+      highlight: Vec::new(),
+      matches_only: false,
+      ignore_case: false,
+      nfc: false,
+      print0: false,
+      attrs: false,
+      xattr: false,
+      inodes: false,
+      devices: false,
+      links: false,
+      count_links: false,
+      only: Vec::new(),
+      one_file_system: false,
+      save_cache: None,
+      sort: None,
+      outputs: Vec::new(),
+      output_file: None,
+      filter: Filter::new(),
+      export_filter: Filter::new(),
+      stream: false,
+      top: None,
+      help: false,
+    }
+  }
+}
+
+impl Options {
+  /// Parses the command line arguments, without the program name.
+  ///
+  /// # Returns
+  ///
+  /// The options, or a message describing the first bad argument.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use rust_tree::Level;
+  ///
+  /// let options = Options::parse(vec!["-vv".to_string()]).unwrap();
+  /// assert_eq!(options.verbosity, Level::Debug);
+  /// ```
+  pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut positionals = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+      let (arg, mut value) = match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+        _ => (arg, None),
+      };
+      let mut value = |name: &str| {
+        value.take().or_else(|| args.next())
+          .ok_or_else(|| format!("'{}' needs a value", name))
+      };
+
+      match arg.as_str() {
+        "--attrs" => {
+          options.attrs = true;
+        },
+        "--audit-perms" => {
+          options.audit_perms = true;
+        },
+        "--auto-collapse" => {
+          let depth = value("--auto-collapse")?;
+          let depth = depth.parse().map_err(|_| format!("{}: invalid depth", depth))?;
+          options.auto_collapse = Some(depth);
+        },
+        "--base" => {
+          options.anchor = Anchor::Base(PathBuf::from(value("--base")?));
+        },
+        "--broken-only" => {
+          options.filter.broken_only();
+        },
+        "--budget" => {
+          options.budgets.extend(Budget::parse_list(&value("--budget")?)?);
+        },
+        "--checksum" => {
+          options.checksum = Some(ChecksumAlgorithm::parse(&value("--checksum")?)?);
+        },
+        "--color" => {
+          options.color = match value("--color")?.as_str() {
+            "always" => Some(true),
+            "never" => Some(false),
+            "auto" => None,
+            when => return Err(format!("unknown color mode '{}', expected always, never or auto", when)),
+          };
+        },
+        "--columns" => {
+          options.columns = true;
+        },
+        "--compact" => {
+          options.compact = true;
+        },
+        "--count-links" => {
+          options.count_links = true;
+        },
+        "--csv" => {
+          options.outputs.push(Output { format: Format::Csv, path: None });
+        },
+        "--date-style" => {
+          options.date_style = Some(DateStyle::parse(&value("--date-style")?)?);
+        },
+        "--device" => {
+          options.devices = true;
+        },
+        "--dot" => {
+          options.outputs.push(Output { format: Format::Dot, path: None });
+        },
+        "--du" => {
+          options.du = true;
+        },
+        "--emit-script" => {
+          options.outputs.push(Output { format: Format::Script, path: None });
+        },
+        "--exclude" => {
+          options.filter.exclude(&value("--exclude")?);
+        },
+        "--exec" => {
+          options.exec = Some(parse_command(&value("--exec")?)?);
+        },
+        "--export-exclude" => {
+          options.export_filter.exclude(&value("--export-exclude")?);
+        },
+        "--export-include" => {
+          options.export_filter.include(&value("--export-include")?);
+        },
+        "--filelimit" => {
+          let limit = value("--filelimit")?;
+          let limit = limit.parse().map_err(|_| format!("{}: invalid number of entries", limit))?;
+          options.file_limit = Some(limit);
+        },
+        "--ext-stats" => {
+          options.ext_stats = true;
+        },
+        "--find-duplicates" => {
+          options.find_duplicates = true;
+        },
+        "--git-status" => {
+          options.git_status = true;
+        },
+        "--grep-name" => {
+          options.filter.grep_name(Regex::new(&value("--grep-name")?)?);
+        },
+        "--hide-control-chars" => {
+          options.names = NameStyle::Hidden;
+        },
+        "--highlight" => {
+          options.highlight.push(value("--highlight")?);
+        },
+        "--ignore-case" => {
+          options.ignore_case = true;
+          options.filter.ignore_case();
+          options.export_filter.ignore_case();
+        },
+        "--include" => {
+          options.filter.include(&value("--include")?);
+        },
+        "--inodes" => {
+          options.inodes = true;
+        },
+        "--interactive" => {
+          options.interactive = true;
+        },
+        "--jobs" => {
+          let jobs = value("--jobs")?;
+          options.jobs = jobs.parse().ok().filter(|jobs| *jobs > 0).ok_or_else(|| format!("{}: invalid number of jobs", jobs))?;
+        },
+        "--jsonl" => {
+          options.outputs.push(Output { format: Format::Jsonl, path: None });
+        },
+        "--leaves" => {
+          options.leaves = true;
+        },
+        "--matches-only" => {
+          options.matches_only = true;
+        },
+        "--max-depth" => {
+          let depth = value("--max-depth")?;
+          let depth = depth.parse().map_err(|_| format!("{}: invalid depth", depth))?;
+          options.max_depth = Some(depth);
+        },
+        "--mermaid" => {
+          options.outputs.push(Output { format: Format::Mermaid, path: None });
+        },
+        "--min-depth" => {
+          let depth = value("--min-depth")?;
+          options.min_depth = depth.parse().map_err(|_| format!("{}: invalid depth", depth))?;
+        },
+        "--newer" => {
+          options.filter.newer(parse_time(&value("--newer")?, SystemTime::now())?);
+        },
+        "--nfc" => {
+          options.nfc = true;
+          options.filter.normalize();
+          options.export_filter.normalize();
+        },
+        "--nlink" => {
+          options.links = true;
+        },
+        "--older" => {
+          options.filter.older(parse_time(&value("--older")?, SystemTime::now())?);
+        },
+        "--only" => {
+          let paths = value("--only")?;
+          options.only.extend(paths.split(',').filter(|path| !path.is_empty()).map(PathBuf::from));
+        },
+        "--output" => {
+          options.outputs.push(Output::parse(&value("--output")?)?);
+        },
+        "--prune" => {
+          options.filter.prune();
+        },
+        "--save-cache" => {
+          options.save_cache = Some(PathBuf::from(value("--save-cache")?));
+        },
+        "--size" => {
+          let size = value("--size")?;
+          match size.strip_prefix('-') {
+            Some(size) => options.filter.max_size(parse_size(size)?),
+            None => options.filter.min_size(parse_size(size.trim_start_matches('+'))?),
+          }
+        },
+        "--sort" => {
+          options.sort = Some(SortOrder::parse(&value("--sort")?)?);
+        },
+        "--stream" => {
+          options.stream = true;
+        },
+        "--top" => {
+          let count = value("--top")?;
+          let count = count.parse().map_err(|_| format!("{}: invalid number of entries", count))?;
+          options.top = Some(count);
+        },
+        "--type" => {
+          options.filter.file_kind(FileKind::parse(&value("--type")?)?);
+        },
+        "--virtual-root" => {
+          options.anchor = Anchor::Virtual;
+        },
+        "--xattr" => {
+          options.attrs = true;
+          options.xattr = true;
+        },
+        "--yaml" => {
+          options.outputs.push(Output { format: Format::Yaml, path: None });
+        },
+        "-f" => {
+          options.full_path = true;
+        },
+        "-N" | "--literal" => {
+          options.names = NameStyle::Literal;
+        },
+        "-Q" | "--quote-name" => {
+          options.names = NameStyle::Quoted;
+        },
+        "-0" | "--print0" => {
+          options.print0 = true;
+        },
+        "-H" => {
+          let base_url = value("-H")?;
+          options.outputs.push(Output { format: Format::Html { base_url }, path: None });
+        },
+        "-X" | "--xml" => {
+          options.outputs.push(Output { format: Format::Xml, path: None });
+        },
+        "-x" => {
+          options.one_file_system = true;
+        },
+        "-o" => {
+          options.output_file = Some(PathBuf::from(value("-o")?));
+        },
+        "-v" | "--verbose" => {
+          options.verbosity = Options::more_verbose(options.verbosity);
+        },
+        "-q" | "--quiet" => {
+          options.verbosity = Level::Error;
+        },
+        "-h" | "--help" => {
+          options.help = true;
+        },
+        _ if arg.starts_with('-') && arg.len() > 2 && arg[1..].chars().all(|c| c == 'v') => {
+          for _ in 1..arg.len() {
+            options.verbosity = Options::more_verbose(options.verbosity);
+          }
+        },
+        _ if arg.starts_with('-') && arg != "-" => {
+          return Err(format!("unexpected argument '{}'", arg));
+        },
+        _ => {
+          positionals.push(arg);
+        },
+      }
+    }
+
+    if let Some(max) = options.max_depth.filter(|max| options.min_depth > *max) {
+      return Err(format!("--min-depth {} is deeper than --max-depth {}", options.min_depth, max));
+    }
+    if options.matches_only {
+      if options.highlight.is_empty() {
+        return Err("--matches-only needs a --highlight pattern".to_string());
+      }
+      for pattern in &options.highlight {
+        options.filter.include(pattern);
+      }
+    }
+
+    let mut positionals = positionals.into_iter();
+    match positionals.next().as_deref() {
+      None => {},
+      Some("diff") => {
+        match (positionals.next(), positionals.next()) {
+          (Some(old), Some(new)) => {
+            options.command = Command::Diff(PathBuf::from(old), PathBuf::from(new));
+          },
+          _ => return Err("'diff' needs an old and a new root".to_string()),
+        }
+      },
+      Some("scaffold") => {
+        match (positionals.next(), positionals.next()) {
+          (Some(tree), Some(dir)) => {
+            options.command = Command::Scaffold(PathBuf::from(tree), PathBuf::from(dir));
+          },
+          _ => return Err("'scaffold' needs a tree and a directory".to_string()),
+        }
+      },
+      Some(root) => {
+        let mut roots = vec![PathBuf::from(root)];
+        roots.extend(positionals.by_ref().map(PathBuf::from));
+        let exporting = options.save_cache.is_some() || options.outputs.iter().any(|output| output.path.is_some());
+        if roots.len() > 1 && exporting {
+          return Err("--save-cache and the outputs written to files take a single ROOT".to_string());
+        }
+        options.command = Command::Show(roots);
+      },
+    }
+    if let Some(arg) = positionals.next() {
+      return Err(format!("unexpected argument '{}'", arg));
+    }
+    Ok(options)
+  }
+
+  /// Parses the command line arguments, without the program name, after
+  /// the default options of the configuration file and of the environment,
+  /// see the `config` module, so they override them.
+  ///
+  /// # Returns
+  ///
+  /// The options, or a message describing the first bad argument, naming
+  /// the configuration file or the environment for theirs.
+  pub fn parse_with_defaults<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut defaults = Vec::new();
+    if let Some(path) = Config::path() {
+      let config = Config::load(&path)?.to_args();
+      Options::parse(config.clone()).map_err(|e| format!("{}: {}", path.display(), e))?;
+      defaults.extend(config);
+    }
+    // The variables which are not unicode cannot be options.
+    let mut vars: Vec<(String, String)> = env::vars_os()
+      .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+      .collect();
+    vars.sort();
+    // The variables of other versions, or set by mistake, must not stop
+    // every run.
+    let mut environment = Vec::new();
+    for (name, value) in vars {
+      let args = Config::from_env([(name.clone(), value)]).to_args();
+      match Options::parse(args.clone()) {
+        Err(e) if e.starts_with("unexpected argument") => warning!("environment: {} is not an option, ignoring it", name),
+        _ => environment.extend(args),
+      }
+    }
+    Options::parse(environment.clone()).map_err(|e| format!("environment: {}", e))?;
+    defaults.extend(environment);
+    Options::parse(defaults.into_iter().chain(args))
+  }
+
+  fn more_verbose(level: Level) -> Level {
+    match level {
+      Level::Error => Level::Warn,
+      Level::Warn => Level::Info,
+      Level::Info | Level::Debug => Level::Debug,
+    }
+  }
+}
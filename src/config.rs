@@ -0,0 +1,300 @@
+//! Default options from a configuration file and from the environment.
+//!
+//! The configuration file is `~/.config/rust-tree/config.toml`, or the file
+//! named by `RUST_TREE_CONFIG`. Its keys are the long options of the
+//! command line, without the dashes, and its values their arguments:
+//!
+//! ```toml
+//! # The options used when none are given.
+//! sort = "version"
+//! color = "never"
+//! exclude = ["target/", "*.log"]
+//! prune = true
+//! ```
+//!
+//! Only the part of TOML these need is read, strings, integers, booleans
+//! and arrays of them, as the options take nothing else. The
+//! variables like `RUST_TREE_SORT=version` give options too, `true` and
+//! `false` turning flags on and off.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The prefix of the environment variables giving options.
+const ENV_PREFIX: &str = "RUST_TREE_";
+
+/// Represents a value of the configuration file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigValue {
+  String(String),
+  Integer(i64),
+  Boolean(bool),
+  Array(Vec<ConfigValue>),
+}
+
+/// Represents default options, by the name of their long option.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+  /// The options in the order they are given, like `("sort", "version")`.
+  pub entries: Vec<(String, ConfigValue)>,
+}
+
+impl Config {
+  /// Gets the path of the configuration file: `RUST_TREE_CONFIG` if set,
+  /// else `rust-tree/config.toml` in `XDG_CONFIG_HOME` or in
+  /// `~/.config`. `None` if there is no home directory.
+  pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("RUST_TREE_CONFIG") {
+      return Some(PathBuf::from(path));
+    }
+    let config = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+      .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join("rust-tree").join("config.toml"))
+  }
+
+  /// Loads the configuration file at `path`, a missing file giving no
+  /// options.
+  pub fn load(path: &Path) -> Result<Config, String> {
+    match fs::read_to_string(path) {
+      Ok(text) => Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+      Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+  }
+
+  /// Parses a configuration file.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::config::{Config, ConfigValue};
+  ///
+  /// let config = Config::parse("sort = 'version' # by name\nexclude = [\n  \"target/\",\n]\n").unwrap();
+  /// assert_eq!(config.entries, [
+  ///   ("sort".to_string(), ConfigValue::String("version".to_string())),
+  ///   ("exclude".to_string(), ConfigValue::Array(vec![ConfigValue::String("target/".to_string())])),
+  /// ]);
+  /// assert_eq!(Config::parse("sort =").unwrap_err(), "line 1: expected a value");
+  /// ```
+  pub fn parse(text: &str) -> Result<Config, String> {
+    let mut parser = Parser { text, pos: 0 };
+    let mut entries = Vec::new();
+    loop {
+      parser.skip_blank(true);
+      match parser.peek() {
+        None => return Ok(Config { entries }),
+        Some('[') => return Err(parser.error("tables are not supported")),
+        Some(_) => {},
+      }
+      let key = parser.key()?;
+      parser.skip_blank(false);
+      if parser.peek() != Some('=') {
+        return Err(parser.error("expected '='"));
+      }
+      parser.pos += 1;
+      let value = parser.value()?;
+      parser.skip_blank(false);
+      if !matches!(parser.peek(), None | Some('\n')) {
+        return Err(parser.error("expected the end of the line"));
+      }
+      entries.push((key, value));
+    }
+  }
+
+  /// Gets the options given by the `RUST_TREE_*` variables of `vars`, like
+  /// `RUST_TREE_DATE_STYLE=iso` for `date-style`. `RUST_TREE_CONFIG` names
+  /// the configuration file and gives no option.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::config::{Config, ConfigValue};
+  ///
+  /// let vars = [("RUST_TREE_DATE_STYLE", "iso"), ("RUST_TREE_PRUNE", "true"), ("HOME", "/root")];
+  /// let config = Config::from_env(vars.map(|(name, value)| (name.to_string(), value.to_string())));
+  /// assert_eq!(config.to_args(), ["--date-style=iso", "--prune"]);
+  /// ```
+  pub fn from_env<I: IntoIterator<Item = (String, String)>>(vars: I) -> Config {
+    let mut entries: Vec<(String, ConfigValue)> = vars.into_iter()
+      .filter(|(name, _)| name != "RUST_TREE_CONFIG")
+      .filter_map(|(name, value)| {
+        let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase().replace('_', "-");
+        let value = match value.as_str() {
+          "true" => ConfigValue::Boolean(true),
+          "false" => ConfigValue::Boolean(false),
+          _ => ConfigValue::String(value),
+        };
+        Some((key, value))
+      })
+      .collect();
+    // The environment has no order.
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Config { entries }
+  }
+
+  /// Converts the options to command line arguments, the arrays giving
+  /// their option once per item and the false booleans leaving it out.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::config::Config;
+  ///
+  /// let config = Config::parse("top = 10\nexclude = ['*.o', '*.a']\nprune = false\nx = true").unwrap();
+  /// assert_eq!(config.to_args(), ["--top=10", "--exclude=*.o", "--exclude=*.a", "-x"]);
+  /// ```
+  pub fn to_args(&self) -> Vec<String> {
+    fn push(args: &mut Vec<String>, key: &str, value: &ConfigValue) {
+      let flag = match key.len() {
+        1 => format!("-{}", key),
+        _ => format!("--{}", key.replace('_', "-")),
+      };
+      let argument = match value {
+        ConfigValue::Boolean(true) => return args.push(flag),
+        ConfigValue::Boolean(false) => return,
+        ConfigValue::String(s) => s.clone(),
+        ConfigValue::Integer(n) => n.to_string(),
+        ConfigValue::Array(items) => return items.iter().for_each(|item| push(args, key, item)),
+      };
+      // The short options take their value as the next argument.
+      match key.len() {
+        1 => args.extend([flag, argument]),
+        _ => args.push(format!("{}={}", flag, argument)),
+      }
+    }
+
+    let mut args = Vec::new();
+    for (key, value) in &self.entries {
+      push(&mut args, key, value);
+    }
+    args
+  }
+}
+
+/// The state of the parsing of a configuration file.
+struct Parser<'a> {
+  text: &'a str,
+  pos: usize,
+}
+
+impl Parser<'_> {
+  fn error(&self, message: &str) -> String {
+    let line = self.text[..self.pos].matches('\n').count() + 1;
+    format!("line {}: {}", line, message)
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.text[self.pos..].chars().next()
+  }
+
+  /// Skips the spaces and the comments, and the line breaks too with
+  /// `lines`.
+  fn skip_blank(&mut self, lines: bool) {
+    while let Some(c) = self.peek() {
+      match c {
+        ' ' | '\t' | '\r' => self.pos += 1,
+        '\n' if lines => self.pos += 1,
+        '#' => self.pos += self.text[self.pos..].find('\n').unwrap_or(self.text.len() - self.pos),
+        _ => break,
+      }
+    }
+  }
+
+  fn key(&mut self) -> Result<String, String> {
+    if self.peek() == Some('"') {
+      return self.string();
+    }
+    let start = self.pos;
+    while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+      self.pos += 1;
+    }
+    match &self.text[start..self.pos] {
+      "" => Err(self.error("expected a key")),
+      key => Ok(key.to_string()),
+    }
+  }
+
+  fn value(&mut self) -> Result<ConfigValue, String> {
+    self.skip_blank(false);
+    match self.peek() {
+      Some('"') => Ok(ConfigValue::String(self.string()?)),
+      Some('\'') => {
+        self.pos += 1;
+        let end = self.text[self.pos..].find(['\'', '\n']).map(|end| self.pos + end);
+        match end {
+          Some(end) if self.text[end..].starts_with('\'') => {
+            let literal = self.text[self.pos..end].to_string();
+            self.pos = end + 1;
+            Ok(ConfigValue::String(literal))
+          },
+          _ => Err(self.error("unterminated string")),
+        }
+      },
+      Some('[') => self.array(),
+      Some('t') if self.text[self.pos..].starts_with("true") => {
+        self.pos += 4;
+        Ok(ConfigValue::Boolean(true))
+      },
+      Some('f') if self.text[self.pos..].starts_with("false") => {
+        self.pos += 5;
+        Ok(ConfigValue::Boolean(false))
+      },
+      Some('+' | '-' | '0'..='9') => {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| matches!(c, '+' | '-' | '_' | '0'..='9')) {
+          self.pos += 1;
+        }
+        let number = self.text[start..self.pos].replace('_', "");
+        number.parse().map(ConfigValue::Integer).map_err(|_| self.error("invalid integer"))
+      },
+      Some('\n') | None => Err(self.error("expected a value")),
+      Some(_) => Err(self.error("unexpected character")),
+    }
+  }
+
+  fn string(&mut self) -> Result<String, String> {
+    self.pos += 1;
+    let mut s = String::new();
+    loop {
+      let c = self.peek().filter(|c| *c != '\n').ok_or_else(|| self.error("unterminated string"))?;
+      self.pos += c.len_utf8();
+      match c {
+        '"' => return Ok(s),
+        '\\' => {
+          let escape = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+          self.pos += escape.len_utf8();
+          match escape {
+            '"' => s.push('"'),
+            '\\' => s.push('\\'),
+            'n' => s.push('\n'),
+            'r' => s.push('\r'),
+            't' => s.push('\t'),
+            _ => return Err(self.error("invalid escape")),
+          }
+        },
+        c => s.push(c),
+      }
+    }
+  }
+
+  fn array(&mut self) -> Result<ConfigValue, String> {
+    self.pos += 1;
+    let mut items = Vec::new();
+    loop {
+      self.skip_blank(true);
+      if self.peek() == Some(']') {
+        self.pos += 1;
+        return Ok(ConfigValue::Array(items));
+      }
+      items.push(self.value()?);
+      self.skip_blank(true);
+      match self.peek() {
+        Some(',') => self.pos += 1,
+        Some(']') => {},
+        _ => return Err(self.error("expected ',' or ']'")),
+      }
+    }
+  }
+}
@@ -0,0 +1,78 @@
+//! Writing trees as CSV, one row per node.
+//!
+//! ```text
+//! path,type,depth,size,mtime,checksum
+//! .,directory,0,4096,2024-03-09T16:00:00Z,
+//! src,directory,1,4096,2024-03-09T16:00:00Z,
+//! src/main.rs,file,2,42,2024-03-09T16:00:00Z,sha256:9f86d081884c7d65...
+//! ```
+
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use crate::date::{format_date, DateStyle};
+use crate::node::*;
+
+/// Quotes `field` if it holds a comma, a quote or a line break, doubling
+/// its quotes.
+fn quote(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+impl Tree {
+  /// Writes the tree, including mounted trees, to `out` as CSV with a
+  /// header and one row per node: its path relative to the root (`.` for
+  /// the root), its type, its depth, its size, its modification time and
+  /// its checksum. The unknown values are left empty.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// let mut node = Node::from_path("src/a,b.rs").unwrap();
+  /// node.meta.size = Some(42);
+  /// tree.insert(node);
+  ///
+  /// let mut out = Vec::new();
+  /// tree.write_csv(&mut out).unwrap();
+  /// assert_eq!(
+  ///   String::from_utf8(out).unwrap(),
+  ///   "path,type,depth,size,mtime,checksum\n.,directory,0,,,\nsrc,directory,1,,,\n\"src/a,b.rs\",file,2,42,,\n",
+  /// );
+  /// ```
+  pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    writeln!(out, "path,type,depth,size,mtime,checksum")?;
+
+    let now = SystemTime::now();
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut result = Ok(());
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      let path = if depth == 0 {
+        ".".to_string()
+      } else {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+        names.join("/")
+      };
+      let node_type = match node.node_type {
+        NodeType::File() => "file",
+        NodeType::Directory() => "directory",
+      };
+      let size = node.meta.size.map(|size| size.to_string()).unwrap_or_default();
+      let modified = node.meta.modified.map(|modified| format_date(modified, DateStyle::Iso, now)).unwrap_or_default();
+      let checksum = node.meta.checksum.as_deref().unwrap_or_default();
+      result = writeln!(out, "{},{},{},{},{},{}", quote(&path), node_type, depth, size, modified, checksum);
+      result.is_ok()
+    });
+    result
+  }
+}
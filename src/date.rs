@@ -0,0 +1,250 @@
+//! Rendering of modification times, shared by the text output and the
+//! reports.
+//!
+//! Dates are rendered in UTC, so the exports of a tree are the same
+//! whatever the time zone of the machine writing them.
+
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Represents how dates are rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateStyle {
+  /// ISO 8601, like `2024-03-09T14:05:00Z`.
+  Iso,
+  /// The time elapsed until now, like `3 days ago`.
+  Relative,
+  /// The numeric date and time of the locale of `LC_ALL`, `LC_TIME` or
+  /// `LANG`, like `03/09/2024 02:05 PM` for `en_US`.
+  Locale,
+}
+
+impl DateStyle {
+  /// Parses the name of a style: `iso`, `relative` or `locale`.
+  pub fn parse(name: &str) -> Result<DateStyle, String> {
+    match name {
+      "iso" => Ok(DateStyle::Iso),
+      "relative" => Ok(DateStyle::Relative),
+      "locale" => Ok(DateStyle::Locale),
+      _ => Err(format!("unknown date style '{}', expected iso, relative or locale", name)),
+    }
+  }
+}
+
+/// Represents a date and time in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Civil {
+  year: i64,
+  month: u32,
+  day: u32,
+  hour: u32,
+  minute: u32,
+  second: u32,
+}
+
+impl Civil {
+  fn from_time(time: SystemTime) -> Civil {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+      Ok(since) => since.as_secs() as i64,
+      Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    };
+    let (days, rest) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // From the proleptic Gregorian calendar algorithms of Howard Hinnant.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    Civil {
+      year,
+      month,
+      day,
+      hour: (rest / 3600) as u32,
+      minute: (rest / 60 % 60) as u32,
+      second: (rest % 60) as u32,
+    }
+  }
+
+  fn to_time(self) -> SystemTime {
+    let year = self.year - i64::from(self.month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((self.month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let secs = days * 86400 + i64::from(self.hour * 3600 + self.minute * 60 + self.second);
+    match secs {
+      secs if secs >= 0 => UNIX_EPOCH + Duration::from_secs(secs as u64),
+      secs => UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()),
+    }
+  }
+}
+
+/// Gets the time of a date and time in UTC, for the formats storing them
+/// rather than timestamps.
+pub(crate) fn utc_time(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> SystemTime {
+  Civil { year, month, day, hour, minute, second }.to_time()
+}
+
+/// Parses a point in time given as an age, like `30m`, `12h`, `7d`, `2w` or
+/// `1y` before `now`, or as a date in UTC like `2023-01-01` or
+/// `2023-01-01T12:30:00Z`.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use rust_tree::date::parse_time;
+///
+/// let now = UNIX_EPOCH + Duration::from_secs(1_710_000_000);
+/// assert_eq!(parse_time("7d", now), Ok(now - Duration::from_secs(7 * 86400)));
+/// assert_eq!(parse_time("2024-03-09", now), Ok(UNIX_EPOCH + Duration::from_secs(1_709_942_400)));
+/// assert_eq!(parse_time("2024-03-09T16:00:00Z", now), Ok(now));
+/// assert!(parse_time("soon", now).is_err());
+/// ```
+pub fn parse_time(text: &str, now: SystemTime) -> Result<SystemTime, String> {
+  let invalid = || format!("{}: invalid age or date, expected like 7d or 2023-01-01", text);
+  if let Some(unit) = text.chars().last().filter(|unit| unit.is_ascii_alphabetic() && text.len() > 1 && !text.contains('-')) {
+    let seconds = match unit {
+      's' => 1,
+      'm' => 60,
+      'h' => 3600,
+      'd' => 86400,
+      'w' => 7 * 86400,
+      'y' => 365 * 86400,
+      _ => return Err(invalid()),
+    };
+    let count: u64 = text[..text.len() - 1].parse().map_err(|_| invalid())?;
+    return now.checked_sub(Duration::from_secs(count * seconds)).ok_or_else(invalid);
+  }
+
+  let (date, time) = text.trim_end_matches('Z').split_once('T').unwrap_or((text, "00:00:00"));
+  let numbers = |text: &str, separator: char| -> Option<Vec<u32>> {
+    text.split(separator).map(|number| number.parse().ok()).collect()
+  };
+  match (numbers(date, '-').as_deref(), numbers(time, ':').as_deref()) {
+    (Some(&[year, month, day]), Some(&[hour, minute, second])) if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+      Ok(utc_time(i64::from(year), month, day, hour, minute, second))
+    },
+    _ => Err(invalid()),
+  }
+}
+
+/// Formats `time` with `style`, `now` being the reference of relative
+/// dates.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use rust_tree::date::{format_date, DateStyle};
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1_710_000_000);
+/// assert_eq!(format_date(time, DateStyle::Iso, time), "2024-03-09T16:00:00Z");
+///
+/// let now = time + Duration::from_secs(3 * 86400 + 600);
+/// assert_eq!(format_date(time, DateStyle::Relative, now), "3 days ago");
+/// assert_eq!(format_date(now, DateStyle::Relative, time), "in 3 days");
+/// ```
+pub fn format_date(time: SystemTime, style: DateStyle, now: SystemTime) -> String {
+  match style {
+    DateStyle::Iso => {
+      let c = Civil::from_time(time);
+      format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", c.year, c.month, c.day, c.hour, c.minute, c.second)
+    },
+    DateStyle::Relative => {
+      match now.duration_since(time) {
+        Ok(elapsed) => match format_duration(elapsed) {
+          Some(elapsed) => format!("{} ago", elapsed),
+          None => "just now".to_string(),
+        },
+        Err(e) => match format_duration(e.duration()) {
+          Some(remaining) => format!("in {}", remaining),
+          None => "just now".to_string(),
+        },
+      }
+    },
+    DateStyle::Locale => {
+      let locale = ["LC_ALL", "LC_TIME", "LANG"].iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+      format_locale_date(time, &locale)
+    },
+  }
+}
+
+/// Formats `time` with the numeric date and time of `locale`, like `en_US`
+/// or `de_DE.UTF-8`. Unknown locales, `C` and `POSIX` get an ISO 8601 like
+/// date.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use rust_tree::date::format_locale_date;
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1_710_000_000);
+/// assert_eq!(format_locale_date(time, "en_US.UTF-8"), "03/09/2024 04:00 PM");
+/// assert_eq!(format_locale_date(time, "de_DE"), "09.03.2024 16:00");
+/// assert_eq!(format_locale_date(time, "ja_JP"), "2024/03/09 16:00");
+/// assert_eq!(format_locale_date(time, "C"), "2024-03-09 16:00");
+/// ```
+pub fn format_locale_date(time: SystemTime, locale: &str) -> String {
+  let c = Civil::from_time(time);
+  let name = locale.split(['.', '@']).next().unwrap_or("");
+  let language = name.split(['_', '-']).next().unwrap_or("");
+
+  match (language, name) {
+    ("en", "en_US" | "en_PH" | "en-US") => {
+      let hour = match c.hour % 12 {
+        0 => 12,
+        hour => hour,
+      };
+      let period = if c.hour < 12 { "AM" } else { "PM" };
+      format!("{:02}/{:02}/{:04} {:02}:{:02} {}", c.month, c.day, c.year, hour, c.minute, period)
+    },
+    ("en" | "fr" | "es" | "it" | "pt" | "el" | "ca" | "ga", _) => {
+      format!("{:02}/{:02}/{:04} {:02}:{:02}", c.day, c.month, c.year, c.hour, c.minute)
+    },
+    ("de" | "da" | "fi" | "nb" | "nn" | "no" | "ru" | "uk" | "pl" | "cs" | "sk" | "tr" | "ro", _) => {
+      format!("{:02}.{:02}.{:04} {:02}:{:02}", c.day, c.month, c.year, c.hour, c.minute)
+    },
+    ("nl", _) => {
+      format!("{:02}-{:02}-{:04} {:02}:{:02}", c.day, c.month, c.year, c.hour, c.minute)
+    },
+    ("ja" | "zh" | "ko", _) => {
+      format!("{:04}/{:02}/{:02} {:02}:{:02}", c.year, c.month, c.day, c.hour, c.minute)
+    },
+    _ => {
+      format!("{:04}-{:02}-{:02} {:02}:{:02}", c.year, c.month, c.day, c.hour, c.minute)
+    },
+  }
+}
+
+/// Formats a duration in its largest whole unit, like `3 days`, `None`
+/// under a minute.
+fn format_duration(duration: Duration) -> Option<String> {
+  const UNITS: [(u64, &str); 6] = [
+    (365 * 86400, "year"),
+    (30 * 86400, "month"),
+    (7 * 86400, "week"),
+    (86400, "day"),
+    (3600, "hour"),
+    (60, "minute"),
+  ];
+
+  let secs = duration.as_secs();
+  let (count, unit) = UNITS.iter()
+    .find(|(length, _)| secs >= *length)
+    .map(|(length, unit)| (secs / length, *unit))?;
+  let plural = if count == 1 { "" } else { "s" };
+  Some(format!("{} {}{}", count, unit, plural))
+}
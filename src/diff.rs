@@ -0,0 +1,204 @@
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+use crate::node::*;
+use crate::render::NameStyle;
+
+/// Represents how an entry differs between two trees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+  /// Only in the new tree.
+  Added,
+  /// Only in the old tree.
+  Removed,
+  /// In both trees, with a different type or size.
+  Changed,
+}
+
+impl DiffKind {
+  /// Gets the marker rendered before the entries of this kind.
+  pub fn marker(&self) -> char {
+    match self {
+      DiffKind::Added => '+',
+      DiffKind::Removed => '-',
+      DiffKind::Changed => '~',
+    }
+  }
+}
+
+/// Represents an entry that differs between two trees.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffEntry {
+  pub kind: DiffKind,
+  /// The path of the entry.
+  pub path: String,
+  /// The type of the entry, in the new tree for changed entries.
+  pub node_type: NodeType,
+}
+
+/// Represents the structural differences between two trees.
+#[derive(Clone, Debug, Default)]
+pub struct TreeDiff {
+  /// The differing entries, in pre-order with siblings sorted by name.
+  pub entries: Vec<DiffEntry>,
+  /// How the paths are written, see `RenderOptions::names`.
+  pub names: NameStyle,
+}
+
+impl TreeDiff {
+  /// Checks whether the trees are structurally identical.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Writes one line per entry, made of its marker and its path, with a
+  /// trailing slash for directories.
+  ///
+  /// # Output
+  ///
+  /// ```text
+  /// + src/lib.rs
+  /// - docs/
+  /// - docs/index.md
+  /// ~ Cargo.toml
+  /// ```
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    for entry in &self.entries {
+      let slash = match entry.node_type {
+        NodeType::Directory() => "/",
+        NodeType::File() => "",
+      };
+      writeln!(out, "{} {}{}", entry.kind.marker(), self.names.apply(&entry.path), slash)?;
+    }
+    Ok(())
+  }
+
+  /// Displays the differences.
+  pub fn display(&self) {
+    self.write_to(&mut io::stdout()).expect("Could not write to stdout");
+  }
+
+  fn push_all(&mut self, kind: DiffKind, node: &Node) {
+    self.entries.push(DiffEntry {
+      kind,
+      path: node.path.clone(),
+      node_type: node.node_type.clone(),
+    });
+    for child in TreeDiff::sorted(&node.children) {
+      self.push_all(kind, child);
+    }
+  }
+
+  fn sorted(nodes: &[Node]) -> Vec<&Node> {
+    let mut sorted: Vec<&Node> = nodes.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    sorted
+  }
+
+  fn diff_recursive(&mut self, old: &Node, new: &Node) {
+    let old_children = TreeDiff::sorted(&old.children);
+    let new_children = TreeDiff::sorted(&new.children);
+    let (mut i, mut j) = (0, 0);
+    while i < old_children.len() || j < new_children.len() {
+      let order = match (old_children.get(i), new_children.get(j)) {
+        (Some(a), Some(b)) => a.name.cmp(&b.name),
+        (Some(_), None) => Ordering::Less,
+        _ => Ordering::Greater,
+      };
+
+      match order {
+        Ordering::Less => {
+          self.push_all(DiffKind::Removed, old_children[i]);
+          i += 1;
+        },
+        Ordering::Greater => {
+          self.push_all(DiffKind::Added, new_children[j]);
+          j += 1;
+        },
+        Ordering::Equal => {
+          self.diff_nodes(old_children[i], new_children[j]);
+          i += 1;
+          j += 1;
+        },
+      }
+    }
+  }
+
+  fn diff_nodes(&mut self, old: &Node, new: &Node) {
+    match (&old.node_type, &new.node_type) {
+      (NodeType::Directory(), NodeType::Directory()) => {
+        self.diff_recursive(old, new);
+      },
+      (NodeType::File(), NodeType::File()) => {
+        if let (Some(a), Some(b)) = (old.meta.size, new.meta.size) {
+          if a != b {
+            self.entries.push(DiffEntry {
+              kind: DiffKind::Changed,
+              path: new.path.clone(),
+              node_type: new.node_type.clone(),
+            });
+          }
+        }
+      },
+      _ => {
+        self.entries.push(DiffEntry {
+          kind: DiffKind::Changed,
+          path: new.path.clone(),
+          node_type: new.node_type.clone(),
+        });
+        if let NodeType::Directory() = old.node_type {
+          for child in TreeDiff::sorted(&old.children) {
+            self.push_all(DiffKind::Removed, child);
+          }
+        }
+        for child in TreeDiff::sorted(&new.children) {
+          self.push_all(DiffKind::Added, child);
+        }
+      },
+    }
+  }
+}
+
+impl Tree {
+  /// Compares this tree to `other`, without looking at file contents.
+  ///
+  /// Entries only in `other` are added, entries only in this tree are
+  /// removed, and entries whose type changed or files whose known size
+  /// changed are changed. Mounted trees are not compared.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut old = Tree::new();
+  /// old.insert(Node::from_path("src/").unwrap());
+  /// old.insert(Node::from_path("src/main.rs").unwrap());
+  ///
+  /// let mut new = Tree::new();
+  /// new.insert(Node::from_path("src/").unwrap());
+  /// new.insert(Node::from_path("src/lib.rs").unwrap());
+  ///
+  /// let mut out = Vec::new();
+  /// old.diff(&new).write_to(&mut out).unwrap();
+  /// assert_eq!(String::from_utf8(out).unwrap(), "+ src/lib.rs\n- src/main.rs\n");
+  /// ```
+  pub fn diff(&self, other: &Tree) -> TreeDiff {
+    let mut diff = TreeDiff::default();
+    match (&self.root, &other.root) {
+      (Some(old), Some(new)) => diff.diff_recursive(old, new),
+      (Some(old), None) => {
+        for child in TreeDiff::sorted(&old.children) {
+          diff.push_all(DiffKind::Removed, child);
+        }
+      },
+      (None, Some(new)) => {
+        for child in TreeDiff::sorted(&new.children) {
+          diff.push_all(DiffKind::Added, child);
+        }
+      },
+      (None, None) => {},
+    }
+    diff
+  }
+}
@@ -0,0 +1,69 @@
+//! Writing trees as Graphviz DOT digraphs.
+//!
+//! ```text
+//! digraph tree {
+//!   n0 [label="/srv/data", shape=folder];
+//!   n1 [label="main.rs", shape=note];
+//!   n0 -> n1;
+//! }
+//! ```
+
+use crate::node::*;
+
+/// Escapes the characters of `text` that are special in DOT strings.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '"' => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      '\n' => escaped.push_str("\\n"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+impl Tree {
+  /// Gets the tree, including mounted trees, as a DOT digraph whose
+  /// directories are folders and files are notes, each with an edge from
+  /// its parent.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  ///
+  /// let dot = tree.to_dot();
+  /// assert!(dot.starts_with("digraph tree {\n"));
+  /// assert!(dot.contains("  n1 [label=\"src\", shape=folder];\n"));
+  /// assert!(dot.contains("  n2 [label=\"main.rs\", shape=note];\n  n1 -> n2;\n"));
+  /// ```
+  pub fn to_dot(&self) -> String {
+    let mut dot = String::from("digraph tree {\n");
+    // The ids of the directories leading to the current node.
+    let mut ids: Vec<usize> = Vec::new();
+    let mut next = 0;
+    self.walk(|_, node, offset| {
+      let depth = (node.depth + offset) as usize;
+      let shape = match node.node_type {
+        NodeType::File() => "note",
+        NodeType::Directory() => "folder",
+      };
+      dot.push_str(&format!("  n{} [label=\"{}\", shape={}];\n", next, escape(&node.name), shape));
+      ids.truncate(depth);
+      if let Some(parent) = ids.last() {
+        dot.push_str(&format!("  n{} -> n{};\n", parent, next));
+      }
+      ids.push(next);
+      next += 1;
+      true
+    });
+    dot.push_str("}\n");
+    dot
+  }
+}
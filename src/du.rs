@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use crate::date::{format_date, DateStyle};
+use crate::node::*;
+use crate::render::NameStyle;
+use crate::size::format_size;
+
+/// Represents the disk usage of a directory, or of a file in the reports of
+/// the largest entries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiskUsage {
+  /// The path of the entry, `.` for the root. The paths of the directories
+  /// end with a slash in the reports of the largest entries.
+  pub path: String,
+  /// The total size in bytes of the directory and of everything below it.
+  pub size: u64,
+  /// The latest modification time of the directory and of everything below
+  /// it, if any is known.
+  pub modified: Option<SystemTime>,
+}
+
+/// Represents the disk usage of every directory of a tree.
+#[derive(Clone, Debug, Default)]
+pub struct DiskUsageReport {
+  /// The directories in post-order, like `du`, so the root comes last.
+  pub entries: Vec<DiskUsage>,
+  /// How the modification times are rendered, `None` to leave them out.
+  pub date_style: Option<DateStyle>,
+  /// How the paths are written, see `RenderOptions::names`.
+  pub names: NameStyle,
+}
+
+impl DiskUsageReport {
+  /// Writes one line per directory, made of its size and its path
+  /// separated by a tab. With a date style, the latest modification time
+  /// is written between them like `du --time`.
+  ///
+  /// # Output
+  ///
+  /// ```text
+  /// 1.5K    src
+  /// 12      docs
+  /// 1.5K    .
+  /// ```
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let now = SystemTime::now();
+    for entry in &self.entries {
+      match (self.date_style, entry.modified) {
+        (Some(style), Some(modified)) => {
+          writeln!(out, "{}\t{}\t{}", format_size(entry.size), format_date(modified, style, now), self.names.apply(&entry.path))?;
+        },
+        (Some(_), None) => {
+          writeln!(out, "{}\t-\t{}", format_size(entry.size), self.names.apply(&entry.path))?;
+        },
+        (None, _) => {
+          writeln!(out, "{}\t{}", format_size(entry.size), self.names.apply(&entry.path))?;
+        },
+      }
+    }
+    Ok(())
+  }
+
+  /// Displays the disk usage.
+  pub fn display(&self) {
+    self.write_to(&mut io::stdout()).expect("Could not write to stdout");
+  }
+}
+
+/// The state of a disk usage computation.
+struct Usage {
+  /// Whether every link to a file is counted.
+  count_links: bool,
+  /// The device and inode numbers of the multi-linked files seen so far.
+  seen: HashSet<(u64, u64)>,
+  /// Whether the files are pushed along with the directories.
+  files: bool,
+  entries: Vec<DiskUsage>,
+}
+
+impl Usage {
+  /// Gets the size of a file, zero if one of its other links was already
+  /// counted.
+  fn file_size(&mut self, node: &Node) -> u64 {
+    let size = node.meta.size.unwrap_or(0);
+    if self.count_links || node.meta.links.unwrap_or(1) < 2 {
+      return size;
+    }
+    match node.meta.inode {
+      Some(inode) if !self.seen.insert(inode) => 0,
+      _ => size,
+    }
+  }
+
+  /// Computes the size and latest modification time of `node`, pushing it
+  /// and its descendant directories, `prefix` being the mount path of
+  /// `tree` in the displayed tree.
+  fn node_size(&mut self, tree: &Tree, node: &Node, prefix: &str) -> (u64, Option<SystemTime>) {
+    enum Work<'a> {
+      Enter(&'a Tree, &'a Node, String),
+      /// Sums up a directory, the sizes of its children being the last
+      /// ones computed.
+      Exit(&'a Node, String, usize),
+    }
+
+    // The sizes and latest modification times of the entries done whose
+    // parent is not done yet.
+    let mut sizes: Vec<(u64, Option<SystemTime>)> = Vec::new();
+    let mut stack = vec![Work::Enter(tree, node, prefix.to_string())];
+    while let Some(work) = stack.pop() {
+      match work {
+        Work::Enter(_, node, prefix) if node.node_type == NodeType::File() => {
+          let size = self.file_size(node);
+          if self.files {
+            self.entries.push(DiskUsage { path: Usage::join(&prefix, &node.path), size, modified: node.meta.modified });
+          }
+          sizes.push((size, node.meta.modified));
+        },
+        Work::Enter(tree, node, prefix) => {
+          let mut children: Vec<Work> = node.children.iter()
+            .map(|child| Work::Enter(tree, child, prefix.clone()))
+            .collect();
+          if let Some(mounted) = tree.mounts.get(&node.path) {
+            let prefix = Usage::join(&prefix, &node.path);
+            if let Some(root) = &mounted.root {
+              children.extend(root.children.iter().map(|child| Work::Enter(mounted, child, prefix.clone())));
+            }
+          }
+          stack.push(Work::Exit(node, prefix, children.len()));
+          stack.extend(children.into_iter().rev());
+        },
+        Work::Exit(node, prefix, count) => {
+          let mut size = node.meta.size.unwrap_or(0);
+          let mut modified = node.meta.modified;
+          for (child_size, child_modified) in sizes.drain(sizes.len() - count..) {
+            size += child_size;
+            modified = modified.max(child_modified);
+          }
+
+          let path = match (prefix.as_str(), node.depth, self.files) {
+            ("", 0, _) => ".".to_string(),
+            (_, _, true) => format!("{}/", Usage::join(&prefix, &node.path)),
+            _ => Usage::join(&prefix, &node.path),
+          };
+          self.entries.push(DiskUsage { path, size, modified });
+          sizes.push((size, modified));
+        },
+      }
+    }
+    sizes.pop().unwrap_or_default()
+  }
+
+  fn join(prefix: &str, path: &str) -> String {
+    match (prefix, path) {
+      (prefix, "/") => prefix.to_string(),
+      ("", path) => path.to_string(),
+      (prefix, path) => format!("{}/{}", prefix, path),
+    }
+  }
+}
+
+impl Tree {
+  /// Computes the disk usage of every directory, including mounted trees,
+  /// from the apparent sizes of the nodes.
+  ///
+  /// Like `du`, a file with several hard links in the tree is only counted
+  /// the first time it is met, unless `count_links` is set.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut meta = NodeMeta::default();
+  /// meta.size = Some(100);
+  /// meta.inode = Some((1, 42));
+  /// meta.links = Some(2);
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add("a/data.bin", NodeType::File(), meta.clone());
+  /// source.add("b/data.bin", NodeType::File(), meta);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  ///
+  /// let root = |links| tree.disk_usage(links).entries.last().unwrap().size;
+  /// assert_eq!(root(false), 100);
+  /// assert_eq!(root(true), 200);
+  /// ```
+  ///
+  /// Deep trees are summed up without recursion:
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let data = || NodeData { default_path: "d".to_string(), length: 1 };
+  /// let mut node = Node::new_file(data(), 100_001, "d".to_string(), "d".to_string());
+  /// node.meta.size = Some(7);
+  /// for depth in (1..=100_000).rev() {
+  ///   let mut directory = Node::new_directory(data(), depth, "d".to_string(), "d".to_string());
+  ///   directory.children.push(node);
+  ///   node = directory;
+  /// }
+  /// let mut tree = Tree::with_root(".", None);
+  /// tree.root.as_mut().unwrap().children.push(node);
+  ///
+  /// let report = tree.disk_usage(false);
+  /// assert_eq!(report.entries.len(), 100_001);
+  /// assert_eq!(report.entries.last().unwrap().size, 7);
+  /// assert_eq!(tree.largest_entries(1, false).entries[0].size, 7);
+  /// ```
+  pub fn disk_usage(&self, count_links: bool) -> DiskUsageReport {
+    let mut usage = Usage {
+      count_links,
+      seen: HashSet::new(),
+      files: false,
+      entries: Vec::new(),
+    };
+    if let Some(root) = &self.root {
+      usage.node_size(self, root, "");
+    }
+    DiskUsageReport {
+      entries: usage.entries,
+      date_style: None,
+      names: NameStyle::default(),
+    }
+  }
+
+  /// Gets the `count` largest files and directories below the root,
+  /// including mounted trees, the largest first, like `du -a | sort -rh |
+  /// head`. Directories have the size of their content, counted like
+  /// `Tree::disk_usage`, and their paths end with a slash.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add_file("logs/app.log", 500);
+  /// source.add_file("logs/old.log", 300);
+  /// source.add_file("notes.txt", 600);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  ///
+  /// let paths: Vec<String> = tree.largest_entries(2, false).entries.into_iter().map(|entry| entry.path).collect();
+  /// assert_eq!(paths, ["logs/", "notes.txt"]);
+  /// ```
+  pub fn largest_entries(&self, count: usize, count_links: bool) -> DiskUsageReport {
+    let mut usage = Usage {
+      count_links,
+      seen: HashSet::new(),
+      files: true,
+      entries: Vec::new(),
+    };
+    if let Some(root) = &self.root {
+      usage.node_size(self, root, "");
+    }
+    // The root is last, and the largest of all.
+    usage.entries.pop();
+    usage.entries.sort_by_key(|entry| std::cmp::Reverse(entry.size));
+    usage.entries.truncate(count);
+    DiskUsageReport {
+      entries: usage.entries,
+      date_style: None,
+      names: NameStyle::default(),
+    }
+  }
+}
@@ -0,0 +1,184 @@
+//! Finding the files of a tree with the same content, from their checksums.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use crate::node::*;
+use crate::render::NameStyle;
+use crate::size::format_size;
+use crate::unicode::to_nfc;
+
+/// Represents files with the same content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateSet {
+  /// The checksum of the content of the files.
+  pub checksum: String,
+  /// The size of one of the files.
+  pub size: u64,
+  /// The paths of the files relative to the root, in pre-order.
+  pub paths: Vec<String>,
+}
+
+impl DuplicateSet {
+  /// Gets the space taken by the copies other than the first one.
+  pub fn wasted(&self) -> u64 {
+    self.size * (self.paths.len() as u64 - 1)
+  }
+}
+
+/// Represents the files of a tree with the same content, in sets.
+#[derive(Clone, Debug, Default)]
+pub struct DuplicateReport {
+  /// The sets of duplicates, the ones wasting the most space first.
+  pub sets: Vec<DuplicateSet>,
+  /// How the paths are written, see `RenderOptions::names`.
+  pub names: NameStyle,
+}
+
+impl DuplicateReport {
+  /// Checks whether every file has its own content.
+  pub fn is_empty(&self) -> bool {
+    self.sets.is_empty()
+  }
+
+  /// Gets the space taken by all the copies other than the first one of
+  /// each set.
+  pub fn wasted(&self) -> u64 {
+    self.sets.iter().map(DuplicateSet::wasted).sum()
+  }
+
+  /// Writes every set of duplicates, followed by one line per file, and the
+  /// total wasted space.
+  ///
+  /// # Output
+  ///
+  /// ```text
+  /// 3 files of 1.5K, 3.0K wasted (sha256:9f86d081884c...)
+  ///   src/logo.png
+  ///   docs/logo.png
+  ///   backup/logo.png
+  ///
+  /// 1 duplicate set, 3.0K wasted
+  /// ```
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    for set in &self.sets {
+      let checksum = set.checksum.get(..19).unwrap_or(&set.checksum);
+      writeln!(out, "{} files of {}, {} wasted ({}...)", set.paths.len(), format_size(set.size), format_size(set.wasted()), checksum)?;
+      for path in &set.paths {
+        writeln!(out, "  {}", self.names.apply(path))?;
+      }
+      writeln!(out)?;
+    }
+    let plural = if self.sets.len() == 1 { "" } else { "s" };
+    writeln!(out, "{} duplicate set{}, {} wasted", self.sets.len(), plural, format_size(self.wasted()))
+  }
+}
+
+impl Tree {
+  /// Groups the files of the tree, including mounted trees, with the same
+  /// checksum, see `Tree::compute_checksums`. Empty files and the other
+  /// hard links to a file already in a set are left out, as they take no
+  /// space.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for (path, checksum) in [("a.txt", "sha256:01"), ("docs/", ""), ("docs/a.txt", "sha256:01"), ("b.txt", "sha256:02")] {
+  ///   let mut node = Node::from_path(path).unwrap();
+  ///   node.meta.size = Some(100);
+  ///   node.meta.checksum = (!checksum.is_empty()).then(|| checksum.to_string());
+  ///   tree.insert(node);
+  /// }
+  ///
+  /// let report = tree.find_duplicates();
+  /// assert_eq!(report.sets.len(), 1);
+  /// assert_eq!(report.sets[0].paths, ["a.txt", "docs/a.txt"]);
+  /// assert_eq!(report.wasted(), 100);
+  /// ```
+  pub fn find_duplicates(&self) -> DuplicateReport {
+    let mut sets: Vec<DuplicateSet> = Vec::new();
+    let mut by_checksum: HashMap<&str, usize> = HashMap::new();
+    let mut inodes: HashSet<(u64, u64)> = HashSet::new();
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+      }
+      let (Some(checksum), Some(size)) = (&node.meta.checksum, node.meta.size) else {
+        return true;
+      };
+      if node.node_type != NodeType::File() || size == 0 || node.meta.inode.is_some_and(|inode| !inodes.insert(inode)) {
+        return true;
+      }
+      let path = names.join("/");
+      match by_checksum.get(checksum.as_str()) {
+        Some(&i) => sets[i].paths.push(path),
+        None => {
+          by_checksum.insert(checksum, sets.len());
+          sets.push(DuplicateSet { checksum: checksum.clone(), size, paths: vec![path] });
+        },
+      }
+      true
+    });
+    sets.retain(|set| set.paths.len() > 1);
+    sets.sort_by_key(|set| std::cmp::Reverse(set.wasted()));
+    DuplicateReport { sets, names: NameStyle::default() }
+  }
+
+  /// Groups the entries of every directory, including mounted trees, whose
+  /// names are the same once composed, see `unicode::to_nfc`, like the
+  /// copies of a file made on macOS and on Linux.
+  ///
+  /// # Returns
+  ///
+  /// The paths of the entries relative to the root, grouped in pre-order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["docs/", "docs/cafe\u{301}.txt", "docs/café.txt", "docs/cafe.txt"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  /// assert_eq!(tree.find_duplicate_names(), [["docs/cafe\u{301}.txt", "docs/café.txt"]]);
+  /// ```
+  pub fn find_duplicate_names(&self) -> Vec<Vec<String>> {
+    let mut groups = Vec::new();
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    self.walk(|tree, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+      }
+      let mounted = tree.mounts.get(&node.path).and_then(|mounted| mounted.root.as_ref());
+      let mut by_name: HashMap<Cow<str>, Vec<&str>> = HashMap::new();
+      let mut order = Vec::new();
+      for child in node.children.iter().chain(mounted.into_iter().flat_map(|root| &root.children)) {
+        let name = to_nfc(&child.name);
+        if !by_name.contains_key(&name) {
+          order.push(name.clone());
+        }
+        by_name.entry(name).or_default().push(&child.name);
+      }
+      for name in order {
+        let same = &by_name[&name];
+        if same.len() > 1 {
+          groups.push(same.iter().map(|child| names.iter().chain([child]).copied().collect::<Vec<&str>>().join("/")).collect());
+        }
+      }
+      true
+    });
+    groups
+  }
+}
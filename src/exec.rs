@@ -0,0 +1,135 @@
+//! Running a command for every file of a tree, like `find -exec`, once the
+//! filters are applied.
+
+use std::io;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::node::*;
+
+/// Splits a command line into words like a shell, with single quotes,
+/// double quotes and backslashes, without expanding anything.
+///
+/// # Returns
+///
+/// The words, or a description of the problem if a quote is not closed or
+/// there are none.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::exec::parse_command;
+///
+/// assert_eq!(parse_command("wc -l {}").unwrap(), ["wc", "-l", "{}"]);
+/// assert_eq!(parse_command(r#"sh -c 'head -1 "$0"' {}"#).unwrap(), ["sh", "-c", "head -1 \"$0\"", "{}"]);
+/// assert_eq!(parse_command("echo a\\ b").unwrap(), ["echo", "a b"]);
+/// assert!(parse_command("echo 'a").is_err());
+/// ```
+pub fn parse_command(line: &str) -> Result<Vec<String>, String> {
+  let mut words = Vec::new();
+  let mut word: Option<String> = None;
+  let mut chars = line.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      c if c.is_whitespace() => words.extend(word.take()),
+      '\'' => {
+        let word = word.get_or_insert_with(String::new);
+        loop {
+          match chars.next() {
+            Some('\'') => break,
+            Some(c) => word.push(c),
+            None => return Err(format!("{}: unclosed quote", line)),
+          }
+        }
+      },
+      '"' => {
+        let word = word.get_or_insert_with(String::new);
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+              Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+              Some(c) => word.extend(['\\', c]),
+              None => return Err(format!("{}: unclosed quote", line)),
+            },
+            Some(c) => word.push(c),
+            None => return Err(format!("{}: unclosed quote", line)),
+          }
+        }
+      },
+      '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+      c => word.get_or_insert_with(String::new).push(c),
+    }
+  }
+  words.extend(word);
+  match words.is_empty() {
+    true => Err("empty command".to_string()),
+    false => Ok(words),
+  }
+}
+
+/// Gets the words of the command run for the file at `path`: the words of
+/// `command` with every `{}` replaced by the path, or followed by the path
+/// if none holds `{}`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::exec::command_for;
+///
+/// let words = ["cp".to_string(), "{}".to_string(), "{}.bak".to_string()];
+/// assert_eq!(command_for(&words, "a.txt"), ["cp", "a.txt", "a.txt.bak"]);
+/// assert_eq!(command_for(&["wc".to_string()], "a.txt"), ["wc", "a.txt"]);
+/// ```
+pub fn command_for(command: &[String], path: &str) -> Vec<String> {
+  match command.iter().any(|word| word.contains("{}")) {
+    true => command.iter().map(|word| word.replace("{}", path)).collect(),
+    false => command.iter().cloned().chain([path.to_string()]).collect(),
+  }
+}
+
+/// Represents the run of a command for a file.
+#[derive(Debug)]
+pub struct ExecResult {
+  /// The path of the file, see `Tree::leaf_paths`.
+  pub path: String,
+  /// What the command printed and its exit status, or the error starting
+  /// it.
+  pub output: io::Result<Output>,
+}
+
+impl Tree {
+  /// Runs the command `command`, whose words are those of
+  /// `parse_command`, for every file of the tree, including mounted trees,
+  /// see `command_for`. At most `jobs` commands run at a time, without
+  /// stdin and with their output captured, and `done` is called with the
+  /// result of every one as soon as it ends, in the order of the files
+  /// with a single job.
+  pub fn exec_files<F: FnMut(ExecResult)>(&self, command: &[String], jobs: usize, mut done: F) {
+    let paths = self.leaf_paths();
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+      for _ in 0..jobs.clamp(1, paths.len().max(1)) {
+        let sender = sender.clone();
+        let (paths, next) = (&paths, &next);
+        scope.spawn(move || {
+          while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+            let words = command_for(command, path);
+            let output = Command::new(&words[0]).args(&words[1..]).stdin(Stdio::null()).output();
+            if sender.send(ExecResult { path: path.clone(), output }).is_err() {
+              break;
+            }
+          }
+        });
+      }
+      // The results end with the last worker.
+      drop(sender);
+      for result in receiver {
+        done(result);
+      }
+    });
+  }
+}
@@ -0,0 +1,100 @@
+//! Counting the files of a tree by extension, to see what it is made of.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::node::*;
+use crate::size::format_size;
+
+/// Represents the files of a tree with the same extension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionStats {
+  /// The extension of the files in lowercase, without the dot, empty for
+  /// the files without one.
+  pub extension: String,
+  /// The number of files.
+  pub files: u64,
+  /// The total size of the files in bytes.
+  pub size: u64,
+}
+
+/// Represents the files of a tree grouped by extension.
+#[derive(Clone, Debug, Default)]
+pub struct ExtensionReport {
+  /// The extensions, the ones taking the most space first.
+  pub extensions: Vec<ExtensionStats>,
+}
+
+impl ExtensionReport {
+  /// Writes a table with one row per extension, with its number of files
+  /// and their total size.
+  ///
+  /// # Output
+  ///
+  /// ```text
+  /// EXTENSION  FILES   SIZE
+  /// rs            42  12.5K
+  /// md             3   4.0K
+  /// (none)         1     35
+  /// ```
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let rows: Vec<(&str, String, String)> = self.extensions.iter().map(|stats| {
+      let extension = if stats.extension.is_empty() { "(none)" } else { &stats.extension };
+      (extension, stats.files.to_string(), format_size(stats.size))
+    }).collect();
+    let width = |header: &str, column: &dyn Fn(&(&str, String, String)) -> usize| {
+      rows.iter().map(column).chain([header.len()]).max().unwrap_or(0)
+    };
+    let extensions = width("EXTENSION", &|row| row.0.chars().count());
+    let files = width("FILES", &|row| row.1.len());
+    let size = width("SIZE", &|row| row.2.len());
+    writeln!(out, "{:<extensions$}  {:>files$}  {:>size$}", "EXTENSION", "FILES", "SIZE")?;
+    for (extension, count, total) in &rows {
+      writeln!(out, "{:<extensions$}  {:>files$}  {:>size$}", extension, count, total)?;
+    }
+    Ok(())
+  }
+}
+
+impl Tree {
+  /// Groups the files of the tree, including mounted trees, by extension,
+  /// ignoring its case. Dot files like `.bashrc` have no extension.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add_file("src/main.rs", 300);
+  /// source.add_file("src/lib.RS", 200);
+  /// source.add_file("README.md", 900);
+  /// source.add_file("Makefile", 50);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  ///
+  /// let report = tree.extension_stats();
+  /// let rows: Vec<(&str, u64, u64)> = report.extensions.iter()
+  ///   .map(|stats| (stats.extension.as_str(), stats.files, stats.size))
+  ///   .collect();
+  /// assert_eq!(rows, [("md", 1, 900), ("rs", 2, 500), ("", 1, 50)]);
+  /// ```
+  pub fn extension_stats(&self) -> ExtensionReport {
+    let mut by_extension: HashMap<String, ExtensionStats> = HashMap::new();
+    self.walk(|_, node, _| {
+      if node.node_type == NodeType::File() {
+        let extension = match node.name.rsplit_once('.') {
+          Some((stem, extension)) if !stem.is_empty() => extension.to_lowercase(),
+          _ => String::new(),
+        };
+        let stats = by_extension.entry(extension.clone()).or_insert(ExtensionStats { extension, files: 0, size: 0 });
+        stats.files += 1;
+        stats.size += node.meta.size.unwrap_or(0);
+      }
+      true
+    });
+    let mut extensions: Vec<ExtensionStats> = by_extension.into_values().collect();
+    extensions.sort_by(|a, b| b.size.cmp(&a.size).then(b.files.cmp(&a.files)).then(a.extension.cmp(&b.extension)));
+    ExtensionReport { extensions }
+  }
+}
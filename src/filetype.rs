@@ -0,0 +1,120 @@
+//! Telling the kinds of files apart, from their extension or, when it is
+//! not known, from the first bytes of their content.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Represents a kind of file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+  Image,
+  Video,
+  Text,
+  /// Any file which is not text, images and videos included.
+  Binary,
+}
+
+const IMAGES: [&str; 14] = ["avif", "bmp", "gif", "heic", "ico", "jpeg", "jpg", "png", "psd", "raw", "svg", "tif", "tiff", "webp"];
+const VIDEOS: [&str; 12] = ["3gp", "avi", "flv", "m4v", "mkv", "mov", "mp4", "mpeg", "mpg", "ogv", "webm", "wmv"];
+const TEXTS: [&str; 44] = [
+  "c", "cfg", "conf", "cpp", "cs", "css", "csv", "go", "h", "hpp", "html", "ini", "java", "js", "json", "jsx", "kt",
+  "log", "lua", "md", "php", "pl", "py", "rb", "rs", "rst", "sh", "sql", "swift", "tex", "toml", "ts", "tsv", "tsx",
+  "txt", "xml", "yaml", "yml", "zsh", "bash", "fish", "lock", "gitignore", "treeignore",
+];
+const BINARIES: [&str; 22] = [
+  "7z", "a", "bin", "bz2", "class", "dll", "dylib", "exe", "gz", "iso", "jar", "mp3", "o", "pdf", "so", "tar", "wasm",
+  "wav", "xz", "zip", "zst", "flac",
+];
+
+impl FileKind {
+  /// Parses the name of a kind: `image`, `video`, `text` or `binary`.
+  pub fn parse(name: &str) -> Result<FileKind, String> {
+    match name {
+      "image" => Ok(FileKind::Image),
+      "video" => Ok(FileKind::Video),
+      "text" => Ok(FileKind::Text),
+      "binary" => Ok(FileKind::Binary),
+      _ => Err(format!("unknown file type '{}', expected image, video, text or binary", name)),
+    }
+  }
+
+  /// Checks whether a file of the kind `kind` is of this kind, images and
+  /// videos being binary files too.
+  pub fn includes(&self, kind: FileKind) -> bool {
+    *self == kind || (*self == FileKind::Binary && kind != FileKind::Text)
+  }
+
+  /// Gets the kind of the files named `name` from their extension, `None`
+  /// if it is not known.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::filetype::FileKind;
+  ///
+  /// assert_eq!(FileKind::from_name("logo.PNG"), Some(FileKind::Image));
+  /// assert_eq!(FileKind::from_name("main.rs"), Some(FileKind::Text));
+  /// assert_eq!(FileKind::from_name("Makefile"), None);
+  /// ```
+  pub fn from_name(name: &str) -> Option<FileKind> {
+    let (_, extension) = name.rsplit_once('.')?;
+    let extension = extension.to_ascii_lowercase();
+    let known = |extensions: &[&str]| extensions.contains(&extension.as_str());
+    match () {
+      _ if known(&IMAGES) => Some(FileKind::Image),
+      _ if known(&VIDEOS) => Some(FileKind::Video),
+      _ if known(&TEXTS) => Some(FileKind::Text),
+      _ if known(&BINARIES) => Some(FileKind::Binary),
+      _ => None,
+    }
+  }
+
+  /// Gets the kind of a file from the first bytes of its content: the
+  /// signatures of the common image and video formats, then text if they
+  /// are UTF-8 without NUL bytes.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::filetype::FileKind;
+  ///
+  /// assert_eq!(FileKind::from_content(b"\x89PNG\r\n\x1a\n...."), FileKind::Image);
+  /// assert_eq!(FileKind::from_content(b"\0\0\0\x18ftypmp42"), FileKind::Video);
+  /// assert_eq!(FileKind::from_content("#!/bin/sh\necho héllo\n".as_bytes()), FileKind::Text);
+  /// assert_eq!(FileKind::from_content(b"\x7fELF\x02\x01\x01\0"), FileKind::Binary);
+  /// ```
+  pub fn from_content(bytes: &[u8]) -> FileKind {
+    let riff = |format: &[u8]| bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(format);
+    if bytes.starts_with(b"\x89PNG") || bytes.starts_with(b"\xff\xd8\xff") || bytes.starts_with(b"GIF8")
+      || bytes.starts_with(b"BM") || riff(b"WEBP") || bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+      return FileKind::Image;
+    }
+    if bytes.get(4..8) == Some(b"ftyp") || bytes.starts_with(b"\x1a\x45\xdf\xa3") || riff(b"AVI ") {
+      return FileKind::Video;
+    }
+    // A character may be cut at the end of the bytes read.
+    let text = match std::str::from_utf8(bytes) {
+      Ok(_) => true,
+      Err(e) => e.error_len().is_none(),
+    };
+    match text && !bytes.contains(&0) {
+      true => FileKind::Text,
+      false => FileKind::Binary,
+    }
+  }
+
+  /// Gets the kind of the file named `name` from its extension or, when it
+  /// is not known, from the content of the file at `path`. Files which
+  /// cannot be read are binary.
+  pub fn detect(name: &str, path: Option<&Path>) -> FileKind {
+    if let Some(kind) = FileKind::from_name(name) {
+      return kind;
+    }
+    let mut bytes = Vec::new();
+    match path.map(|path| File::open(path).and_then(|file| file.take(8192).read_to_end(&mut bytes))) {
+      Some(Ok(_)) => FileKind::from_content(&bytes),
+      _ => FileKind::Binary,
+    }
+  }
+}
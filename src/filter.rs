@@ -0,0 +1,362 @@
+//! Include and exclude patterns selecting the part of a tree to output.
+//!
+//! Filters are applied while a directory is scanned, see
+//! `ScanOptions::filter`, so the excluded directories are not read and the
+//! dropped entries are not kept. The trees loaded whole are filtered into a
+//! copy with `Tree::filtered`, like a scan both displayed and exported with
+//! different filters. Patterns have the syntax of `.treeignore` lines and
+//! are matched against the paths of the output tree, mounted trees
+//! included.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::filetype::FileKind;
+use crate::ignore::IgnoreRules;
+use crate::node::*;
+use crate::regex::Regex;
+use crate::unicode::to_nfc;
+
+/// Represents the entries to keep when outputting a tree.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+  include: IgnoreRules,
+  exclude: IgnoreRules,
+  prune: bool,
+  min_size: Option<u64>,
+  max_size: Option<u64>,
+  newer: Option<SystemTime>,
+  older: Option<SystemTime>,
+  kinds: Vec<FileKind>,
+  broken_only: bool,
+  nfc: bool,
+  regexes: Vec<Regex>,
+}
+
+impl Filter {
+  pub fn new() -> Filter {
+    Filter::default()
+  }
+
+  /// Only keeps the entries matching `pattern`, or one of the other include
+  /// patterns, and the directories leading to them. The patterns are
+  /// composed, see `Filter::normalize`.
+  pub fn include(&mut self, pattern: &str) {
+    self.include.add(&to_nfc(pattern));
+  }
+
+  /// Only keeps the entries whose name or path matches `regex`, like an
+  /// include pattern.
+  pub fn grep_name(&mut self, regex: Regex) {
+    self.regexes.push(regex);
+  }
+
+  /// Drops the entries matching `pattern` and everything below them.
+  pub fn exclude(&mut self, pattern: &str) {
+    self.exclude.add(&to_nfc(pattern));
+  }
+
+  /// Matches the patterns whatever the case, see
+  /// `IgnoreRules::ignore_case`.
+  pub fn ignore_case(&mut self) {
+    self.include.ignore_case();
+    self.exclude.ignore_case();
+  }
+
+  /// Matches the patterns against the composed paths, see
+  /// `unicode::to_nfc`, so the decomposed names of macOS match.
+  pub fn normalize(&mut self) {
+    self.nfc = true;
+  }
+
+  /// Drops the directories left without any file below them once the
+  /// patterns are applied.
+  pub fn prune(&mut self) {
+    self.prune = true;
+  }
+
+  /// Only keeps the files of at least `size` bytes, and the directories
+  /// leading to them.
+  pub fn min_size(&mut self, size: u64) {
+    self.min_size = Some(size);
+  }
+
+  /// Only keeps the files of less than `size` bytes, and the directories
+  /// leading to them.
+  pub fn max_size(&mut self, size: u64) {
+    self.max_size = Some(size);
+  }
+
+  /// Only keeps the files modified after `time`, and the directories
+  /// leading to them.
+  pub fn newer(&mut self, time: SystemTime) {
+    self.newer = Some(time);
+  }
+
+  /// Only keeps the files modified before `time`, and the directories
+  /// leading to them.
+  pub fn older(&mut self, time: SystemTime) {
+    self.older = Some(time);
+  }
+
+  /// Only keeps the files of the kind `kind`, or of one of the other kinds
+  /// given, and the directories leading to them. The kinds of the files
+  /// with an unknown extension are told from their content.
+  pub fn file_kind(&mut self, kind: FileKind) {
+    self.kinds.push(kind);
+  }
+
+  /// Only keeps the broken symbolic links, and the directories leading to
+  /// them.
+  pub fn broken_only(&mut self) {
+    self.broken_only = true;
+  }
+
+  /// Checks whether the filter keeps every entry.
+  pub fn is_empty(&self) -> bool {
+    self.include.is_empty() && self.exclude.is_empty() && !self.prune && !self.has_meta()
+      && self.regexes.is_empty()
+  }
+
+  /// Checks whether every entry is included, without include patterns and
+  /// regular expressions.
+  fn includes_all(&self) -> bool {
+    self.include.is_empty() && self.regexes.is_empty()
+  }
+
+  /// Gets `path` as the patterns are matched against it, composed with
+  /// `Filter::normalize`.
+  fn matched_path<'a>(&self, path: &'a str) -> Cow<'a, str> {
+    match self.nfc {
+      true => to_nfc(path),
+      false => Cow::Borrowed(path),
+    }
+  }
+
+  /// Checks whether the entry at `path` matches an exclude pattern, and is
+  /// dropped with everything below it.
+  pub fn excludes(&self, path: &str, is_dir: bool) -> bool {
+    self.exclude.is_ignored(&self.matched_path(path), is_dir)
+  }
+
+  /// Checks whether the entry named `name` at `path` matches an include
+  /// pattern or a regular expression, every entry being included without
+  /// them. The entries below an included directory are included too.
+  pub fn includes(&self, name: &str, path: &str, is_dir: bool) -> bool {
+    self.includes_all() || self.is_included(name, &self.matched_path(path), is_dir)
+  }
+
+  /// Checks whether the entry named `name` at `path` matches an include
+  /// pattern or a regular expression.
+  fn is_included(&self, name: &str, path: &str, is_dir: bool) -> bool {
+    self.include.is_ignored(path, is_dir) || self.regexes.iter().any(|regex| regex.is_match(name) || regex.is_match(path))
+  }
+
+  /// Checks whether the file named `name` is selected by its size, age,
+  /// kind and link, the unknown ones never being. The kind of a file with
+  /// an unknown extension is told from the content of `file`, if given.
+  pub fn selects_file(&self, name: &str, meta: &NodeMeta, file: Option<&Path>) -> bool {
+    self.matches_meta(meta) && self.matches_kind(name, file)
+  }
+
+  /// Checks whether a directory without anything kept below it is kept,
+  /// `included` telling whether it or one of its ancestors is included.
+  pub fn keeps_empty_directory(&self, included: bool) -> bool {
+    included && !self.prune && !self.has_meta()
+  }
+
+  /// Checks whether the entry at `path` is kept on its own, its ancestors
+  /// being matched too, for the trees built one path at a time. The
+  /// directories leading to the kept entries are kept as well.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::filter::Filter;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut filter = Filter::new();
+  /// filter.include("src/");
+  /// filter.exclude("*.o");
+  /// let meta = NodeMeta::default();
+  /// assert!(filter.keeps_path("src/main.rs", false, &meta, None));
+  /// assert!(!filter.keeps_path("src/main.o", false, &meta, None));
+  /// assert!(!filter.keeps_path("docs/index.md", false, &meta, None));
+  ///
+  /// filter.exclude("target/");
+  /// assert!(!filter.keeps_path("src/target/debug/app", false, &meta, None));
+  /// ```
+  pub fn keeps_path(&self, path: &str, is_dir: bool, meta: &NodeMeta, file: Option<&Path>) -> bool {
+    let path = path.trim_matches('/');
+    let mut included = false;
+    let ancestors = path.match_indices('/').map(|(end, _)| (&path[..end], true));
+    for (entry, entry_is_dir) in ancestors.chain([(path, is_dir)]) {
+      if self.excludes(entry, entry_is_dir) {
+        return false;
+      }
+      let name = entry.rsplit('/').next().unwrap_or(entry);
+      included = included || self.includes(name, entry, entry_is_dir);
+    }
+    match is_dir {
+      true => self.keeps_empty_directory(included),
+      false => included && self.selects_file(path.rsplit('/').next().unwrap_or(path), meta, file),
+    }
+  }
+
+  /// Checks whether files are selected by their size, age, kind or links.
+  fn has_meta(&self) -> bool {
+    self.min_size.is_some() || self.max_size.is_some() || self.newer.is_some() || self.older.is_some()
+      || !self.kinds.is_empty() || self.broken_only
+  }
+
+  /// Checks whether the size, age and link of a file are selected, the
+  /// unknown ones never being.
+  fn matches_meta(&self, meta: &NodeMeta) -> bool {
+    let size = meta.size;
+    let modified = meta.modified;
+    self.min_size.is_none_or(|min| size.is_some_and(|size| size >= min))
+      && self.max_size.is_none_or(|max| size.is_some_and(|size| size < max))
+      && self.newer.is_none_or(|newer| modified.is_some_and(|modified| modified > newer))
+      && self.older.is_none_or(|older| modified.is_some_and(|modified| modified < older))
+      && (!self.broken_only || meta.link.as_ref().is_some_and(|link| link.broken))
+  }
+
+  /// Checks whether the kind of the file named `name` is selected, the
+  /// content of `file` being read if needed.
+  fn matches_kind(&self, name: &str, file: Option<&Path>) -> bool {
+    if self.kinds.is_empty() {
+      return true;
+    }
+    let kind = FileKind::detect(name, file);
+    self.kinds.iter().any(|selected| selected.includes(kind))
+  }
+}
+
+/// The state of a filtered copy.
+struct Filtering<'a> {
+  filter: &'a Filter,
+}
+
+impl Filtering<'_> {
+  /// Copies the children of `node` that the filter keeps into `copy`, along
+  /// with the tree mounted on `node`, `prefix` being the mount path of
+  /// `tree` in the output tree.
+  ///
+  /// # Returns
+  ///
+  /// Whether an entry below `node` was kept.
+  fn copy_children(&self, tree: &Tree, node: &Node, copy: &mut Node, prefix: &str, included: bool, target: &mut Tree) -> bool {
+    let mut kept = false;
+    for child in &node.children {
+      if let Some(child) = self.copy_node(tree, child, prefix, included, target) {
+        copy.children.push(child);
+        kept = true;
+      }
+    }
+
+    if let Some(mounted) = tree.mounts.get(&node.path) {
+      let prefix = Filtering::join(prefix, &node.path);
+      let mut copy_mounted = Tree::new();
+      copy_mounted.base = mounted.base.clone();
+      if let Some(root) = &mounted.root {
+        let mut copy_root = root.clone_entry();
+        if self.copy_children(mounted, root, &mut copy_root, &prefix, included, &mut copy_mounted) {
+          kept = true;
+        }
+        copy_mounted.root = Some(copy_root);
+      }
+      target.mounts.insert(node.path.clone(), copy_mounted);
+    }
+    kept
+  }
+
+  /// Copies `node` if the filter keeps it, `included` telling whether one
+  /// of its ancestors matched an include pattern.
+  fn copy_node(&self, tree: &Tree, node: &Node, prefix: &str, included: bool, target: &mut Tree) -> Option<Node> {
+    let path = Filtering::join(prefix, &node.path);
+    let is_dir = node.node_type == NodeType::Directory();
+    if self.filter.excludes(&path, is_dir) {
+      return None;
+    }
+
+    let file = tree.base.as_ref().map(|_| tree.absolute_path(node));
+    if !is_dir && !self.filter.selects_file(&node.name, &node.meta, file.as_deref()) {
+      return None;
+    }
+
+    let included = included || self.filter.includes(&node.name, &path, is_dir);
+    let mut copy = node.clone_entry();
+    let kept = self.copy_children(tree, node, &mut copy, prefix, included, target);
+    // When pruning or selecting files by size, age, kind or links, the
+    // directories kept below hold files.
+    let kept_alone = match is_dir {
+      true => self.filter.keeps_empty_directory(included),
+      false => included,
+    };
+    if !kept && !kept_alone {
+      // The mounted tree copied for a dropped mount point is not needed.
+      target.mounts.remove(&node.path);
+      return None;
+    }
+    Some(copy)
+  }
+
+  fn join(prefix: &str, path: &str) -> String {
+    match (prefix, path) {
+      (prefix, "/") => prefix.to_string(),
+      ("", path) => path.to_string(),
+      (prefix, path) => format!("{}/{}", prefix, path),
+    }
+  }
+}
+
+impl Tree {
+  /// Copies the entries of the tree that `filter` keeps, including mounted
+  /// trees. The root is always kept.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::filter::Filter;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["src/", "src/main.rs", "src/main.o", "docs/", "docs/index.md"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  ///
+  /// let mut filter = Filter::new();
+  /// filter.include("*.rs");
+  /// filter.include("*.o");
+  /// filter.exclude("main.o");
+  /// let filtered = tree.filtered(&filter);
+  /// assert!(filtered.find_by_path("src/main.rs").is_some());
+  /// assert!(filtered.find_by_path("src/main.o").is_none());
+  /// assert!(filtered.find_by_path("docs").is_none());
+  ///
+  /// let mut filter = Filter::new();
+  /// filter.exclude("*.md");
+  /// filter.prune();
+  /// assert!(tree.filtered(&filter).find_by_path("docs").is_none());
+  ///
+  /// tree.find_by_path_mut("src/main.o").unwrap().meta.size = Some(20 << 20);
+  /// let mut filter = Filter::new();
+  /// filter.min_size(10 << 20);
+  /// let filtered = tree.filtered(&filter);
+  /// assert!(filtered.find_by_path("src/main.o").is_some());
+  /// assert!(filtered.find_by_path("src/main.rs").is_none());
+  /// assert!(filtered.find_by_path("docs").is_none());
+  /// ```
+  pub fn filtered(&self, filter: &Filter) -> Tree {
+    let mut target = Tree::new();
+    target.base = self.base.clone();
+    if let Some(root) = &self.root {
+      let filtering = Filtering { filter };
+      let mut copy = root.clone_entry();
+      filtering.copy_children(self, root, &mut copy, "", filter.includes_all(), &mut target);
+      target.root = Some(copy);
+    }
+    target
+  }
+}
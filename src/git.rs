@@ -0,0 +1,188 @@
+//! Reading the state and the history of git repositories with the `git`
+//! command.
+//!
+//! The command is run rather than the repository read, so packed objects,
+//! worktrees and the configuration of the user are handled the way git
+//! itself handles them.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::node::*;
+
+/// Represents the status of an entry in the working directory of a
+/// repository, as listed by `git status`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GitStatus {
+  /// Ignored by a `.gitignore` file, `!`.
+  Ignored,
+  /// Not tracked, `?`.
+  Untracked,
+  /// Added to the index, `A`.
+  Added,
+  /// Modified, deleted, renamed or conflicted, `M`.
+  Modified,
+}
+
+impl GitStatus {
+  /// Gets the indicator of the status, like the first column of
+  /// `git status --short`.
+  pub fn marker(&self) -> char {
+    match self {
+      GitStatus::Ignored => '!',
+      GitStatus::Untracked => '?',
+      GitStatus::Added => 'A',
+      GitStatus::Modified => 'M',
+    }
+  }
+
+  /// Gets the ANSI color code of the status, the colors of `git status`.
+  pub fn color(&self) -> &'static str {
+    match self {
+      GitStatus::Ignored => "90",
+      GitStatus::Untracked => "31",
+      GitStatus::Added => "32",
+      GitStatus::Modified => "33",
+    }
+  }
+}
+
+/// Represents the statuses of the entries of a directory in a repository,
+/// by path relative to that directory.
+///
+/// Directories holding entries with a status other than ignored get the
+/// most significant of them, so the changes can be found from the top.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitStatuses {
+  statuses: HashMap<String, GitStatus>,
+}
+
+impl GitStatuses {
+  /// Runs `git status` in `dir`, which must be in a repository.
+  pub fn load(dir: &Path) -> Result<GitStatuses, String> {
+    let prefix = git(dir, &["rev-parse", "--show-prefix"])?;
+    let output = git(dir, &["status", "--porcelain=v1", "-z", "--ignored", "--untracked-files=all", "."])?;
+    Ok(GitStatuses::parse(&output, prefix.trim_end()))
+  }
+
+  /// Parses the output of `git status --porcelain=v1 -z`, whose paths are
+  /// relative to the top of the repository, keeping the entries below
+  /// `prefix`, the path of the directory from the top ending with a slash.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::git::{GitStatus, GitStatuses};
+  ///
+  /// let output = " M src/main.rs\0A  src/git.rs\0?? notes.txt\0!! target/\0R  new.rs\0old.rs\0";
+  /// let statuses = GitStatuses::parse(output, "");
+  /// assert_eq!(statuses.get("src/git.rs"), Some(GitStatus::Added));
+  /// assert_eq!(statuses.get("src"), Some(GitStatus::Modified));
+  /// assert_eq!(statuses.get("target"), Some(GitStatus::Ignored));
+  /// assert_eq!(statuses.get("new.rs"), Some(GitStatus::Modified));
+  /// assert_eq!(statuses.get("old.rs"), None);
+  ///
+  /// let statuses = GitStatuses::parse(output, "src/");
+  /// assert_eq!(statuses.get("main.rs"), Some(GitStatus::Modified));
+  /// assert_eq!(statuses.get("notes.txt"), None);
+  /// ```
+  pub fn parse(output: &str, prefix: &str) -> GitStatuses {
+    let mut statuses = GitStatuses::default();
+    let mut records = output.split('\0');
+    while let Some(record) = records.next() {
+      let (Some(code), Some(path)) = (record.get(..2), record.get(3..)) else {
+        continue;
+      };
+      // Renames and copies are followed by their source.
+      if code.contains(['R', 'C']) {
+        records.next();
+      }
+      let status = match code {
+        "!!" => GitStatus::Ignored,
+        "??" => GitStatus::Untracked,
+        code if code.contains('A') => GitStatus::Added,
+        _ => GitStatus::Modified,
+      };
+      if let Some(path) = path.strip_prefix(prefix) {
+        statuses.insert(path.trim_end_matches('/'), status);
+      }
+    }
+    statuses
+  }
+
+  fn insert(&mut self, path: &str, status: GitStatus) {
+    if path.is_empty() {
+      return;
+    }
+    self.statuses.insert(path.to_string(), status);
+    if status == GitStatus::Ignored {
+      return;
+    }
+    let mut parent = path;
+    while let Some((directory, _)) = parent.rsplit_once('/') {
+      let entry = self.statuses.entry(directory.to_string()).or_insert(status);
+      *entry = (*entry).max(status);
+      parent = directory;
+    }
+  }
+
+  /// Gets the status of the entry at `path`, relative to the directory.
+  pub fn get(&self, path: &str) -> Option<GitStatus> {
+    self.statuses.get(path).copied()
+  }
+}
+
+impl Tree {
+  /// Builds the tree of the revision `rev` of the repository of `dir`, from
+  /// the objects of the commit rather than the working directory, with
+  /// `git ls-tree`. The root is named after the revision, files have the
+  /// size and mode of their blob and submodules are empty directories.
+  ///
+  /// Any tree-ish can be given, like `HEAD~3`, a tag or `v1.0:src` for a
+  /// subdirectory. Only the entries below `dir` are listed when it is a
+  /// subdirectory of the repository. Revisions starting with a dash are
+  /// refused.
+  pub fn from_git(dir: &Path, rev: &str) -> Result<Tree, String> {
+    // It would be read as an option of git.
+    if rev.starts_with('-') {
+      return Err(format!("git: invalid revision '{}'", rev));
+    }
+    let output = git(dir, &["ls-tree", "-r", "-t", "-z", "--long", rev, "--"])?;
+    let mut tree = Tree::with_root(rev, None);
+    for record in output.split('\0').filter(|record| !record.is_empty()) {
+      // <mode> <type> <object> <size>\t<path>
+      let Some((info, path)) = record.split_once('\t') else {
+        return Err(format!("git: unexpected ls-tree entry '{}'", record));
+      };
+      let fields: Vec<&str> = info.split_whitespace().collect();
+      let [mode, kind, _, size] = fields[..] else {
+        return Err(format!("git: unexpected ls-tree entry '{}'", record));
+      };
+      let line = match kind {
+        "tree" | "commit" => format!("{}/", path),
+        _ => path.to_string(),
+      };
+      if let Some(mut node) = Node::from_path(&line) {
+        node.meta.size = size.parse().ok();
+        node.meta.mode = u32::from_str_radix(mode, 8).ok();
+        tree.insert(node);
+      }
+    }
+    Ok(tree)
+  }
+}
+
+/// Runs git with `args` in `dir`.
+///
+/// # Returns
+///
+/// The standard output of git, or its error message if it failed.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+  let output = Command::new("git").arg("-C").arg(dir).args(args).output()
+    .map_err(|e| format!("git: {}", e))?;
+  if !output.status.success() {
+    return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+  }
+  String::from_utf8(output.stdout).map_err(|_| "git: the output is not UTF-8".to_string())
+}
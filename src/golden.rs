@@ -0,0 +1,94 @@
+//! Golden file snapshots for the renderers.
+//!
+//! A rendering is compared to `tests/golden/<name>.txt`. Setting the
+//! `UPDATE_GOLDEN` environment variable writes the rendering to the golden
+//! file instead, which is how new snapshots are created.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Runs `render` against a buffer and returns what it wrote.
+pub fn render<F: FnOnce(&mut Vec<u8>) -> io::Result<()>>(render: F) -> String {
+  let mut out = Vec::new();
+  render(&mut out).expect("Could not render");
+  String::from_utf8(out).expect("Rendering is not UTF-8")
+}
+
+/// Gets the path of the golden file called `name`.
+pub fn golden_path(name: &str) -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("golden")
+    .join(format!("{}.txt", name))
+}
+
+/// Asserts that `actual` matches the golden file called `name`.
+///
+/// # Panics
+///
+/// If the golden file is missing or differs, with a line diff of the
+/// expected and actual outputs.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_tree::golden::*;
+/// use rust_tree::prelude::*;
+///
+/// let actual = render(|out| Tree::new().write_to(out));
+/// assert_golden("empty_tree", &actual);
+/// ```
+pub fn assert_golden(name: &str, actual: &str) {
+  let path = golden_path(name);
+  if env::var_os("UPDATE_GOLDEN").is_some() {
+    fs::create_dir_all(path.parent().unwrap()).expect("Could not create golden directory");
+    fs::write(&path, actual).expect("Could not write golden file");
+    return;
+  }
+
+  let expected = match fs::read_to_string(&path) {
+    Ok(expected) => expected,
+    Err(e) => panic!("Could not read {}: {}, rerun with UPDATE_GOLDEN=1 to create it", path.display(), e),
+  };
+  if expected != actual {
+    panic!("{} does not match:\n{}", path.display(), diff(&expected, actual));
+  }
+}
+
+/// Makes a line diff of `expected` and `actual`, prefixing removed lines
+/// with `-`, added lines with `+` and common lines with a space.
+pub fn diff(expected: &str, actual: &str) -> String {
+  let a: Vec<&str> = expected.lines().collect();
+  let b: Vec<&str> = actual.lines().collect();
+
+  // Longest common subsequence lengths of every pair of suffixes.
+  let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+  for i in (0..a.len()).rev() {
+    for j in (0..b.len()).rev() {
+      lcs[i][j] = if a[i] == b[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+
+  let mut out = String::new();
+  let (mut i, mut j) = (0, 0);
+  while i < a.len() || j < b.len() {
+    if i < a.len() && j < b.len() && a[i] == b[j] {
+      out.push_str(&format!(" {}\n", a[i]));
+      i += 1;
+      j += 1;
+    } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+      out.push_str(&format!("+{}\n", b[j]));
+      j += 1;
+    } else {
+      out.push_str(&format!("-{}\n", a[i]));
+      i += 1;
+    }
+  }
+  out
+}
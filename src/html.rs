@@ -0,0 +1,125 @@
+//! Writing trees as an HTML page of nested lists, like `tree -H`.
+
+use std::io::{self, Write};
+
+use crate::node::*;
+use crate::xml::escape;
+
+/// Percent-encodes the characters of a path that are not allowed in a URL
+/// path as is, keeping the slashes.
+fn encode_path(path: &str) -> String {
+  let mut encoded = String::with_capacity(path.len());
+  for byte in path.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+        encoded.push(byte as char);
+      },
+      _ => encoded.push_str(&format!("%{:02X}", byte)),
+    }
+  }
+  encoded
+}
+
+/// The state of an HTML writer.
+struct HtmlWriter<'a, W: Write> {
+  out: &'a mut W,
+  base_url: &'a str,
+  /// The names of the directories leading to the current node.
+  names: Vec<String>,
+  /// The depths of the directories whose list is still open.
+  open: Vec<u64>,
+  files: u64,
+  directories: u64,
+}
+
+impl<W: Write> HtmlWriter<'_, W> {
+  /// Closes the lists of the open directories at or below `depth`.
+  fn close_to(&mut self, depth: u64) -> io::Result<()> {
+    while let Some(open) = self.open.pop_if(|open| *open >= depth) {
+      let indent = "  ".repeat(open as usize * 2);
+      writeln!(self.out, "{}  </ul>", indent)?;
+      writeln!(self.out, "{}</li>", indent)?;
+    }
+    Ok(())
+  }
+
+  /// Writes the list item of `node`, `depth` levels deep in the output.
+  fn write_node(&mut self, node: &Node, depth: u64) -> io::Result<()> {
+    self.close_to(depth)?;
+    self.names.truncate(depth as usize - 1);
+    self.names.push(node.name.clone());
+    let href = format!("{}/{}", self.base_url.trim_end_matches('/'), encode_path(&self.names.join("/")));
+
+    let indent = "  ".repeat(depth as usize * 2);
+    match node.node_type {
+      NodeType::File() => {
+        self.files += 1;
+        writeln!(self.out, "{}<li><a href=\"{}\">{}</a></li>", indent, escape(&href), escape(&node.name))
+      },
+      NodeType::Directory() => {
+        self.directories += 1;
+        self.open.push(depth);
+        writeln!(self.out, "{}<li><a href=\"{}/\">{}/</a>", indent, escape(&href), escape(&node.name))?;
+        writeln!(self.out, "{}  <ul>", indent)
+      },
+    }
+  }
+}
+
+impl Tree {
+  /// Writes the tree, including mounted trees, to `out` as an HTML page
+  /// whose nested lists link every entry to `base_url` followed by its
+  /// path.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("docs/").unwrap());
+  /// tree.insert(Node::from_path("docs/read me.md").unwrap());
+  ///
+  /// let mut out = Vec::new();
+  /// tree.write_html(&mut out, "https://example.com/files/").unwrap();
+  /// let html = String::from_utf8(out).unwrap();
+  /// assert!(html.contains("<a href=\"https://example.com/files/docs/read%20me.md\">read me.md</a>"));
+  /// ```
+  pub fn write_html<W: Write>(&self, out: &mut W, base_url: &str) -> io::Result<()> {
+    let title = escape(self.root.as_ref().map_or("", |root| &root.name));
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "  <meta charset=\"utf-8\">")?;
+    writeln!(out, "  <title>{}</title>", title)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "  <h1>{}</h1>", title)?;
+    writeln!(out, "  <ul>")?;
+
+    let mut writer = HtmlWriter {
+      out: &mut *out,
+      base_url,
+      names: Vec::new(),
+      open: Vec::new(),
+      files: 0,
+      directories: 0,
+    };
+    let mut result = Ok(());
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        result = writer.write_node(node, depth);
+      }
+      result.is_ok()
+    });
+    result?;
+    writer.close_to(1)?;
+
+    let (files, directories) = (writer.files, writer.directories);
+    writeln!(out, "  </ul>")?;
+    writeln!(out, "  <p>{} directories, {} files</p>", directories, files)?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+  }
+}
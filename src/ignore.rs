@@ -0,0 +1,228 @@
+//! Exclusion rules with the syntax of gitignore files.
+//!
+//! `scan` picks the rules up from a `.treeignore` file at the scan root.
+//! Blank lines and lines starting with `#` are skipped, `!` re-includes
+//! what a previous rule excluded, a trailing `/` only matches directories
+//! and a pattern containing another `/` is relative to the root instead of
+//! matching names at any depth. Patterns support `*`, `?`, `**` and
+//! `[...]` classes, and the last matching rule wins.
+
+/// The name of the file the rules are read from at the scan root.
+pub const IGNORE_FILE: &str = ".treeignore";
+
+/// Represents a rule of an ignore file.
+#[derive(Clone, Debug)]
+struct Rule {
+  pattern: Vec<char>,
+  /// Whether the rule re-includes what it matches.
+  negated: bool,
+  /// Whether the rule only matches directories.
+  dir_only: bool,
+  /// Whether the pattern is matched against the whole path rather than
+  /// the name.
+  anchored: bool,
+}
+
+/// Represents the rules of an ignore file.
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreRules {
+  rules: Vec<Rule>,
+  ignore_case: bool,
+}
+
+impl IgnoreRules {
+  pub fn new() -> IgnoreRules {
+    IgnoreRules::default()
+  }
+
+  /// Parses the content of an ignore file.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::ignore::IgnoreRules;
+  ///
+  /// let rules = IgnoreRules::parse("target/\n*.log\n!keep.log\n/docs/*.md\n");
+  /// assert!(rules.is_ignored("target", true));
+  /// assert!(!rules.is_ignored("target", false));
+  /// assert!(rules.is_ignored("logs/debug.log", false));
+  /// assert!(!rules.is_ignored("logs/keep.log", false));
+  /// assert!(rules.is_ignored("docs/index.md", false));
+  /// assert!(!rules.is_ignored("src/docs/index.md", false));
+  /// ```
+  pub fn parse(text: &str) -> IgnoreRules {
+    let mut rules = IgnoreRules::new();
+    for line in text.lines() {
+      rules.add(line);
+    }
+    rules
+  }
+
+  /// Adds the rule written on a line of an ignore file, blank lines and
+  /// comments are skipped.
+  pub fn add(&mut self, line: &str) {
+    // Trailing spaces are ignored unless they are escaped.
+    let mut line = line.trim_end_matches(['\r', '\n']);
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+      line = &line[..line.len() - 1];
+    }
+    if line.is_empty() || line.starts_with('#') {
+      return;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+      Some(rest) => (true, rest),
+      None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+      Some(rest) => (true, rest),
+      None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    if line.is_empty() {
+      return;
+    }
+
+    self.rules.push(Rule {
+      pattern: self.fold(line),
+      negated,
+      dir_only,
+      anchored,
+    });
+  }
+
+  /// Matches the rules whatever the case of the paths and of the
+  /// patterns, like on the filesystems of Windows and macOS.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::ignore::IgnoreRules;
+  ///
+  /// let mut rules = IgnoreRules::parse("*.JPG\n");
+  /// assert!(!rules.is_ignored("photos/beach.jpg", false));
+  /// rules.ignore_case();
+  /// assert!(rules.is_ignored("photos/beach.jpg", false));
+  /// assert!(rules.is_ignored("photos/Beach.Jpg", false));
+  /// ```
+  pub fn ignore_case(&mut self) {
+    self.ignore_case = true;
+    for rule in &mut self.rules {
+      rule.pattern = rule.pattern.iter().flat_map(|c| c.to_lowercase()).collect();
+    }
+  }
+
+  /// Gets the characters of `text`, in lowercase when the case is ignored.
+  fn fold(&self, text: &str) -> Vec<char> {
+    match self.ignore_case {
+      true => text.chars().flat_map(char::to_lowercase).collect(),
+      false => text.chars().collect(),
+    }
+  }
+
+  /// Checks whether there are no rules.
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  /// Checks whether the entry at the relative `path` is excluded.
+  ///
+  /// The ancestors of the entry are not checked, which is what a scan
+  /// that does not descend into excluded directories needs.
+  pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+    let path = self.fold(path.trim_matches('/'));
+    let name_start = path.iter().rposition(|c| *c == '/').map_or(0, |i| i + 1);
+
+    let mut ignored = false;
+    for rule in &self.rules {
+      if rule.dir_only && !is_dir {
+        continue;
+      }
+      let text = if rule.anchored { &path[..] } else { &path[name_start..] };
+      if glob_match(&rule.pattern, text) {
+        ignored = !rule.negated;
+      }
+    }
+    ignored
+  }
+}
+
+/// Matches `text` against a gitignore `pattern`, where `*` and `?` do not
+/// match slashes and `**` matches any number of directories.
+pub(crate) fn glob_match(pattern: &[char], text: &[char]) -> bool {
+  match pattern.first() {
+    None => text.is_empty(),
+    Some('*') if pattern.get(1) == Some(&'*') => {
+      let rest = &pattern[2..];
+      // `**/` also matches no directory at all.
+      if rest.first() == Some(&'/') && glob_match(&rest[1..], text) {
+        return true;
+      }
+      (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+    },
+    Some('*') => {
+      let rest = &pattern[1..];
+      for i in 0..=text.len() {
+        if glob_match(rest, &text[i..]) {
+          return true;
+        }
+        if text.get(i) == Some(&'/') {
+          break;
+        }
+      }
+      false
+    },
+    Some('?') => {
+      matches!(text.first(), Some(c) if *c != '/') && glob_match(&pattern[1..], &text[1..])
+    },
+    Some('[') => {
+      match (text.first(), match_class(&pattern[1..], text.first().copied())) {
+        (Some(c), Some((true, rest))) if *c != '/' => glob_match(rest, &text[1..]),
+        // An unterminated class is a literal bracket.
+        (Some('['), None) => glob_match(&pattern[1..], &text[1..]),
+        _ => false,
+      }
+    },
+    Some('\\') if pattern.len() > 1 => {
+      text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+    },
+    Some(c) => {
+      text.first() == Some(c) && glob_match(&pattern[1..], &text[1..])
+    },
+  }
+}
+
+/// Matches `c` against the character class at the start of `pattern`,
+/// just after its opening bracket.
+///
+/// # Returns
+///
+/// Whether `c` is in the class and the pattern after the class, or `None`
+/// if the class is not terminated.
+fn match_class(pattern: &[char], c: Option<char>) -> Option<(bool, &[char])> {
+  let (negated, mut i) = match pattern.first() {
+    Some('!' | '^') => (true, 1),
+    _ => (false, 0),
+  };
+
+  let mut found = false;
+  let mut first = true;
+  while i < pattern.len() {
+    if pattern[i] == ']' && !first {
+      return Some((found != negated, &pattern[i + 1..]));
+    }
+    first = false;
+
+    let low = pattern[i];
+    if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|high| *high != ']') {
+      let high = pattern[i + 2];
+      found |= c.is_some_and(|c| low <= c && c <= high);
+      i += 3;
+    } else {
+      found |= c == Some(low);
+      i += 1;
+    }
+  }
+  None
+}
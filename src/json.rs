@@ -0,0 +1,519 @@
+//! Saving trees to JSON and loading them back.
+//!
+//! A tree is an object with its `base`, its `root` node and the trees
+//! mounted on it keyed by their mount path. A node is an object with its
+//! `name`, `type` (`file` or `directory`), optional `size` and `modified`
+//! metadata, the `attrs` set by `Node::set_attr` and `children`. Paths
+//! and depths are not stored, they are derived from the nesting when
+//! loading.
+//!
+//! ```text
+//! {
+//!   "base": "/srv/data",
+//!   "root": {
+//!     "name": "/srv/data",
+//!     "type": "directory",
+//!     "children": [
+//!       { "name": "main.rs", "type": "file", "size": 42, "attrs": { "owner": "core" }, "children": [] }
+//!     ]
+//!   },
+//!   "mounts": {}
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::node::*;
+
+/// Represents a JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+  Null,
+  Bool(bool),
+  /// A number, kept as written so that 64 bit integers do not lose
+  /// precision.
+  Number(String),
+  String(String),
+  Array(Vec<Json>),
+  /// An object, with its members in order.
+  Object(Vec<(String, Json)>),
+}
+
+impl Json {
+  /// Parses a JSON document.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::json::Json;
+  ///
+  /// let json = Json::parse(r#"{ "size": 42, "tags": ["a", null] }"#).unwrap();
+  /// assert_eq!(json.get("size").and_then(Json::as_u64), Some(42));
+  /// assert!(Json::parse("[1, 2").is_err());
+  /// ```
+  pub fn parse(text: &str) -> Result<Json, String> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+      return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+  }
+
+  /// Gets the member `key` of an object.
+  pub fn get(&self, key: &str) -> Option<&Json> {
+    match self {
+      Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+      _ => None,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Json::String(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  pub fn as_u64(&self) -> Option<u64> {
+    match self {
+      Json::Number(n) => n.parse().ok(),
+      _ => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&[Json]> {
+    match self {
+      Json::Array(items) => Some(items),
+      _ => None,
+    }
+  }
+
+  /// Writes the value, indenting nested arrays and objects by two spaces.
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    self.write_indented(out, 0)?;
+    writeln!(out)
+  }
+
+  /// Writes the value on a single line, without spaces nor a trailing line
+  /// break.
+  pub fn write_compact<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    match self {
+      Json::Array(items) => {
+        write!(out, "[")?;
+        for (i, item) in items.iter().enumerate() {
+          if i > 0 {
+            write!(out, ",")?;
+          }
+          item.write_compact(out)?;
+        }
+        write!(out, "]")
+      },
+      Json::Object(members) => {
+        write!(out, "{{")?;
+        for (i, (key, value)) in members.iter().enumerate() {
+          if i > 0 {
+            write!(out, ",")?;
+          }
+          write_string(out, key)?;
+          write!(out, ":")?;
+          value.write_compact(out)?;
+        }
+        write!(out, "}}")
+      },
+      value => value.write_indented(out, 0),
+    }
+  }
+
+  fn write_indented<W: Write>(&self, out: &mut W, indent: usize) -> io::Result<()> {
+    match self {
+      Json::Null => write!(out, "null"),
+      Json::Bool(b) => write!(out, "{}", b),
+      Json::Number(n) => write!(out, "{}", n),
+      Json::String(s) => write_string(out, s),
+      Json::Array(items) if items.is_empty() => write!(out, "[]"),
+      Json::Array(items) => {
+        writeln!(out, "[")?;
+        for (i, item) in items.iter().enumerate() {
+          write!(out, "{:width$}", "", width = indent + 2)?;
+          item.write_indented(out, indent + 2)?;
+          writeln!(out, "{}", if i + 1 < items.len() { "," } else { "" })?;
+        }
+        write!(out, "{:width$}]", "", width = indent)
+      },
+      Json::Object(members) if members.is_empty() => write!(out, "{{}}"),
+      Json::Object(members) => {
+        writeln!(out, "{{")?;
+        for (i, (key, value)) in members.iter().enumerate() {
+          write!(out, "{:width$}", "", width = indent + 2)?;
+          write_string(out, key)?;
+          write!(out, ": ")?;
+          value.write_indented(out, indent + 2)?;
+          writeln!(out, "{}", if i + 1 < members.len() { "," } else { "" })?;
+        }
+        write!(out, "{:width$}}}", "", width = indent)
+      },
+    }
+  }
+}
+
+pub(crate) fn write_string<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+  write!(out, "\"")?;
+  for c in s.chars() {
+    match c {
+      '"' => write!(out, "\\\"")?,
+      '\\' => write!(out, "\\\\")?,
+      '\n' => write!(out, "\\n")?,
+      '\r' => write!(out, "\\r")?,
+      '\t' => write!(out, "\\t")?,
+      c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+      c => write!(out, "{}", c)?,
+    }
+  }
+  write!(out, "\"")
+}
+
+struct Parser<'a> {
+  text: &'a str,
+  pos: usize,
+}
+
+impl Parser<'_> {
+  fn error(&self, message: &str) -> String {
+    format!("{} at byte {}", message, self.pos)
+  }
+
+  fn peek(&self) -> Option<u8> {
+    self.text.as_bytes().get(self.pos).copied()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+      self.pos += 1;
+    }
+  }
+
+  fn expect(&mut self, c: u8) -> Result<(), String> {
+    self.skip_whitespace();
+    if self.peek() != Some(c) {
+      return Err(self.error(&format!("expected '{}'", c as char)));
+    }
+    self.pos += 1;
+    Ok(())
+  }
+
+  fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+    if !self.text[self.pos..].starts_with(keyword) {
+      return Err(self.error("unexpected character"));
+    }
+    self.pos += keyword.len();
+    Ok(value)
+  }
+
+  fn value(&mut self) -> Result<Json, String> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some(b'n') => self.keyword("null", Json::Null),
+      Some(b't') => self.keyword("true", Json::Bool(true)),
+      Some(b'f') => self.keyword("false", Json::Bool(false)),
+      Some(b'"') => Ok(Json::String(self.string()?)),
+      Some(b'[') => self.array(),
+      Some(b'{') => self.object(),
+      Some(b'-' | b'0'..=b'9') => self.number(),
+      Some(_) => Err(self.error("unexpected character")),
+      None => Err(self.error("unexpected end of input")),
+    }
+  }
+
+  fn number(&mut self) -> Result<Json, String> {
+    let start = self.pos;
+    while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+      self.pos += 1;
+    }
+    let number = &self.text[start..self.pos];
+    match number.parse::<f64>() {
+      Ok(_) => Ok(Json::Number(number.to_string())),
+      Err(_) => Err(self.error("invalid number")),
+    }
+  }
+
+  fn hex(&mut self) -> Result<u32, String> {
+    let digits = self.text.get(self.pos..self.pos + 4).ok_or_else(|| self.error("truncated escape"))?;
+    let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid escape"))?;
+    self.pos += 4;
+    Ok(code)
+  }
+
+  fn string(&mut self) -> Result<String, String> {
+    self.expect(b'"')?;
+    let mut s = String::new();
+    loop {
+      let c = self.text[self.pos..].chars().next().ok_or_else(|| self.error("unterminated string"))?;
+      self.pos += c.len_utf8();
+      match c {
+        '"' => return Ok(s),
+        '\\' => {
+          let escape = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+          self.pos += 1;
+          match escape {
+            b'"' => s.push('"'),
+            b'\\' => s.push('\\'),
+            b'/' => s.push('/'),
+            b'b' => s.push('\u{8}'),
+            b'f' => s.push('\u{c}'),
+            b'n' => s.push('\n'),
+            b'r' => s.push('\r'),
+            b't' => s.push('\t'),
+            b'u' => {
+              let mut code = self.hex()?;
+              // A high surrogate is followed by the escaped low surrogate.
+              if (0xd800..0xdc00).contains(&code) && self.text[self.pos..].starts_with("\\u") {
+                self.pos += 2;
+                let low = self.hex()?;
+                code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+              }
+              s.push(char::from_u32(code).ok_or_else(|| self.error("invalid escape"))?);
+            },
+            _ => return Err(self.error("invalid escape")),
+          }
+        },
+        c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+        c => s.push(c),
+      }
+    }
+  }
+
+  fn array(&mut self) -> Result<Json, String> {
+    self.expect(b'[')?;
+    let mut items = Vec::new();
+    self.skip_whitespace();
+    if self.peek() == Some(b']') {
+      self.pos += 1;
+      return Ok(Json::Array(items));
+    }
+    loop {
+      items.push(self.value()?);
+      self.skip_whitespace();
+      match self.peek() {
+        Some(b',') => self.pos += 1,
+        Some(b']') => {
+          self.pos += 1;
+          return Ok(Json::Array(items));
+        },
+        _ => return Err(self.error("expected ',' or ']'")),
+      }
+    }
+  }
+
+  fn object(&mut self) -> Result<Json, String> {
+    self.expect(b'{')?;
+    let mut members = Vec::new();
+    self.skip_whitespace();
+    if self.peek() == Some(b'}') {
+      self.pos += 1;
+      return Ok(Json::Object(members));
+    }
+    loop {
+      self.skip_whitespace();
+      let key = self.string()?;
+      self.expect(b':')?;
+      members.push((key, self.value()?));
+      self.skip_whitespace();
+      match self.peek() {
+        Some(b',') => self.pos += 1,
+        Some(b'}') => {
+          self.pos += 1;
+          return Ok(Json::Object(members));
+        },
+        _ => return Err(self.error("expected ',' or '}'")),
+      }
+    }
+  }
+}
+
+impl Node {
+  /// Converts the node and its descendants to JSON.
+  pub fn to_json(&self) -> Json {
+    let node_type = match self.node_type {
+      NodeType::File() => "file",
+      NodeType::Directory() => "directory",
+    };
+    let mut members = vec![
+      ("name".to_string(), Json::String(self.name.clone())),
+      ("type".to_string(), Json::String(node_type.to_string())),
+    ];
+    if self.data.default_path != self.path {
+      members.push(("default_path".to_string(), Json::String(self.data.default_path.clone())));
+    }
+    if let Some(size) = self.meta.size {
+      members.push(("size".to_string(), Json::Number(size.to_string())));
+    }
+    if let Some(size) = self.meta.compressed_size {
+      members.push(("compressed_size".to_string(), Json::Number(size.to_string())));
+    }
+    if let Some(checksum) = &self.meta.checksum {
+      members.push(("checksum".to_string(), Json::String(checksum.clone())));
+    }
+    if let Some(link) = &self.meta.link {
+      members.push(("target".to_string(), Json::String(link.target.clone())));
+      members.push(("broken".to_string(), Json::Bool(link.broken)));
+    }
+    if let Some(e) = &self.meta.error {
+      members.push(("error".to_string(), Json::String(e.clone())));
+    }
+    if !self.meta.xattrs.is_empty() {
+      let names = self.meta.xattrs.iter().map(|name| Json::String(name.clone())).collect();
+      members.push(("xattrs".to_string(), Json::Array(names)));
+    }
+    if !self.meta.attrs.is_empty() {
+      let attrs = self.meta.attrs.iter().map(|(key, value)| (key.clone(), Json::String(value.clone()))).collect();
+      members.push(("attrs".to_string(), Json::Object(attrs)));
+    }
+    if let Some(since_epoch) = self.meta.modified.and_then(|m| m.duration_since(UNIX_EPOCH).ok()) {
+      members.push(("modified".to_string(), Json::Object(vec![
+        ("secs".to_string(), Json::Number(since_epoch.as_secs().to_string())),
+        ("nanos".to_string(), Json::Number(since_epoch.subsec_nanos().to_string())),
+      ])));
+    }
+    let children = self.children.iter().map(Node::to_json).collect();
+    members.push(("children".to_string(), Json::Array(children)));
+    Json::Object(members)
+  }
+
+  /// Converts JSON back to a node at `depth`, under a parent at
+  /// `parent_path`.
+  fn from_json(json: &Json, parent_path: &str, depth: u64) -> Result<Node, String> {
+    let name = json.get("name").and_then(Json::as_str).ok_or("node without a name")?;
+    if depth > 0 && (name.is_empty() || name.contains('/')) {
+      return Err(format!("{}: invalid node name", name));
+    }
+    let path = match (depth, parent_path) {
+      (0, _) => "/".to_string(),
+      (_, "/") => name.to_string(),
+      _ => format!("{}/{}", parent_path, name),
+    };
+
+    let default_path = match json.get("default_path").and_then(Json::as_str) {
+      Some(default_path) => default_path.to_string(),
+      None => path.clone(),
+    };
+    let data = NodeData {
+      length: default_path.len() as u64,
+      default_path,
+    };
+    let mut node = match json.get("type").and_then(Json::as_str) {
+      Some("file") => Node::new_file(data, depth, path, name.to_string()),
+      Some("directory") => Node::new_directory(data, depth, path, name.to_string()),
+      _ => return Err(format!("{}: invalid node type", name)),
+    };
+
+    node.meta.size = json.get("size").and_then(Json::as_u64);
+    node.meta.compressed_size = json.get("compressed_size").and_then(Json::as_u64);
+    node.meta.checksum = json.get("checksum").and_then(Json::as_str).map(String::from);
+    node.meta.link = json.get("target").and_then(Json::as_str).map(|target| Link {
+      target: target.to_string(),
+      broken: matches!(json.get("broken"), Some(Json::Bool(true))),
+    });
+    node.meta.error = json.get("error").and_then(Json::as_str).map(String::from);
+    node.meta.xattrs = json.get("xattrs").and_then(Json::as_array).unwrap_or(&[])
+      .iter().filter_map(Json::as_str).map(String::from).collect();
+    if let Some(Json::Object(attrs)) = json.get("attrs") {
+      node.meta.attrs = attrs.iter()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+        .collect();
+    }
+    node.meta.modified = json.get("modified").and_then(|modified| {
+      let secs = modified.get("secs").and_then(Json::as_u64)?;
+      let nanos = modified.get("nanos").and_then(Json::as_u64).unwrap_or(0);
+      Some(UNIX_EPOCH + Duration::new(secs, nanos as u32))
+    });
+
+    let children = json.get("children").and_then(Json::as_array).unwrap_or(&[]);
+    if !children.is_empty() && node.node_type == NodeType::File() {
+      return Err(format!("{}: a file cannot have children", node.path));
+    }
+    for child in children {
+      let child = Node::from_json(child, &node.path, depth + 1)?;
+      node.children.push(child);
+    }
+    Ok(node)
+  }
+}
+
+impl Tree {
+  /// Converts the tree, including mounted trees, to JSON.
+  pub fn to_json(&self) -> Json {
+    let base = match self.base() {
+      Some(base) => Json::String(base.to_string_lossy().into_owned()),
+      None => Json::Null,
+    };
+    let root = match &self.root {
+      Some(root) => root.to_json(),
+      None => Json::Null,
+    };
+    let mut mounts: Vec<(&String, &Tree)> = self.mounts.iter().collect();
+    mounts.sort_by(|a, b| a.0.cmp(b.0));
+    let mounts = mounts.into_iter()
+      .map(|(path, tree)| (path.clone(), tree.to_json()))
+      .collect();
+
+    Json::Object(vec![
+      ("base".to_string(), base),
+      ("root".to_string(), root),
+      ("mounts".to_string(), Json::Object(mounts)),
+    ])
+  }
+
+  /// Writes the tree to `out` as JSON, see the `json` module for the format.
+  pub fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    self.to_json().write_to(out)
+  }
+
+  /// Loads a tree saved with `Tree::write_json`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// let mut main = Node::from_path("src/main.rs").unwrap();
+  /// main.set_attr("owner", "core");
+  /// tree.insert(main);
+  ///
+  /// let mut out = Vec::new();
+  /// tree.write_json(&mut out).unwrap();
+  /// let loaded = Tree::from_json(&String::from_utf8(out).unwrap()).unwrap();
+  /// assert_eq!(loaded.find_by_path("src/main.rs").unwrap().get_attr("owner"), Some("core"));
+  /// assert!(tree.diff(&loaded).is_empty());
+  /// ```
+  pub fn from_json(text: &str) -> Result<Tree, String> {
+    Tree::from_json_value(&Json::parse(text)?)
+  }
+
+  fn from_json_value(json: &Json) -> Result<Tree, String> {
+    let mut tree = Tree::new();
+    tree.base = json.get("base").and_then(Json::as_str).map(PathBuf::from);
+    tree.root = match json.get("root") {
+      Some(Json::Null) | None => None,
+      Some(root) => {
+        let root = Node::from_json(root, "/", 0)?;
+        if root.node_type != NodeType::Directory() {
+          return Err("the root node is not a directory".to_string());
+        }
+        Some(root)
+      },
+    };
+    if let Some(Json::Object(mounts)) = json.get("mounts") {
+      tree.mounts = mounts.iter()
+        .map(|(path, mounted)| Ok((path.clone(), Tree::from_json_value(mounted)?)))
+        .collect::<Result<HashMap<String, Tree>, String>>()?;
+    }
+    Ok(tree)
+  }
+}
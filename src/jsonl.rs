@@ -0,0 +1,89 @@
+//! Writing trees as JSON Lines, one object per node.
+//!
+//! ```text
+//! {"path":".","type":"directory","depth":0}
+//! {"path":"src","type":"directory","depth":1}
+//! {"path":"src/main.rs","type":"file","depth":2,"size":42,"modified":{"secs":1710000000,"nanos":0}}
+//! ```
+
+use std::io::{self, Write};
+use std::time::UNIX_EPOCH;
+
+use crate::json::Json;
+use crate::node::*;
+
+impl Node {
+  /// Gets the JSON Lines object of this node only, at `path` relative to
+  /// the root and `depth` levels deep, with the members of `Node::to_json`
+  /// but its children.
+  fn to_jsonl(&self, path: String, depth: u64) -> Json {
+    let node_type = match self.node_type {
+      NodeType::File() => "file",
+      NodeType::Directory() => "directory",
+    };
+    let mut members = vec![
+      ("path".to_string(), Json::String(path)),
+      ("type".to_string(), Json::String(node_type.to_string())),
+      ("depth".to_string(), Json::Number(depth.to_string())),
+    ];
+    if let Some(size) = self.meta.size {
+      members.push(("size".to_string(), Json::Number(size.to_string())));
+    }
+    if let Some(since_epoch) = self.meta.modified.and_then(|m| m.duration_since(UNIX_EPOCH).ok()) {
+      members.push(("modified".to_string(), Json::Object(vec![
+        ("secs".to_string(), Json::Number(since_epoch.as_secs().to_string())),
+        ("nanos".to_string(), Json::Number(since_epoch.subsec_nanos().to_string())),
+      ])));
+    }
+    if let Some(checksum) = &self.meta.checksum {
+      members.push(("checksum".to_string(), Json::String(checksum.clone())));
+    }
+    if !self.meta.attrs.is_empty() {
+      let attrs = self.meta.attrs.iter().map(|(key, value)| (key.clone(), Json::String(value.clone()))).collect();
+      members.push(("attrs".to_string(), Json::Object(attrs)));
+    }
+    Json::Object(members)
+  }
+}
+
+impl Tree {
+  /// Writes the tree, including mounted trees, to `out` as JSON Lines: one
+  /// object per node in pre-order, each written as soon as its node is
+  /// visited, with its path relative to the root (`.` for the root).
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// let mut node = Node::from_path("src/main.rs").unwrap();
+  /// node.meta.size = Some(42);
+  /// tree.insert(node);
+  ///
+  /// let mut out = Vec::new();
+  /// tree.write_jsonl(&mut out).unwrap();
+  /// let lines: Vec<String> = String::from_utf8(out).unwrap().lines().map(String::from).collect();
+  /// assert_eq!(lines.len(), 3);
+  /// assert_eq!(lines[2], r#"{"path":"src/main.rs","type":"file","depth":2,"size":42}"#);
+  /// ```
+  pub fn write_jsonl<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut result = Ok(());
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      let path = if depth == 0 {
+        ".".to_string()
+      } else {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+        names.join("/")
+      };
+      result = node.to_jsonl(path, depth).write_compact(out).and_then(|_| writeln!(out));
+      result.is_ok()
+    });
+    result
+  }
+}
@@ -0,0 +1,74 @@
+//! Build, query and display trees of paths.
+//!
+//! The types most programs need are re-exported by the `prelude`, which is
+//! the stable entry point of the crate.
+//!
+//! ```
+//! use rust_tree::prelude::*;
+//!
+//! let mut tree = Tree::new();
+//! tree.insert(Node::from_path("src/").unwrap());
+//! tree.insert(Node::from_path("src/main.rs").unwrap());
+//! assert_eq!(tree.stats().files, 1);
+//! ```
+
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod archive;
+pub mod arena;
+pub mod audit;
+pub mod budget;
+pub mod cache;
+pub mod checksum;
+pub mod config;
+pub mod csv;
+pub mod date;
+pub mod diff;
+pub mod dot;
+pub mod du;
+pub mod duplicates;
+pub mod exec;
+pub mod extensions;
+pub mod filetype;
+pub mod filter;
+pub mod git;
+#[cfg(feature = "golden")]
+pub mod golden;
+pub mod html;
+pub mod ignore;
+pub mod json;
+pub mod jsonl;
+pub mod mermaid;
+pub mod node;
+pub mod output;
+pub mod remote;
+pub mod render;
+pub mod scaffold;
+pub mod scan;
+pub mod sort;
+pub mod tui;
+pub mod xattr;
+pub mod xml;
+pub mod yaml;
+
+pub(crate) mod log;
+pub(crate) mod regex;
+pub(crate) mod size;
+pub(crate) mod text;
+pub(crate) mod unicode;
+
+// The helpers the program shares with the library, the log macros
+// expanding to the level checks.
+pub use crate::log::{enabled, set_verbosity, verbosity, Level};
+pub use crate::regex::Regex;
+pub use crate::size::{format_size, parse_size};
+pub use crate::text::is_tree_text;
+
+/// Re-exports the stable types of the crate.
+pub mod prelude {
+  pub use crate::diff::{DiffEntry, DiffKind, TreeDiff};
+  pub use crate::node::{ConflictPolicy, DuplicatePolicy, Link, Node, NodeData, NodeMeta, NodeType, SubtreeStats, Tree};
+  pub use crate::output::{Format, Output};
+  pub use crate::render::{NameStyle, RenderOptions};
+  pub use crate::scan::{scan, scan_into, scan_only, Entry, FsSource, MockSource, ScanOptions, Source};
+}
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Represents the level of a diagnostic message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+  Error = 0,
+  Warn = 1,
+  Info = 2,
+  Debug = 3,
+}
+
+impl Level {
+  fn from_u8(value: u8) -> Level {
+    match value {
+      0 => Level::Error,
+      1 => Level::Warn,
+      2 => Level::Info,
+      _ => Level::Debug,
+    }
+  }
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+
+/// Sets the most verbose level of the messages that are printed.
+pub fn set_verbosity(level: Level) {
+  VERBOSITY.store(level as u8, Ordering::Relaxed);
+}
+
+/// Gets the most verbose level of the messages that are printed.
+pub fn verbosity() -> Level {
+  Level::from_u8(VERBOSITY.load(Ordering::Relaxed))
+}
+
+/// Checks whether messages of `level` are printed.
+pub fn enabled(level: Level) -> bool {
+  level <= verbosity()
+}
+
+/// Prints a message to stderr if messages of `$level` are enabled.
+///
+/// Errors writing to stderr are ignored, as `eprintln!` would panic when
+/// it is a closed pipe.
+#[macro_export]
+macro_rules! log {
+  ($level:expr, $($arg:tt)*) => {
+    if $crate::enabled($level) {
+      use ::std::io::Write as _;
+      let _ = writeln!(::std::io::stderr(), $($arg)*);
+    }
+  };
+}
+
+/// Prints an error message to stderr.
+#[macro_export]
+macro_rules! error {
+  ($($arg:tt)*) => { $crate::log!($crate::Level::Error, $($arg)*) };
+}
+
+/// Prints a warning to stderr unless `-q` was given.
+#[macro_export]
+macro_rules! warning {
+  ($($arg:tt)*) => { $crate::log!($crate::Level::Warn, $($arg)*) };
+}
+
+/// Prints an informational message to stderr with `-v`.
+#[macro_export]
+macro_rules! info {
+  ($($arg:tt)*) => { $crate::log!($crate::Level::Info, $($arg)*) };
+}
+
+/// Prints a debug message to stderr with `-vv`.
+#[macro_export]
+macro_rules! debug {
+  ($($arg:tt)*) => { $crate::log!($crate::Level::Debug, $($arg)*) };
+}
+
+pub(crate) use crate::{debug, warning};
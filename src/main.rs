@@ -1,9 +1,33 @@
-pub mod node;
+mod cli;
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 
-use node::*;
+use cli::{Anchor, Command, Options};
+use rust_tree::archive::{is_archive, read_archive};
+use rust_tree::git::GitStatuses;
+use rust_tree::ignore::IgnoreRules;
+use rust_tree::checksum::ChecksumAlgorithm;
+use rust_tree::filter::Filter;
+use rust_tree::prelude::*;
+use rust_tree::tui::browse;
+use rust_tree::{debug, error, format_size, info, is_tree_text, warning};
+
+/** The exit code when some entries could not be read. */
+const EXIT_PARTIAL: i32 = 1;
+/** The exit code when the arguments are invalid. */
+const EXIT_USAGE: i32 = 2;
+/** The exit code when a subtree is over its budget. */
+const EXIT_OVER_BUDGET: i32 = 3;
+/**
+ * The exit code when an error stops the program, like a root which cannot
+ * be loaded or an output which cannot be written.
+ */
+const EXIT_FAILURE: i32 = 4;
 
 
 
@@ -12,79 +36,611 @@ use node::*;
  * @param path The path to the file to read.
  * @return A vector containing the lines of the file.
  */
-fn read_file(path: &str) -> Vec<String> {
+fn read_file(path: &Path) -> io::Result<Vec<String>> {
     let mut lines = Vec::new();
-    let file = File::open(path).expect("Could not open file");
+    let file = File::open(path)?;
     let reader = BufReader::new(file);
     for line in reader.lines() {
-     lines.push(line.expect("Could not read line"));
+     lines.push(line?);
     }
-    lines
+    Ok(lines)
 }
 
-fn main() {
-    let lines = read_file("paths.txt");
-
+/**
+ * Find the directory all the lines of a path list are in, like `.` for the
+ * output of `find .`, `project` for `find project` or `/usr` for
+ * `find /usr`, to label the root with. The lines keep their full paths.
+ * Directories are in common with themselves, files with their parent, so
+ * the candidate prefix is shrunk as the lines are read, then loses its
+ * last name if it is only a file.
+ * @param lines The paths, one per line.
+ * @return The label, `None` if the lines have nothing in common.
+ */
+fn common_root_label<'a, I: Iterator<Item = &'a str> + Clone>(lines: I) -> Option<String> {
+    let split = |line: &'a str| -> Vec<&'a str> {
+        let mut parts: Vec<&str> = line.split('/').filter(|part| !part.is_empty()).collect();
+        if parts.first() == Some(&".") {
+            parts.remove(0);
+        }
+        parts
+    };
+    let lines = lines.filter(|line| !line.is_empty());
+    let all = |start: fn(&str) -> bool| lines.clone().next().is_some() && lines.clone().all(start);
+    let dotted = all(|line| line == "." || line.starts_with("./"));
+    let absolute = all(|line| line.starts_with('/'));
 
+    let mut prefix: Option<Vec<&str>> = None;
+    for parts in lines.clone().map(split) {
+        match &mut prefix {
+            Some(prefix) => {
+                let common = prefix.iter().zip(&parts).take_while(|(a, b)| a == b).count();
+                prefix.truncate(common);
+            },
+            None => prefix = Some(parts),
+        }
+    }
+    let mut prefix = prefix.unwrap_or_default();
+    // Every line is the prefix itself, a file unless it ends with a slash.
+    if !prefix.is_empty() && lines.clone().all(|line| !line.ends_with('/') && split(line).len() == prefix.len()) {
+        prefix.pop();
+    }
 
-    let mut tree = Tree::new();
-    for line in lines {
-        println!("");
-        println!("[---------------------------------------------------");
-        println!("Adding : {}", line);
+    match (prefix.is_empty(), dotted, absolute) {
+        (true, true, _) => Some(".".to_string()),
+        (true, _, true) => Some("/".to_string()),
+        (true, _, _) => None,
+        (false, true, _) => Some(format!("./{}", prefix.join("/"))),
+        (false, _, true) => Some(format!("/{}", prefix.join("/"))),
+        (false, _, _) => Some(prefix.join("/")),
+    }
+}
 
-        let default_path = line.clone();
-        let mut split: Vec<&str> = line.split("/").collect();
+/**
+ * Build a tree from the lines of a path list.
+ * Lines ending with a slash and lines leading to other lines are
+ * directories, other lines are files. The root is labelled with the
+ * directory all the lines are in, if any. The lines the filter drops are
+ * left out, but for the directories leading to the kept ones.
+ * @param lines The paths, one per line, with the metadata of their entry.
+ * @param base The directory the paths are anchored under.
+ * @param filter The entries to keep.
+ * @return The tree.
+ */
+fn build_tree(lines: Vec<(String, NodeMeta)>, base: Option<PathBuf>, filter: &Filter) -> Tree {
+    let root = common_root_label(lines.iter().map(|(line, _)| line.as_str()));
+    let mut parents = HashSet::new();
+    for node in lines.iter().filter_map(|(line, _)| Node::from_path(line)) {
+        let mut path = node.path.as_str();
+        while let Some((parent, _)) = path.rsplit_once('/') {
+            if !parents.insert(parent.to_string()) {
+                break;
+            }
+            path = parent;
+        }
+    }
 
-        // Remove the first element if it is a dot.
-        if split[0] == "." {
-            split.remove(0);
+    let classify = |path: &str| {
+        if parents.contains(path) { NodeType::Directory() } else { NodeType::File() }
+    };
+    let mut nodes = Vec::new();
+    for (line, meta) in lines {
+        debug!("Adding : {}", line);
+        match Node::from_path_with(&line, classify) {
+            Some(node) => nodes.push((node, meta)),
+            None => debug!("Skipping a line naming the root or nothing."),
         }
+    }
+    if !filter.is_empty() {
+        let kept: Vec<bool> = nodes.iter().map(|(node, meta)| {
+            let file = base.as_ref().map(|base| base.join(&node.path));
+            filter.keeps_path(&node.path, node.node_type == NodeType::Directory(), meta, file.as_deref())
+        }).collect();
+        // The directories leading to the kept entries, as the list has them.
+        let mut leading = HashSet::new();
+        for ((node, _), _) in nodes.iter().zip(&kept).filter(|(_, kept)| **kept) {
+            let mut path = node.path.as_str();
+            while let Some((parent, _)) = path.rsplit_once('/') {
+                if !leading.insert(parent.to_string()) {
+                    break;
+                }
+                path = parent;
+            }
+        }
+        let mut kept = kept.into_iter();
+        nodes.retain(|(node, _)| kept.next().unwrap_or(false) || leading.contains(&node.path));
+    }
 
-        // Remove the last element if it is empty.
-        if split[split.len() - 1] == "" {
-            split.remove(split.len() - 1);
+    let mut tree = match root {
+        Some(label) => Tree::with_root(&label, base),
+        None => {
+            let mut tree = Tree::new();
+            tree.set_base(base);
+            tree
+        }
+    };
+    // The paths in the tree, to add the directories the list leaves out.
+    let mut present = HashSet::new();
+    for (mut node, meta) in nodes {
+        let mut missing = Vec::new();
+        let mut path = node.path.as_str();
+        while let Some((parent, _)) = path.rsplit_once('/') {
+            if !present.insert(parent.to_string()) {
+                break;
+            }
+            missing.push(parent.to_string());
+            path = parent;
+        }
+        for parent in missing.iter().rev() {
+            if let Some(directory) = Node::from_path(&format!("{}/", parent)) {
+                tree.insert(directory);
+            }
         }
+        present.insert(node.path.clone());
+        node.meta = meta;
+        tree.insert(node);
+    }
+    tree
+}
+
+/**
+ * Where what is printed goes: stdout, or the file given with `-o`.
+ */
+struct Printer {
+    out: Box<dyn Write>,
+    path: Option<PathBuf>,
+}
 
-        let data = NodeData {
-            length: default_path.len() as u64,
-            default_path,
+impl Printer {
+    /**
+     * Open the destination of what is printed.
+     * @param path The file to write to, `None` for stdout.
+     * @return The printer, or the error creating the file.
+     */
+    fn open(path: Option<PathBuf>) -> io::Result<Printer> {
+        let out: Box<dyn Write> = match &path {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(io::stdout().lock()),
         };
+        Ok(Printer { out, path })
+    }
 
-        let depth = split.len() as u64;
+    /**
+     * Print with `write`, exiting with an error if it fails. A closed
+     * stdout, like when piping to `head`, ends the program quietly.
+     * @param write The function writing what is printed.
+     */
+    fn print<F: FnOnce(&mut Box<dyn Write>) -> io::Result<()>>(&mut self, write: F) {
+        if let Err(e) = write(&mut self.out) {
+            self.fail(e);
+        }
+    }
 
-        println!("Split: {:?}", split);
+    /**
+     * Flush what is printed, exiting with an error if it fails.
+     */
+    fn finish(&mut self) {
+        if let Err(e) = self.out.flush() {
+            self.fail(e);
+        }
+    }
 
+    fn fail(&self, e: io::Error) -> ! {
+        match &self.path {
+            None if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+            None => error!("error: stdout: {}", e),
+            Some(path) => error!("error: {}: {}", path.display(), e),
+        }
+        process::exit(EXIT_FAILURE);
+    }
+}
 
-        let name = split[(depth - 1) as usize].to_string();
-        let path = split.join("/");
+/**
+ * Load a tree by scanning a directory, by reading a tree saved as JSON or
+ * as a cache file, by listing an archive, by reading a path list or the
+ * output of `tree`, from a git revision or by listing a remote directory
+ * or bucket.
+ * @param path The directory, the `.json` file, the cache file, the
+ * archive, the path list or the output of `tree`, `git:<rev>` for a
+ * revision of the current repository, `ssh://[user@]host[:port]/path` for
+ * a remote directory or `s3://bucket/prefix` for the objects of a bucket.
+ * @param base The directory the paths of a path list are anchored under.
+ * @param scan The settings of a directory scan, with the filter applied
+ * to every tree.
+ * @param stream The options to print the entries of a directory scan
+ * with as soon as they are scanned and where to, if they are.
+ * @return The tree.
+ */
+fn load_root(path: &Path, base: Option<PathBuf>, scan: &ScanOptions, mut stream: Option<(&RenderOptions, &mut Printer)>) -> io::Result<Tree> {
+    // Directories and path lists are filtered as they are read, the trees
+    // read whole once read.
+    let filter = |tree: Tree| match scan.filter.is_empty() {
+        true => tree,
+        false => tree.filtered(&scan.filter),
+    };
+    if let Some(rev) = path.to_str().and_then(|path| path.strip_prefix("git:")) {
+        Tree::from_git(Path::new("."), rev).map(filter).map_err(io::Error::other)
+    } else if let Some(url) = path.to_str().filter(|path| path.starts_with("ssh://")) {
+        Tree::from_ssh(url).map(filter).map_err(io::Error::other)
+    } else if let Some(url) = path.to_str().filter(|path| path.starts_with("s3://")) {
+        Tree::from_s3(url).map(filter).map_err(io::Error::other)
+    } else if path.is_dir() {
+        let mut tree = Tree::new();
+        tree.set_base(Some(path.canonicalize()?));
+        scan_into(&FsSource, path, scan, &mut tree, |node| {
+            if let Some((options, printer)) = &mut stream {
+                printer.print(|out| node.render_entry_to(out, options));
+            }
+        })?;
+        Ok(tree)
+    } else if Tree::is_cache(path) {
+        Tree::load_cache(path).map(filter)
+    } else if path.extension().is_some_and(|extension| extension == "json") {
+        let text = fs::read_to_string(path)?;
+        Tree::from_json(&text).map(filter).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    } else if is_archive(path) {
+        let entries = read_archive(path).map_err(io::Error::other)?;
+        // The members are not on the disk.
+        Ok(build_tree(entries.into_iter().map(|entry| (entry.path, entry.meta)).collect(), None, &scan.filter))
+    } else {
+        let lines = read_file(path)?;
+        // Trees printed by tree, rather than path lists.
+        let text = lines.join("\n");
+        if is_tree_text(&text) {
+            return Tree::from_text(&text).map(filter).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        // Absolute paths, like the output of `find /usr`, are anchored under
+        // the root of the filesystem.
+        let absolute = lines.iter().any(|line| !line.is_empty())
+            && lines.iter().all(|line| line.is_empty() || line.starts_with('/'));
+        let base = base.map(|base| if absolute { PathBuf::from("/") } else { base });
+        let lines = lines.into_iter().map(|line| (line, NodeMeta::default())).collect();
+        Ok(build_tree(lines, base, &scan.filter))
+    }
+}
 
-        println!("Adding file: {}", name);
-        println!("Path: {}", path);
+/**
+ * Get the width of the terminal from the `COLUMNS` variable set by shells.
+ * @return The width, 80 if unknown.
+ */
+fn terminal_width() -> usize {
+    env::var("COLUMNS").ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|width| *width > 0)
+        .unwrap_or(80)
+}
 
+fn main() {
+    let options = match Options::parse_with_defaults(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            error!("error: {}\n\n{}", message, cli::USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
 
-        let node = if split[(depth - 1) as usize].contains(".") {
-            Node::new_file(data, depth, path, name)
-        } else {
-            Node::new_directory(data, depth, path, name)
-        };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    rust_tree::set_verbosity(options.verbosity);
 
-        tree.insert(node);
+    let mut printer = match Printer::open(options.output_file.clone()) {
+        Ok(printer) => printer,
+        Err(e) => {
+            let path = options.output_file.as_deref().unwrap_or(Path::new("stdout"));
+            error!("error: {}: {}", path.display(), e);
+            process::exit(EXIT_FAILURE);
+        }
+    };
 
-        println!("");
-        println!("---------------------------------------------------]");
+    let base = match &options.anchor {
+        Anchor::Cwd => env::current_dir().ok(),
+        Anchor::Base(base) => Some(env::current_dir().map(|cwd| cwd.join(base)).unwrap_or(base.clone())),
+        Anchor::Virtual => None,
+    };
+
+    let scan = ScanOptions {
+        only: options.only.clone(),
+        one_file_system: options.one_file_system,
+        filter: options.filter.clone(),
+    };
+
+    if let Command::Diff(old, new) = &options.command {
+        // Both trees are compared whole.
+        let scan = ScanOptions { filter: Filter::new(), ..scan.clone() };
+        let load = |path: &Path| match load_root(path, base.clone(), &scan, None) {
+            Ok(tree) => tree,
+            Err(e) => {
+                error!("error: {}: {}", path.display(), e);
+                process::exit(EXIT_FAILURE);
+            }
+        };
+        let mut diff = load(old).diff(&load(new));
+        diff.names = options.names;
+        printer.print(|out| diff.write_to(out));
+        printer.finish();
+        return;
     }
 
+    if let Command::Scaffold(source, dir) = &options.command {
+        let tree = match load_root(source, base.clone(), &scan, None) {
+            Ok(tree) => tree,
+            Err(e) => {
+                error!("error: {}: {}", source.display(), e);
+                process::exit(EXIT_FAILURE);
+            }
+        };
+        match tree.scaffold(dir) {
+            Ok(report) => info!("Created {} directories and {} files in {}, {} already there",
+                report.directories, report.files, dir.display(), report.existing),
+            Err(e) => {
+                error!("error: {}", e);
+                process::exit(EXIT_FAILURE);
+            }
+        }
+        printer.finish();
+        return;
+    }
 
-    tree.display();
+    let roots = match &options.command {
+        Command::Show(roots) => roots,
+        Command::Diff(..) | Command::Scaffold(..) => unreachable!(),
+    };
+    let mut highlight = IgnoreRules::new();
+    for pattern in &options.highlight {
+        highlight.add(pattern);
+    }
+    if options.ignore_case {
+        highlight.ignore_case();
+    }
+    let render = RenderOptions {
+        date_style: options.date_style,
+        columns: options.columns.then(terminal_width),
+        collapse_depth: options.auto_collapse,
+        min_depth: options.min_depth,
+        max_depth: options.max_depth,
+        compact: options.compact,
+        full_path: options.full_path,
+        names: options.names,
+        print0: options.print0,
+        file_limit: options.file_limit,
+        git_status: None,
+        inodes: options.inodes,
+        devices: options.devices,
+        xattrs: options.xattr,
+        links: options.links,
+        highlight,
+        // Colors are only written to terminals, unless NO_COLOR is set.
+        color: options.color.unwrap_or_else(|| {
+            options.output_file.is_none() && io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+        }),
+    };
+    // Several trees printed as text are told apart by headers.
+    let headers = roots.len() > 1 && !options.interactive && !options.leaves && options.exec.is_none() && !options.du && options.top.is_none() && !options.ext_stats && !options.audit_perms
+        && options.outputs.is_empty();
+    let mut total = SubtreeStats::default();
+    let mut over_budget = false;
+    let mut errors: Vec<String> = Vec::new();
+    for (i, root) in roots.iter().enumerate() {
+        if headers {
+            printer.print(|out| {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{}:", root.display())
+            });
+        }
+        let (stats, over, root_errors) = show(root, base.clone(), &scan, &options, &render, &mut printer);
+        total.add(stats);
+        over_budget |= over;
+        errors.extend(root_errors.into_iter().map(|(path, e)| format!("{}: {}", root.join(path).display(), e)));
+    }
+    if headers {
+        let directories = total.directories.saturating_sub(roots.len() as u64);
+        printer.print(|out| {
+            writeln!(out)?;
+            writeln!(out, "{} roots, {} directories, {} files", roots.len(), directories, total.files)
+        });
+    }
+    printer.finish();
+    // The entries which could not be read are reported together.
+    if !errors.is_empty() {
+        let plural = if errors.len() == 1 { "entry" } else { "entries" };
+        warning!("{} {} could not be read:", errors.len(), plural);
+        for e in &errors {
+            warning!("  {}", e);
+        }
+    }
+    if over_budget {
+        process::exit(EXIT_OVER_BUDGET);
+    }
+    if !errors.is_empty() {
+        process::exit(EXIT_PARTIAL);
+    }
+}
 
-    let mainrs = tree.find_by_name("main.rs".to_string());
-    match mainrs {
-        Some(node) => {
-            node.display();
+/**
+ * Load a root and print it, export it and check its budgets as the
+ * options tell.
+ * @param root The root to show.
+ * @param base The directory the paths of a path list are anchored under.
+ * @param scan The settings of a directory scan, with the display filter.
+ * @param options The command line options.
+ * @param render The options of the text output.
+ * @param printer Where what is printed goes.
+ * @return The statistics of the printed tree, whether it is over a budget
+ * and the paths of the entries which could not be read with their errors.
+ */
+fn show(root: &Path, base: Option<PathBuf>, scan: &ScanOptions, options: &Options, render: &RenderOptions, printer: &mut Printer) -> (SubtreeStats, bool, Vec<(String, String)>) {
+    // Only directory scans can be streamed, and the disk usage, the largest
+    // entries, the extension statistics, the permissions audit, the filters,
+    // the columns, the collapsed summaries, the file limit, the full paths
+    // under the root, the depth range, the compact chains, the sort, the git
+    // statuses, the highlights, the checksums, the attributes, the
+    // duplicates, the list of files, the commands and the browser need the
+    // whole tree.
+    let stream = options.stream && !options.interactive && !options.leaves && options.exec.is_none() && options.highlight.is_empty() && !options.git_status && options.checksum.is_none() && !options.attrs
+        && !options.find_duplicates && !options.du && options.top.is_none() && !options.ext_stats && !options.audit_perms && !options.columns && !options.full_path
+        && options.file_limit.is_none() && options.sort.is_none()
+        && options.auto_collapse.is_none() && options.min_depth == 0 && options.max_depth.is_none() && options.filter.is_empty() && options.outputs.is_empty() && root.is_dir();
+    // The outputs written to files are exports, with their own filter, so
+    // the tree is then loaded whole and the display filter applied to a
+    // copy. Otherwise what the display filter drops is never loaded.
+    let exporting = options.save_cache.is_some() || options.outputs.iter().any(|output| output.path.is_some());
+    let whole;
+    let scan = match exporting {
+        true => {
+            whole = ScanOptions { filter: Filter::new(), ..scan.clone() };
+            &whole
         },
-        None => {
-            println!("Could not find main.rs");
+        false => scan,
+    };
+    let mut tree = match load_root(root, base, scan, stream.then_some((render, &mut *printer))) {
+        Ok(tree) => tree,
+        Err(e) => {
+            error!("error: {}: {}", root.display(), e);
+            process::exit(EXIT_FAILURE);
+        }
+    };
+    if let Some(order) = options.sort {
+        tree.sort_by(|a, b| order.compare(a, b));
+    }
+    // Duplicates are found from the checksums.
+    let checksum = options.checksum.or(options.find_duplicates.then_some(ChecksumAlgorithm::Sha256));
+    match checksum {
+        Some(algorithm) if root.is_dir() => {
+            for e in tree.compute_checksums(algorithm) {
+                warning!("{}", e);
+            }
+        },
+        Some(_) => warning!("{}: checksums are only computed for directory scans", root.display()),
+        None => {},
+    }
+    if options.attrs {
+        match root.is_dir() {
+            true => if let Err(e) = tree.load_xattrs() {
+                warning!("{}: {}", root.display(), e);
+            },
+            false => warning!("{}: attributes are only read for directory scans", root.display()),
+        }
+    }
+    let with_git;
+    let render = match options.git_status && root.is_dir() {
+        true => match GitStatuses::load(root) {
+            Ok(statuses) => {
+                with_git = RenderOptions { git_status: Some(statuses), ..render.clone() };
+                &with_git
+            },
+            Err(e) => {
+                warning!("{}: {}", root.display(), e);
+                render
+            }
+        },
+        false => render,
+    };
+
+    let exported = (exporting && !options.export_filter.is_empty()).then(|| tree.filtered(&options.export_filter));
+    let exported = exported.as_ref().unwrap_or(&tree);
+    if let Some(cache) = &options.save_cache {
+        if let Err(e) = exported.save_cache(cache) {
+            error!("error: {}: {}", cache.display(), e);
+            process::exit(EXIT_FAILURE);
+        }
+    }
+
+    let filtered = (exporting && !options.filter.is_empty()).then(|| tree.filtered(&options.filter));
+    let shown = filtered.as_ref().unwrap_or(&tree);
+    if options.interactive {
+        match browse(shown) {
+            Ok(Some(path)) => printer.print(|out| writeln!(out, "{}", path)),
+            Ok(None) => {},
+            Err(e) => {
+                error!("error: terminal: {}", e);
+                process::exit(EXIT_FAILURE);
+            }
+        }
+    } else if options.audit_perms {
+        let mut report = shown.audit_permissions();
+        report.names = render.names;
+        if report.is_empty() {
+            info!("No permission issues found");
+        }
+        printer.print(|out| report.write_to(out));
+    } else if let Some(count) = options.top {
+        let mut report = shown.largest_entries(count, options.count_links);
+        report.date_style = options.date_style;
+        report.names = render.names;
+        printer.print(|out| report.write_to(out));
+    } else if options.ext_stats {
+        let report = shown.extension_stats();
+        printer.print(|out| report.write_to(out));
+    } else if options.du {
+        let mut report = shown.disk_usage(options.count_links);
+        report.date_style = options.date_style;
+        report.names = render.names;
+        printer.print(|out| report.write_to(out));
+    } else if let Some(command) = &options.exec {
+        shown.exec_files(command, options.jobs, |result| match result.output {
+            Ok(output) => {
+                printer.print(|out| out.write_all(&output.stdout));
+                let _ = io::stderr().write_all(&output.stderr);
+                if !output.status.success() {
+                    warning!("{}: {}: {}", command[0], result.path, output.status);
+                }
+            },
+            Err(e) => {
+                error!("error: {}: {}", command[0], e);
+                process::exit(EXIT_FAILURE);
+            }
+        });
+    } else if options.leaves {
+        printer.print(|out| shown.write_leaves(out, render));
+    } else if options.outputs.is_empty() && !stream {
+        printer.print(|out| shown.render_to(out, render));
+    }
+    for output in &options.outputs {
+        match &output.path {
+            Some(path) => {
+                if let Err(e) = output.write(exported, render) {
+                    error!("error: {}: {}", path.display(), e);
+                    process::exit(EXIT_FAILURE);
+                }
+            },
+            None => printer.print(|out| output.format.write_to(shown, out, render)),
+        }
+    }
+    if options.find_duplicates {
+        let mut report = shown.find_duplicates();
+        report.names = render.names;
+        printer.print(|out| {
+            writeln!(out)?;
+            report.write_to(out)
+        });
+    }
+    if options.find_duplicates && options.nfc {
+        for paths in shown.find_duplicate_names() {
+            printer.print(|out| {
+                writeln!(out)?;
+                writeln!(out, "{} entries with the same name once composed", paths.len())?;
+                paths.iter().try_for_each(|path| writeln!(out, "  {}", render.names.apply(path)))
+            });
+        }
+    }
+
+    let mut over_budget = false;
+    for report in shown.check_budgets(&options.budgets) {
+        match report.size {
+            Some(size) if report.is_over() => {
+                over_budget = true;
+                printer.print(|out| writeln!(
+                    out,
+                    "Over budget: {} is {} for a budget of {}",
+                    report.budget.path, format_size(size), format_size(report.budget.limit)
+                ));
+            },
+            Some(_) => {},
+            None => warning!("Budget path {} is not in the tree", report.budget.path),
         }
     }
+    (shown.stats(), over_budget, tree.errors())
 }
@@ -0,0 +1,69 @@
+//! Writing trees as Mermaid flowcharts, to embed in markdown.
+//!
+//! ```text
+//! flowchart TD
+//!   n0["/srv/data/"]
+//!   n1("main.rs")
+//!   n0 --> n1
+//! ```
+
+use crate::node::*;
+
+/// Escapes the characters of `text` that end or break a Mermaid label.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '"' => escaped.push_str("#quot;"),
+      '#' => escaped.push_str("#35;"),
+      '<' => escaped.push_str("#lt;"),
+      '>' => escaped.push_str("#gt;"),
+      '\n' => escaped.push(' '),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+impl Tree {
+  /// Gets the tree, including mounted trees, as a top-down Mermaid
+  /// flowchart whose directories are boxes ending with a slash and files
+  /// are rounded boxes, each linked from its parent.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  ///
+  /// let mermaid = tree.to_mermaid();
+  /// assert!(mermaid.starts_with("flowchart TD\n"));
+  /// assert!(mermaid.contains("  n1[\"src/\"]\n"));
+  /// assert!(mermaid.contains("  n2(\"main.rs\")\n  n1 --> n2\n"));
+  /// ```
+  pub fn to_mermaid(&self) -> String {
+    let mut mermaid = String::from("flowchart TD\n");
+    // The ids of the directories leading to the current node.
+    let mut ids: Vec<usize> = Vec::new();
+    let mut next = 0;
+    self.walk(|_, node, offset| {
+      let depth = (node.depth + offset) as usize;
+      let name = escape(&node.name);
+      match node.node_type {
+        NodeType::File() => mermaid.push_str(&format!("  n{}(\"{}\")\n", next, name)),
+        NodeType::Directory() => mermaid.push_str(&format!("  n{}[\"{}/\"]\n", next, name.trim_end_matches('/'))),
+      }
+      ids.truncate(depth);
+      if let Some(parent) = ids.last() {
+        mermaid.push_str(&format!("  n{} --> n{}\n", parent, next));
+      }
+      ids.push(next);
+      next += 1;
+      true
+    });
+    mermaid
+  }
+}
@@ -1,5 +1,19 @@
-/// Represents node data.
-#[derive(Clone, Debug)]
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::slice;
+use std::time::SystemTime;
+
+use crate::ignore::glob_match;
+use crate::log::{debug, warning};
+use crate::render::RenderOptions;
+
+/// Represents node data, the default data of the nodes: the path as it
+/// was given.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NodeData {
   /// The default path of the node.
   pub default_path: String,
@@ -7,28 +21,210 @@ pub struct NodeData {
   pub length: u64,
 }
 
-#[derive(Clone, Debug)]
+/// Represents optional filesystem metadata of a node.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct NodeMeta {
+  /// The size of the entry in bytes.
+  pub size: Option<u64>,
+  /// The size of the entry in bytes once compressed, for the members of
+  /// compressed archives.
+  pub compressed_size: Option<u64>,
+  /// The last modification time of the entry.
+  pub modified: Option<SystemTime>,
+  /// The device and inode numbers of the entry.
+  pub inode: Option<(u64, u64)>,
+  /// The number of hard links to the entry.
+  pub links: Option<u64>,
+  /// The unix mode of the entry, with its type and permission bits.
+  pub mode: Option<u32>,
+  /// The user and group owning the entry.
+  pub owner: Option<(u32, u32)>,
+  /// The checksum of the content of a file, like `sha256:<hex digest>`.
+  pub checksum: Option<String>,
+  /// Where the entry points to, for symbolic links.
+  pub link: Option<Link>,
+  /// The error met reading the entry, like a directory which could not be
+  /// listed.
+  pub error: Option<String>,
+  /// The names of the extended attributes of the entry, its access control
+  /// lists included, when they are read.
+  pub xattrs: Vec<String>,
+  /// The attributes tagging the entry, like the team owning it, see
+  /// `Node::set_attr`.
+  pub attrs: BTreeMap<String, String>,
+}
+
+/// Represents the target of a symbolic link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+  /// The path the link points to, as written in the link.
+  pub target: String,
+  /// Whether the target is missing or cannot be reached, like a link loop
+  /// or a directory which cannot be searched.
+  pub broken: bool,
+}
+
+/// Aggregate statistics of a node and all of its descendants.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct SubtreeStats {
+  /// The number of files.
+  pub files: u64,
+  /// The number of directories.
+  pub directories: u64,
+  /// The total size in bytes.
+  pub size: u64,
+  /// The newest modification time.
+  pub newest: Option<SystemTime>,
+  /// The number of levels below the node, the max depth of a tree for its
+  /// root.
+  pub max_depth: u64,
+  /// The size in bytes of the largest file, see `Tree::largest_file`.
+  pub largest: u64,
+}
+
+impl SubtreeStats {
+  /// Adds the statistics of another subtree to these ones.
+  pub fn add(&mut self, other: SubtreeStats) {
+    self.files += other.files;
+    self.directories += other.directories;
+    self.size += other.size;
+    self.newest = match (self.newest, other.newest) {
+      (Some(a), Some(b)) => Some(a.max(b)),
+      (a, b) => a.or(b),
+    };
+    self.max_depth = self.max_depth.max(other.max_depth);
+    self.largest = self.largest.max(other.largest);
+  }
+
+  /// Adds the statistics of a child of the node, one level below it.
+  fn add_child(&mut self, mut child: SubtreeStats) {
+    child.max_depth += 1;
+    self.add(child);
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum NodeType {
     File(),
     Directory(),
 }
 
+/// Represents a file or directory of a tree, carrying data of the type `T`
+/// chosen by the user of the tree.
+///
+/// Nodes built from paths or by a scan carry `NodeData`. The lookups, the
+/// traversals and the statistics work with any data, while building a
+/// tree from paths, moving and renaming nodes and rendering need it.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::prelude::*;
+///
+/// struct Review {
+///   approved: bool,
+/// }
+///
+/// let mut src = Node::new_directory(Review { approved: true }, 1, "src".to_string(), "src".to_string());
+/// src.children.push(Node::new_file(Review { approved: false }, 2, "src/main.rs".to_string(), "main.rs".to_string()));
+/// let mut tree: Tree<Review> = Tree::default();
+/// tree.root = Some(Node::new_directory(Review { approved: true }, 0, "/".to_string(), "root".to_string()));
+/// tree.root.as_mut().unwrap().children.push(src);
+///
+/// assert!(!tree.find_by_path("src/main.rs").unwrap().data.approved);
+/// assert_eq!(tree.find_all(|node| node.data.approved).len(), 2);
+/// ```
 #[derive(Clone, Debug)]
-pub struct Node {
+pub struct Node<T = NodeData> {
   pub node_type: NodeType,
-  pub data: NodeData,
+  pub data: T,
   pub depth: u64,
   pub path: String,
   pub name: String,
-  pub children: Vec<Node>,
+  /// The children of a directory. Changing them directly leaves stale
+  /// statistics, see `Node::stats`.
+  pub children: Vec<Node<T>>,
+  /// The metadata of the entry. Changing its size directly leaves stale
+  /// statistics, see `Node::stats`.
+  pub meta: NodeMeta,
+  /// Cached statistics of the subtree, see `Node::stats`.
+  stats: Cell<Option<SubtreeStats>>,
 }
 
-impl From<Node> for Option<Box<Node>> {
-    fn from(node: Node) -> Self {
+impl<T> From<Node<T>> for Option<Box<Node<T>>> {
+    fn from(node: Node<T>) -> Self {
         Some(Box::new(node))
     }
 }
 
+impl<T> Drop for Node<T> {
+  /// Drops the descendants one at a time, as dropping them recursively
+  /// would overflow the stack on very deep trees.
+  fn drop(&mut self) {
+    let mut stack = mem::take(&mut self.children);
+    while let Some(mut node) = stack.pop() {
+      stack.append(&mut node.children);
+    }
+  }
+}
+
+impl<T: PartialEq> PartialEq for Node<T> {
+  /// Compares the nodes and their descendants, their children in order,
+  /// see `Node::eq_unordered`. The cached statistics are not compared.
+  fn eq(&self, other: &Self) -> bool {
+    self.eq_with(other, false)
+  }
+}
+
+impl<T: PartialEq> Node<T> {
+  /// Compares the nodes and their descendants like `==`, the children of
+  /// a directory being matched by name whatever their order, like two
+  /// scans of the same directory.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut a = Node::new_root();
+  /// a.children.push(Node::from_path("main.rs").unwrap());
+  /// a.children.push(Node::from_path("lib.rs").unwrap());
+  /// let mut b = a.clone();
+  /// b.children.reverse();
+  ///
+  /// assert!(a != b);
+  /// assert!(a.eq_unordered(&b));
+  /// ```
+  pub fn eq_unordered(&self, other: &Node<T>) -> bool {
+    self.eq_with(other, true)
+  }
+
+  fn eq_with(&self, other: &Node<T>, unordered: bool) -> bool {
+    let mut stack = vec![(self, other)];
+    while let Some((a, b)) = stack.pop() {
+      if a.node_type != b.node_type || a.name != b.name || a.path != b.path || a.depth != b.depth
+        || a.data != b.data || a.meta != b.meta || a.children.len() != b.children.len() {
+        return false;
+      }
+
+      if unordered {
+        let by_name: HashMap<&str, &Node<T>> = b.children.iter().map(|child| (child.name.as_str(), child)).collect();
+        for child in &a.children {
+          match by_name.get(child.name.as_str()) {
+            Some(other_child) => stack.push((child, other_child)),
+            None => return false,
+          }
+        }
+      } else {
+        stack.extend(a.children.iter().zip(&b.children));
+      }
+    }
+    true
+  }
+}
+
 impl Node {
   /// Creates a new root node directory.
   /// 
@@ -39,7 +235,7 @@ impl Node {
   /// # Examples
   /// 
   /// ```
-  /// use node::*;
+  /// use rust_tree::prelude::*;
   /// 
   /// let root = Node::new_root();
   /// assert_eq!(root.depth, 0);
@@ -57,10 +253,160 @@ impl Node {
       path: "/".to_string(),
       name: "root".to_string(),
       children: Vec::new(),
+      meta: NodeMeta::default(),
+      stats: Cell::new(None),
+    }
+  }
+
+  /// Creates a node from a line of a path list, like `src/main.rs` or
+  /// `./vscode/`.
+  ///
+  /// Lines ending with a slash are directories, other lines are files, see
+  /// `Node::from_path_with` to classify them otherwise. Absolute lines,
+  /// like the output of `find /usr`, are rooted at `/`, and repeated
+  /// slashes are read as one.
+  ///
+  /// # Returns
+  ///
+  /// The node, or `None` if the line holds no path or only the root.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let node = Node::from_path("./src/main.rs").unwrap();
+  /// assert_eq!(node.depth(), 2);
+  /// assert_eq!(node.path(), "src/main.rs");
+  /// assert_eq!(node.name(), "main.rs");
+  ///
+  /// assert_eq!(Node::from_path("/usr//bin/").unwrap().path(), "usr/bin");
+  /// assert!(Node::from_path("/").is_none());
+  /// ```
+  pub fn from_path(line: &str) -> Option<Node> {
+    Node::from_path_with(line, |_| NodeType::File())
+  }
+
+  /// Creates a node from a line of a path list like `Node::from_path`,
+  /// `classify` giving the type of the lines without a trailing slash from
+  /// their normalized path.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let is_dir = |path: &str| match path {
+  ///   "v1.2" | ".git" => NodeType::Directory(),
+  ///   _ => NodeType::File(),
+  /// };
+  /// assert_eq!(Node::from_path_with(".git", is_dir).unwrap().node_type, NodeType::Directory());
+  /// assert_eq!(Node::from_path_with("Makefile", is_dir).unwrap().node_type, NodeType::File());
+  /// assert_eq!(Node::from_path_with("my.folder/", is_dir).unwrap().node_type, NodeType::Directory());
+  /// ```
+  pub fn from_path_with<F: FnOnce(&str) -> NodeType>(line: &str, classify: F) -> Option<Node> {
+    let default_path = line.to_string();
+    let is_dir = line.ends_with('/');
+    let mut split: Vec<&str> = line.split("/").collect();
+
+    // Remove the first element if it is a dot.
+    if split[0] == "." {
+      split.remove(0);
+    }
+
+    // Remove the empty elements, before the root of absolute lines, after
+    // directories and between repeated slashes.
+    split.retain(|part| !part.is_empty());
+    if split.is_empty() {
+      return None;
+    }
+
+    let data = NodeData {
+      length: default_path.len() as u64,
+      default_path,
+    };
+
+    let depth = split.len() as u64;
+
+    debug!("Split: {:?}", split);
+
+    let name = split[(depth - 1) as usize].to_string();
+    let path = split.join("/");
+
+    debug!("Adding file: {}", name);
+    debug!("Path: {}", path);
+
+    let node_type = if is_dir { NodeType::Directory() } else { classify(&path) };
+    let node = match node_type {
+      NodeType::File() => Node::new_file(data, depth, path, name),
+      NodeType::Directory() => Node::new_directory(data, depth, path, name),
+    };
+    Some(node)
+  }
+
+  /// Updates the depth and path of the node and its descendants for it to
+  /// be a child of the directory at `parent_path` and `parent_depth`.
+  pub(crate) fn rebase(&mut self, parent_path: &str, parent_depth: u64) {
+    self.rebase_entry(parent_path, parent_depth);
+    let mut stack = vec![self];
+    while let Some(node) = stack.pop() {
+      for child in &mut node.children {
+        child.rebase_entry(&node.path, node.depth);
+      }
+      stack.extend(node.children.iter_mut());
+    }
+  }
+
+  /// Updates the depth and path of this node only, see `Node::rebase`.
+  pub(crate) fn rebase_entry(&mut self, parent_path: &str, parent_depth: u64) {
+    let old_path = mem::take(&mut self.path);
+    self.depth = parent_depth + 1;
+    self.path = if parent_depth == 0 {
+      self.name.clone()
+    } else {
+      format!("{}/{}", parent_path, self.name)
+    };
+
+    // The default path is the path as it was given, like `./src/`: only
+    // the part matching the old path is replaced.
+    if let Some(i) = self.data.default_path.rfind(&old_path) {
+      let end = i + old_path.len();
+      let rest = &self.data.default_path[end..];
+      if rest.is_empty() || rest == "/" {
+        self.data.default_path = format!("{}{}{}", &self.data.default_path[..i], self.path, rest);
+        self.data.length = self.data.default_path.len() as u64;
+      }
+    }
+  }
+
+  /// Gets the heap memory held by the node itself, in bytes.
+  fn capacity(&self) -> usize {
+    self.children.capacity() * mem::size_of::<Node>()
+      + self.path.capacity()
+      + self.name.capacity()
+      + self.data.default_path.capacity()
+  }
+
+  /// Shrinks the buffers of the node and its descendants, returning the
+  /// number of bytes reclaimed.
+  fn compact(&mut self) -> usize {
+    let mut reclaimed = 0;
+    let mut stack = vec![self];
+    while let Some(node) = stack.pop() {
+      let before = node.capacity();
+      node.children.shrink_to_fit();
+      node.path.shrink_to_fit();
+      node.name.shrink_to_fit();
+      node.data.default_path.shrink_to_fit();
+      reclaimed += before - node.capacity();
+      stack.extend(node.children.iter_mut());
     }
+    reclaimed
   }
+}
 
-  pub fn new_file(data: NodeData, depth: u64, path: String, name: String) -> Node {
+impl<T> Node<T> {
+  pub fn new_file(data: T, depth: u64, path: String, name: String) -> Node<T> {
     Node {
       node_type: NodeType::File(),
       data,
@@ -68,10 +414,12 @@ impl Node {
       path,
       name,
       children: Vec::new(),
+      meta: NodeMeta::default(),
+      stats: Cell::new(None),
     }
   }
 
-  pub fn new_directory(data: NodeData, depth: u64, path: String, name: String) -> Node {
+  pub fn new_directory(data: T, depth: u64, path: String, name: String) -> Node<T> {
     Node {
       node_type: NodeType::Directory(),
       data,
@@ -79,6 +427,8 @@ impl Node {
       path,
       name,
       children: Vec::new(),
+      meta: NodeMeta::default(),
+      stats: Cell::new(None),
     }
   }
 
@@ -91,7 +441,7 @@ impl Node {
   /// # Examples
   /// 
   /// ```
-  /// use node::*;
+  /// use rust_tree::prelude::*;
   /// 
   /// let root = Node::new_root();
   /// assert_eq!(root.depth(), 0);
@@ -105,7 +455,7 @@ impl Node {
   /// # Example
   /// 
   /// ```
-  /// use node::*;
+  /// use rust_tree::prelude::*;
   /// 
   /// let root = Node::new_root();
   /// root.display();
@@ -113,68 +463,196 @@ impl Node {
   /// 
   /// # Output
   /// 
-  /// ```
-  /// Directory: /
+  /// ```text
+  /// Directory: root
   ///  Children: 0
+  ///  Path: /
+  ///  Depth: 0
   /// ```
   /// 
   /// # Example
   /// 
   /// ```
-  /// use node::*;
+  /// use rust_tree::prelude::*;
   /// 
-  /// let root = Node::new_root();
-  /// let mut src = Node::Directory(NodeDirectory::new(
+  /// let mut src = Node::new_directory(
   ///  NodeData {
   ///   default_path: "src/".to_string(),
   ///   length: 4
   ///  },
   ///  1,
-  ///  "src/".to_string(),
+  ///  "src".to_string(),
   ///  "src".to_string()
-  /// ));
+  /// );
   /// 
-  /// src.add_child(Node::File(NodeFile::new(
+  /// src.children.push(Node::new_file(
   ///  NodeData {
   ///   default_path: "src/main.rs".to_string(),
   ///   length: 11
   ///  },
   ///  2,
-  ///  "main.rs".to_string(),
+  ///  "src/main.rs".to_string(),
   ///  "main.rs".to_string()
-  /// )));
+  /// ));
   /// 
-  /// root.insert(src);
-  /// root.display();
+  /// src.display();
   /// ```
   /// 
   /// # Output
   /// 
-  /// ```
-  /// Directory: /
-  ///  Children: 1
-  ///  Directory: src/
-  ///   Children: 1
-  ///   File: main.rs
+  /// ```text
+  ///   Directory: src
+  ///    Children: 1
+  ///    Path: src
+  ///    Depth: 1
+  ///     File: main.rs
+  ///      Path: src/main.rs
+  ///      Depth: 2
   /// ```
   pub fn display(&self) {
-    let ds = "  ".repeat(self.depth as usize);
+    self.write_to(&mut io::stdout()).expect("Could not write to stdout");
+  }
+
+  /// Writes the node and its children to `out`, in the format of
+  /// `Node::display`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut out = Vec::new();
+  /// Node::new_root().write_to(&mut out).unwrap();
+  /// assert!(String::from_utf8(out).unwrap().starts_with("Directory: root"));
+  /// ```
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let mut stack = vec![self];
+    while let Some(node) = stack.pop() {
+      node.write_entry(out, node.depth, &node.name, &node.path)?;
+      stack.extend(node.children.iter().rev());
+    }
+    Ok(())
+  }
+
+  /// Writes this node only to `out`, without its children, in the format
+  /// of `Node::display`.
+  pub fn write_entry_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    self.write_entry(out, self.depth, &self.name, &self.path)
+  }
+
+  /// Copies this node only, without its children.
+  pub(crate) fn clone_entry(&self) -> Node<T> where T: Clone {
+    Node {
+      node_type: self.node_type.clone(),
+      data: self.data.clone(),
+      depth: self.depth,
+      path: self.path.clone(),
+      name: self.name.clone(),
+      children: Vec::new(),
+      meta: self.meta.clone(),
+      stats: Cell::new(None),
+    }
+  }
+
+  /// Writes this node only, indented by `level` levels, named `label` and
+  /// with the path `path`.
+  pub(crate) fn write_entry<W: Write>(&self, out: &mut W, level: u64, label: &str, path: &str) -> io::Result<()> {
+    let ds = "  ".repeat(level as usize);
     match self.node_type {
       NodeType::File() => {
-        println!("{}File: {}", ds, self.name);
-        println!("{} Path: {}", ds, self.path);
-        println!("{} Depth: {}", ds, self.depth);
+        writeln!(out, "{}File: {}", ds, label)?;
+        writeln!(out, "{} Path: {}", ds, path)?;
+        writeln!(out, "{} Depth: {}", ds, self.depth)?;
       },
       NodeType::Directory() => {
-        println!("{}Directory: {}", ds, self.name);
-        println!("{} Children: {}", ds, self.children.len());
-        println!("{} Path: {}", ds, self.path);
-        println!("{} Depth: {}", ds, self.depth);
+        writeln!(out, "{}Directory: {}", ds, label)?;
+        writeln!(out, "{} Children: {}", ds, self.children.len())?;
+        writeln!(out, "{} Path: {}", ds, path)?;
+        writeln!(out, "{} Depth: {}", ds, self.depth)?;
+      },
+    }
+    Ok(())
+  }
 
-        for child in &self.children {
-          child.display();
+  /// Gets the aggregate statistics of the node and its descendants.
+  ///
+  /// The result is cached on the node, and on every node below it, until
+  /// it is invalidated. The `Tree` methods changing nodes clear the caches
+  /// of the changed nodes and of the nodes above them, including the ones
+  /// handing out mutable nodes like `Tree::find_by_path_mut` and
+  /// `Tree::visit_mut`. The fields of the nodes and `Tree::root` are
+  /// public though, and changing `children` or `meta` directly does not
+  /// clear anything: `Node::invalidate_stats` must then be called on the
+  /// changed node and on every node above it, or the statistics are stale.
+  /// Trees mounted below the node are not accounted, see `Tree::stats`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// assert_eq!(tree.stats().files, 1);
+  ///
+  /// // The node handed out and its ancestors are invalidated.
+  /// tree.find_by_path_mut("src").unwrap().children.push(Node::from_path("src/lib.rs").unwrap());
+  /// assert_eq!(tree.stats().files, 2);
+  ///
+  /// // Direct changes are not noticed until invalidated.
+  /// let root = tree.root.as_mut().unwrap();
+  /// root.children.push(Node::from_path("README.md").unwrap());
+  /// assert_eq!(root.stats().files, 2);
+  /// root.invalidate_stats();
+  /// assert_eq!(root.stats().files, 3);
+  /// ```
+  pub fn stats(&self) -> SubtreeStats {
+    // A node is pushed back above its children, so that their statistics
+    // are known when it is popped again.
+    let mut stack = vec![(self, false)];
+    while let Some((node, children_done)) = stack.pop() {
+      if node.stats.get().is_some() {
+        continue;
+      }
+
+      if children_done {
+        let mut stats = node.own_stats();
+        for child in &node.children {
+          stats.add_child(child.stats.get().unwrap_or_default());
         }
-      },
+        node.stats.set(Some(stats));
+      } else {
+        stack.push((node, true));
+        stack.extend(node.children.iter().map(|child| (child, false)));
+      }
+    }
+    self.stats.get().unwrap_or_default()
+  }
+
+  /// Drops the cached statistics of the node, not the ones of the nodes
+  /// below it.
+  ///
+  /// Must be called on the node and its ancestors when the node is mutated
+  /// directly rather than through the `Tree` API, see `Node::stats`.
+  pub fn invalidate_stats(&self) {
+    self.stats.set(None);
+  }
+
+  /// Gets the statistics of this node alone.
+  fn own_stats(&self) -> SubtreeStats {
+    let (files, directories) = match self.node_type {
+      NodeType::File() => (1, 0),
+      NodeType::Directory() => (0, 1),
+    };
+    let size = self.meta.size.unwrap_or(0);
+    SubtreeStats {
+      files,
+      directories,
+      size,
+      newest: self.meta.modified,
+      max_depth: 0,
+      largest: if files == 1 { size } else { 0 },
     }
   }
 
@@ -187,7 +665,7 @@ impl Node {
   /// # Examples
   /// 
   /// ```
-  /// use node::*;
+  /// use rust_tree::prelude::*;
   /// 
   /// let root = Node::new_root();
   /// assert_eq!(root.name(), "root");
@@ -199,101 +677,2022 @@ impl Node {
   pub fn path(&self) -> String {
     self.path.clone()
   }
+
+  /// Tags the node with the attribute `key` set to `value`, replacing its
+  /// previous value.
+  ///
+  /// # Returns
+  ///
+  /// The previous value of the attribute, if any.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut node = Node::from_path("src/main.rs").unwrap();
+  /// node.set_attr("owner", "core");
+  /// assert_eq!(node.set_attr("license", "MIT"), None);
+  /// assert_eq!(node.get_attr("owner"), Some("core"));
+  /// assert_eq!(node.get_attr("reviewed"), None);
+  /// ```
+  pub fn set_attr(&mut self, key: &str, value: &str) -> Option<String> {
+    self.meta.attrs.insert(key.to_string(), value.to_string())
+  }
+
+  /// Gets the value of the attribute `key` of the node.
+  pub fn get_attr(&self, key: &str) -> Option<&str> {
+    self.meta.attrs.get(key).map(String::as_str)
+  }
+
+  /// Removes the attribute `key` from the node, returning its value.
+  pub fn remove_attr(&mut self, key: &str) -> Option<String> {
+    self.meta.attrs.remove(key)
+  }
 }
 
-pub struct Tree {
-  pub root: Option<Node>,
+/// What to do when a path exists in both trees being merged, and is not a
+/// directory in both of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+  /// Keep the node of the tree being merged into.
+  #[default]
+  KeepFirst,
+  /// Keep the node of the tree being merged.
+  KeepLast,
+  /// Fail the merge, leaving the tree untouched.
+  Error,
 }
 
-impl Tree {
-  pub fn new() -> Tree {
+/// Represents how `Tree::insert_with` handles a node whose path is already
+/// in the tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+  /// Merge a directory into the existing one, like `Tree::merge` with
+  /// `ConflictPolicy::KeepLast`, and replace other nodes.
+  #[default]
+  Merge,
+  /// Keep the existing node and drop the new one.
+  Skip,
+  /// Fail the insertion, leaving the tree untouched.
+  Error,
+}
+
+/// A step of a route from the root of a tree to one of its nodes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Step {
+  /// Go to the child at this index.
+  Child(usize),
+  /// Go to the root child at this index of the tree mounted on the node.
+  Mount(usize),
+}
+
+/// The trees mounted under the directories of a tree, keyed by the mount
+/// path.
+type Mounts<T> = HashMap<String, Tree<T>>;
+
+/// Compares two names or paths whatever their case.
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+  a.chars().flat_map(char::to_lowercase).eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Represents a tree of nodes carrying data of the type `T`, `NodeData` by
+/// default, see `Node`.
+pub struct Tree<T = NodeData> {
+  /// The root directory. Changing its nodes directly leaves stale
+  /// statistics, see `Node::stats`.
+  pub root: Option<Node<T>>,
+  /// Trees mounted under a directory of this tree, keyed by the mount path.
+  pub(crate) mounts: Mounts<T>,
+  /// The directory the relative paths of the nodes are anchored under, or
+  /// `None` for a virtual root.
+  pub(crate) base: Option<PathBuf>,
+  /// The routes to the nodes keyed by their path, mount paths included,
+  /// see `Tree::invalidate_index`.
+  index: RefCell<Option<HashMap<String, Vec<Step>>>>,
+}
+
+impl<T> Default for Tree<T> {
+  fn default() -> Self {
     Tree {
       root: None,
+      mounts: HashMap::new(),
+      base: None,
+      index: RefCell::new(None),
     }
   }
+}
 
-  pub fn display(&self) {
-    match &self.root {
-      Some(root) => {
-        root.display();
-      },
-      None => {
-        println!("No root node.");
-      },
+impl<T: Clone> Clone for Tree<T> {
+  /// Copies the nodes and the mounted trees, the path index being rebuilt
+  /// on the first lookup.
+  fn clone(&self) -> Self {
+    Tree {
+      root: self.root.clone(),
+      mounts: self.mounts.clone(),
+      base: self.base.clone(),
+      index: RefCell::new(None),
     }
   }
+}
 
-  pub fn insert(&mut self, node: Node) {
-    match &mut self.root {
-      Some(root) => {
-        Tree::insert_recursive(root, node);
-      },
-      None => {
-        self.root = Some(Node::new_root().into());
-        self.insert(node);
-      },
+impl<T: PartialEq> PartialEq for Tree<T> {
+  /// Compares the bases, the nodes and the mounted trees, see
+  /// `Tree::eq_unordered`.
+  fn eq(&self, other: &Self) -> bool {
+    self.base == other.base && self.root == other.root && self.mounts == other.mounts
+  }
+}
+
+impl<T: PartialEq> Tree<T> {
+  /// Compares the trees like `==`, the children of a directory being
+  /// matched by name whatever their order, see `Node::eq_unordered`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut a = Tree::new();
+  /// a.insert(Node::from_path("main.rs").unwrap());
+  /// a.insert(Node::from_path("lib.rs").unwrap());
+  /// let mut b = Tree::new();
+  /// b.insert(Node::from_path("lib.rs").unwrap());
+  /// b.insert(Node::from_path("main.rs").unwrap());
+  ///
+  /// assert!(a != b);
+  /// assert!(a.eq_unordered(&b));
+  /// ```
+  pub fn eq_unordered(&self, other: &Tree<T>) -> bool {
+    let roots = match (&self.root, &other.root) {
+      (Some(root), Some(other_root)) => root.eq_unordered(other_root),
+      (root, other_root) => root.is_none() && other_root.is_none(),
+    };
+    roots && self.base == other.base && self.mounts.len() == other.mounts.len()
+      && self.mounts.iter().all(|(path, mounted)| {
+        other.mounts.get(path).is_some_and(|other_mounted| mounted.eq_unordered(other_mounted))
+      })
+  }
+}
+
+impl Tree {
+  pub fn new() -> Tree {
+    Tree::default()
+  }
+
+  /// Creates a tree anchored under `base` like `Tree::set_base`, whose root
+  /// is named `name` rather than after the base.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::{Path, PathBuf};
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::with_root(".", Some(PathBuf::from("/srv/data")));
+  /// tree.insert(Node::from_path("src/").unwrap());
+  ///
+  /// let root = tree.root.as_ref().unwrap();
+  /// assert_eq!(root.name(), ".");
+  /// assert_eq!(tree.absolute_path(root), Path::new("/srv/data"));
+  /// ```
+  pub fn with_root(name: &str, base: Option<PathBuf>) -> Tree {
+    let mut tree = Tree::new();
+    tree.base = base;
+    tree.root_or_insert().name = name.to_string();
+    tree
+  }
+
+  /// Anchors the paths of the tree under the directory `base`, or under a
+  /// virtual root if `None`. The root node is labelled with the base.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::{Path, PathBuf};
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.set_base(Some(PathBuf::from("/srv/data")));
+  /// tree.insert(Node::from_path("src/").unwrap());
+  ///
+  /// assert_eq!(tree.root.as_ref().unwrap().name(), "/srv/data");
+  /// let src = tree.find_by_path("src").unwrap();
+  /// assert_eq!(tree.absolute_path(src), Path::new("/srv/data/src"));
+  /// ```
+  pub fn set_base(&mut self, base: Option<PathBuf>) {
+    self.base = base;
+    if let Some(root) = &mut self.root {
+      Tree::label_root(root, self.base.as_deref());
     }
   }
 
-  pub fn find_by_name(&self, name: String) -> Option<Node> {
-    match &self.root {
-      Some(root) => {
-        Tree::find_by_name_recursive(root, name)
-      },
-      None => {
-        None
-      },
+  /// Names the root node after `base`.
+  fn label_root(root: &mut Node, base: Option<&Path>) {
+    let label = match base {
+      Some(base) => base.to_string_lossy().into_owned(),
+      None => "root".to_string(),
+    };
+    root.data.default_path = match base {
+      Some(_) => label.clone(),
+      None => "/".to_string(),
+    };
+    root.data.length = root.data.default_path.len() as u64;
+    root.name = label;
+  }
+
+  /// Normalizes a user supplied path so it can be compared to `Node::path`.
+  ///
+  /// Leading `./` and surrounding slashes are stripped, the empty path is
+  /// the root path `/`.
+  pub(crate) fn normalize_path(path: &str) -> String {
+    let path = path.strip_prefix("./").unwrap_or(path).trim_matches('/');
+    if path.is_empty() || path == "." {
+      "/".to_string()
+    } else {
+      path.to_string()
     }
   }
 
-  pub fn find_by_path(&self, path: String) -> Option<Node> {
-    None
+  pub fn display(&self) {
+    self.write_to(&mut io::stdout()).expect("Could not write to stdout");
   }
 
-  pub fn find_by_depth(&self, depth: u64) -> Option<Node> {
-    None
+  /// Writes the tree, including mounted trees, to `out` in the format of
+  /// `Tree::display`.
+  pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    self.render_to(out, &RenderOptions::default())
+  }
+
+  /// Adds `node` under its parent, found from its path and depth, merging
+  /// it with the node already at its path if any.
+  ///
+  /// The node is skipped with a warning if its parent is missing, see
+  /// `Tree::insert_with`.
+  pub fn insert(&mut self, node: Node) {
+    if let Err(e) = self.insert_with(node, DuplicatePolicy::Merge) {
+      warning!("Could not insert {}, skipping it.", e);
+    }
   }
 
-  fn find_by_name_recursive(node: &Node, name: String) -> Option<Node> {
-    println!("Searching for node: {}", name);
-    if node.name() == name {
-      println!("Found node: {}", node.name());
-      return Some(node.clone());
+  /// Adds `node` under its parent, found from its path and depth, handling
+  /// a node already at its path with `policy`.
+  ///
+  /// The parent is looked up in the path index, which is updated with the
+  /// node, so building a tree from a long path list stays fast even in
+  /// wide directories.
+  ///
+  /// # Returns
+  ///
+  /// A description of the problem if the parent is missing, or with
+  /// `DuplicatePolicy::Error` if the path is already in the tree.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// assert_eq!(tree.root.as_ref().unwrap().children.len(), 1);
+  ///
+  /// let duplicate = Node::from_path("src/main.rs").unwrap();
+  /// assert!(tree.insert_with(duplicate, DuplicatePolicy::Error).is_err());
+  /// assert!(tree.insert_with(Node::from_path("lib.rs").unwrap(), DuplicatePolicy::Error).is_ok());
+  /// ```
+  pub fn insert_with(&mut self, node: Node, policy: DuplicatePolicy) -> Result<(), String> {
+    if self.root.is_none() {
+      self.root_or_insert();
     }
+    // Nodes are only inserted in this tree, not in the mounted ones.
+    let in_tree = |route: &Vec<Step>| route.iter().all(|step| matches!(step, Step::Child(_)));
 
-    for child in &node.children {
-      let found = Tree::find_by_name_recursive(child, name.clone());
-      match found {
-        Some(node) => {
-          return Some(node);
-        },
-        None => {
-          continue;
+    let existing = match node.depth {
+      0 => None,
+      _ => self.route_to(&node.path).filter(in_tree),
+    };
+    if let Some(route) = existing {
+      return match policy {
+        DuplicatePolicy::Merge => {
+          let existing = self.follow_mut(&route).ok_or_else(|| format!("{}: no such node", node.path))?;
+          // The existing routes still lead to their nodes, the ones of the
+          // nodes merged in are added.
+          let added = !node.children.is_empty() || existing.node_type != node.node_type;
+          match (&existing.node_type, &node.node_type) {
+            (NodeType::Directory(), NodeType::Directory()) => {
+              Tree::merge_nodes(existing, node, ConflictPolicy::KeepLast);
+            },
+            _ => {
+              *existing = node;
+            },
+          }
+          if added {
+            self.index_subtree(route);
+          }
+          Ok(())
         },
-      }
+        DuplicatePolicy::Skip => Ok(()),
+        DuplicatePolicy::Error => Err(format!("{}: already exists", node.path)),
+      };
     }
 
-    None
+    let names: Vec<&str> = node.path.split('/').collect();
+    let parent_path = match node.depth as usize {
+      1 => "/".to_string(),
+      depth if depth > 1 && depth <= names.len() => names[..depth - 1].join("/"),
+      _ => String::new(),
+    };
+    debug!("To find: {}", parent_path);
+
+    let mut route = match self.route_to(&parent_path).filter(in_tree) {
+      Some(route) => route,
+      None => return Err(format!("{}: no parent directory", node.path)),
+    };
+    let parent = self.follow_mut(&route).ok_or_else(|| format!("{}: no parent directory", node.path))?;
+    route.push(Step::Child(parent.children.len()));
+    parent.children.push(node);
+    debug!("Added child.");
+    self.index_subtree(route);
+    Ok(())
   }
 
-  fn insert_recursive(node: &mut Node, child: Node) {
-    if child.depth() == node.depth() + 1 {
-      node.children.push(child);
-      println!("Added child.");
-    } else {
-      let child_path = child.path();
-      let path = child_path.split("/").collect::<Vec<&str>>();
-      let to_find = path[node.depth() as usize];
-      println!("To find: {}", to_find);
-
-      for c in &mut node.children {
-        if c.name() == to_find {
-          println!("Found child: {}", c.name());
-          Tree::insert_recursive(c, child);
-          node.display();
-          return;
+  /// Gets the root node, creating it labelled with the base if missing.
+  pub(crate) fn root_or_insert(&mut self) -> &mut Node {
+    // The caller may add nodes below the root.
+    self.invalidate_index();
+    let base = self.base.as_deref();
+    self.root.get_or_insert_with(|| {
+      let mut root = Node::new_root();
+      Tree::label_root(&mut root, base);
+      root
+    })
+  }
+
+  /// Moves the node at `from_path` and its subtree under the directory at
+  /// `to_path`, updating the depth and path of every moved node.
+  ///
+  /// # Returns
+  ///
+  /// A description of the problem if either node is missing, if `to_path`
+  /// is not a directory outside the moved subtree or if it already has a
+  /// child with the same name.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/tar/").unwrap());
+  /// tree.insert(Node::from_path("src/tar/zip.rs").unwrap());
+  ///
+  /// tree.move_node("src/tar", "/").unwrap();
+  /// let zip = tree.find_by_path("tar/zip.rs").unwrap();
+  /// assert_eq!(zip.depth(), 2);
+  /// ```
+  pub fn move_node(&mut self, from_path: &str, to_path: &str) -> Result<(), String> {
+    let from_path = Tree::normalize_path(from_path);
+    let to_path = Tree::normalize_path(to_path);
+    if from_path == "/" {
+      return Err("cannot move the root".to_string());
+    }
+
+    let name = match self.find_by_path(&from_path) {
+      Some(node) => node.name.clone(),
+      None => return Err(format!("{}: no such node", from_path)),
+    };
+    match self.find_by_path(&to_path) {
+      Some(Node { node_type: NodeType::Directory(), children, .. }) => {
+        if children.iter().any(|child| child.name == name) {
+          return Err(format!("{}: already has a child named {}", to_path, name));
         }
+      },
+      Some(_) => return Err(format!("{}: not a directory", to_path)),
+      None => return Err(format!("{}: no such node", to_path)),
+    }
+    if Tree::relative_to(&to_path, &from_path).is_some() {
+      return Err(format!("cannot move {} inside itself", from_path));
+    }
+
+    let mut node = self.remove_by_path(&from_path).unwrap();
+    let parent = self.find_by_path_mut(&to_path).unwrap();
+    node.rebase(&parent.path, parent.depth);
+    let new_path = match to_path.as_str() {
+      "/" => name,
+      _ => format!("{}/{}", to_path, name),
+    };
+    parent.children.push(node);
+    self.rekey_mounts(&from_path, &new_path);
+    // The moved routes were dropped with the node.
+    if let Some(mut route) = self.route_to(&to_path) {
+      let last = self.follow(&route).map_or(0, |parent| parent.children.len() - 1);
+      route.push(Step::Child(last));
+      self.index_subtree(route);
+    }
+    Ok(())
+  }
+
+  /// Renames the node at `path`, updating the path of its descendants.
+  ///
+  /// # Returns
+  ///
+  /// A description of the problem if there is no node at `path`, if it is
+  /// the root, if `new_name` is not a valid name or if a sibling already
+  /// has this name.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("./src/").unwrap());
+  /// tree.insert(Node::from_path("./src/main.rs").unwrap());
+  ///
+  /// tree.rename("src", "bin").unwrap();
+  /// let main = tree.find_by_path("bin/main.rs").unwrap();
+  /// assert_eq!(main.data.default_path, "./bin/main.rs");
+  /// ```
+  pub fn rename(&mut self, path: &str, new_name: &str) -> Result<(), String> {
+    let path = Tree::normalize_path(path);
+    if path == "/" {
+      return Err("cannot rename the root".to_string());
+    }
+    if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains('/') {
+      return Err(format!("{}: invalid name", new_name));
+    }
+
+    let parent_path = match path.rsplit_once('/') {
+      Some((parent, _)) => parent.to_string(),
+      None => "/".to_string(),
+    };
+    let taken = self.find_by_path(&parent_path)
+      .is_some_and(|parent| parent.children.iter().any(|child| child.name == new_name));
+    if taken {
+      return Err(format!("{}: already has a child named {}", parent_path, new_name));
+    }
+
+    let node = match self.find_by_path_mut(&path) {
+      Some(node) => node,
+      None => return Err(format!("{}: no such node", path)),
+    };
+    let node_parent = match node.path.rsplit_once('/') {
+      Some((parent, _)) => parent.to_string(),
+      None => "/".to_string(),
+    };
+    node.name = new_name.to_string();
+    node.rebase(&node_parent, node.depth - 1);
+    self.invalidate_index();
+
+    let new_path = match parent_path.as_str() {
+      "/" => new_name.to_string(),
+      _ => format!("{}/{}", parent_path, new_name),
+    };
+    self.rekey_mounts(&path, &new_path);
+    Ok(())
+  }
+
+  /// Copies the node at `path` and its descendants, the trees mounted
+  /// below it included, into a standalone tree whose root is the node. The
+  /// depths and paths are rebased on the new root, and the new tree is
+  /// anchored under the absolute path of the node if this tree has a base.
+  ///
+  /// # Returns
+  ///
+  /// The new tree, or `None` if there is no node at `path`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::{Path, PathBuf};
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::with_root(".", Some(PathBuf::from("/srv/data")));
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/main.rs").unwrap());
+  ///
+  /// let subtree = tree.subtree("src").unwrap();
+  /// let main = subtree.find_by_path("bin/main.rs").unwrap();
+  /// assert_eq!(main.depth(), 2);
+  /// assert_eq!(subtree.absolute_path(main), Path::new("/srv/data/src/bin/main.rs"));
+  /// assert_eq!(subtree.root.as_ref().unwrap().name(), "src");
+  /// ```
+  pub fn subtree(&self, path: &str) -> Option<Tree> {
+    let route = self.route_to(&Tree::normalize_path(path))?;
+    let (tree, node) = self.follow_with_tree(&route)?;
+
+    let mut subtree = Tree::new();
+    subtree.base = tree.base.as_ref().map(|_| tree.absolute_path(node));
+    let mut root = node.clone();
+    Tree::label_root(&mut root, subtree.base.as_deref());
+    root.name = node.name.clone();
+    root.depth = 0;
+    root.path = "/".to_string();
+    for child in &mut root.children {
+      child.rebase("/", 0);
+    }
+    subtree.root = Some(root);
+
+    for (key, mounted) in &tree.mounts {
+      if let Some(rest) = Tree::relative_to(key, &node.path) {
+        let key = if rest.is_empty() { "/" } else { rest };
+        subtree.mounts.insert(key.to_string(), mounted.clone());
+      }
+    }
+    Some(subtree)
+  }
+
+  /// Shrinks the buffers of every node, and of the mounted trees, to fit
+  /// their content. Useful for long lived trees after heavy pruning.
+  ///
+  /// The nodes are owned by their parent, so removed nodes are freed at
+  /// once and there are no dead nodes to drop: only the spare capacity left
+  /// by the removals is reclaimed. See `ArenaTree::compact` for the slots
+  /// freed in an arena.
+  ///
+  /// # Returns
+  ///
+  /// The number of bytes reclaimed.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// tree.insert(Node::from_path("src/lib.rs").unwrap());
+  /// tree.remove_by_path("src/lib.rs");
+  /// assert!(tree.compact() > 0);
+  /// ```
+  pub fn compact(&mut self) -> usize {
+    let mut reclaimed = match &mut self.root {
+      Some(root) => root.compact(),
+      None => 0,
+    };
+
+    for mounted in self.mounts.values_mut() {
+      reclaimed += mounted.compact();
+    }
+    let before = self.mounts.capacity();
+    self.mounts.shrink_to_fit();
+    reclaimed + (before - self.mounts.capacity()) * mem::size_of::<(String, Tree)>()
+  }
+
+  /// Gets the part of `path` relative to the directory at `base`, if `path`
+  /// is `base` itself or one of its descendants.
+  fn relative_to<'p>(path: &'p str, base: &str) -> Option<&'p str> {
+    if base == "/" {
+      return Some(path);
+    }
+
+    match path.strip_prefix(base) {
+      Some("") => Some(""),
+      Some(rest) => rest.strip_prefix('/'),
+      None => None,
+    }
+  }
+
+  /// Joins the mount path of a tree and a path in that tree.
+  fn join(prefix: &str, path: &str) -> String {
+    match (prefix, path) {
+      (prefix, "/") => prefix.to_string(),
+      ("", path) => path.to_string(),
+      (prefix, path) => format!("{}/{}", prefix, path),
+    }
+  }
+}
+
+impl<T> Tree<T> {
+  /// Gets the directory the paths of the tree are anchored under, `None`
+  /// for a virtual root.
+  pub fn base(&self) -> Option<&Path> {
+    self.base.as_deref()
+  }
+
+  /// Gets the path of `node` anchored under the base of the tree, the
+  /// virtual root being `/`.
+  pub fn absolute_path(&self, node: &Node<T>) -> PathBuf {
+    let base = self.base.as_deref().unwrap_or(Path::new("/"));
+    match node.depth {
+      0 => base.to_path_buf(),
+      _ => base.join(&node.path),
+    }
+  }
+
+  /// Mounts another tree under a directory of this tree.
+  ///
+  /// The mounted tree is not copied: it is kept as is and traversal and
+  /// display descend into it as if its root children were children of the
+  /// directory at `path`. The mount takes effect as soon as such a
+  /// directory exists.
+  ///
+  /// # Returns
+  ///
+  /// The tree previously mounted at `path`, if any.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::new_directory(
+  ///  NodeData { default_path: "vendor/".to_string(), length: 7 },
+  ///  1,
+  ///  "vendor".to_string(),
+  ///  "vendor".to_string()
+  /// ));
+  ///
+  /// assert!(tree.mount("vendor/", Tree::new()).is_none());
+  /// ```
+  pub fn mount(&mut self, path: &str, tree: Tree<T>) -> Option<Tree<T>> {
+    self.invalidate_index();
+    self.mounts.insert(Tree::normalize_path(path), tree)
+  }
+
+  /// Unmounts the tree mounted at `path`, if any, and returns it.
+  pub fn unmount(&mut self, path: &str) -> Option<Tree<T>> {
+    self.invalidate_index();
+    self.mounts.remove(&Tree::normalize_path(path))
+  }
+
+  /// Gets the aggregate statistics of the whole tree, including mounted
+  /// trees: the numbers of files and directories, the root included, the
+  /// max depth, the total size and the size of the largest file.
+  ///
+  /// The statistics of the nodes are cached, see `Node::stats` for the
+  /// nodes changed directly.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add_file("src/bin/main.rs", 300);
+  /// source.add_file("README.md", 900);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  ///
+  /// let stats = tree.stats();
+  /// assert_eq!((stats.files, stats.directories), (2, 3));
+  /// assert_eq!(stats.max_depth, 3);
+  /// assert_eq!((stats.size, stats.largest), (1200, 900));
+  /// assert_eq!(Tree::new().stats().files, 0);
+  /// ```
+  pub fn stats(&self) -> SubtreeStats {
+    match &self.root {
+      Some(root) => {
+        self.subtree_stats(root)
+      },
+      None => {
+        SubtreeStats::default()
+      },
+    }
+  }
+
+  /// Finds the largest file of the tree, including mounted trees, the
+  /// first one in pre-order if several have the same size.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add_file("src/main.rs", 300);
+  /// source.add_file("logo.png", 900);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  /// assert_eq!(tree.largest_file().unwrap().name(), "logo.png");
+  /// ```
+  pub fn largest_file(&self) -> Option<&Node<T>> {
+    let mut largest: Option<&Node<T>> = None;
+    self.visit(|node| {
+      let size = node.meta.size.unwrap_or(0);
+      if node.node_type == NodeType::File() && largest.is_none_or(|largest| size > largest.meta.size.unwrap_or(0)) {
+        largest = Some(node);
+      }
+    });
+    largest
+  }
+
+  /// Finds the first node named `name`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let tree = Tree::new();
+  /// assert!(tree.find_by_name("main.rs").is_none());
+  /// ```
+  pub fn find_by_name(&self, name: &str) -> Option<&Node<T>> {
+    debug!("Searching for node: {}", name);
+    let found = self.find(|node| node.name == name);
+    if let Some(node) = found {
+      debug!("Found node: {}", node.path());
+    }
+    found
+  }
+
+  /// Finds the first node named `name`, allowing it to be updated in place.
+  pub fn find_by_name_mut(&mut self, name: &str) -> Option<&mut Node<T>> {
+    self.find_mut(|node| node.name == name)
+  }
+
+  /// Finds the first node named `name` whatever the case, like on the
+  /// filesystems of Windows and macOS.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("README.md").unwrap());
+  /// assert!(tree.find_by_name("readme.md").is_none());
+  /// assert_eq!(tree.find_by_name_ignore_case("readme.md").unwrap().name(), "README.md");
+  /// ```
+  pub fn find_by_name_ignore_case(&self, name: &str) -> Option<&Node<T>> {
+    self.find(|node| eq_ignore_case(&node.name, name))
+  }
+
+  /// Finds the first node, in pre-order, matching `predicate`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let tree = Tree::new();
+  /// assert!(tree.find(|node| node.depth() > 5).is_none());
+  /// ```
+  pub fn find<P: FnMut(&Node<T>) -> bool>(&self, mut predicate: P) -> Option<&Node<T>> {
+    let mut found = None;
+    self.walk(|_, node, _| {
+      if predicate(node) {
+        found = Some(node);
+      }
+      found.is_none()
+    });
+    found
+  }
+
+  /// Finds all the nodes matching `predicate`, in pre-order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let tree = Tree::new();
+  /// let big = tree.find_all(|node| node.meta.size.unwrap_or(0) > 1_000_000);
+  /// assert!(big.is_empty());
+  /// ```
+  pub fn find_all<P: FnMut(&Node<T>) -> bool>(&self, mut predicate: P) -> Vec<&Node<T>> {
+    let mut found = Vec::new();
+    self.visit(|node| {
+      if predicate(node) {
+        found.push(node);
+      }
+    });
+    found
+  }
+
+  /// Iterates over the files of the tree in pre-order, descending into
+  /// mounted trees, with their path relative to the root. The empty
+  /// directories are not leaves.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// tree.insert(Node::from_path("empty/").unwrap());
+  /// tree.insert(Node::from_path("README.md").unwrap());
+  ///
+  /// let paths: Vec<String> = tree.leaves().map(|(path, _)| path).collect();
+  /// assert_eq!(paths, ["src/main.rs", "README.md"]);
+  /// ```
+  pub fn leaves(&self) -> impl Iterator<Item = (String, &Node<T>)> + '_ {
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut leaves = Vec::new();
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+      }
+      if node.node_type == NodeType::File() {
+        leaves.push((names.join("/"), node));
+      }
+      true
+    });
+    leaves.into_iter()
+  }
+
+  /// Lists the paths of the entries of the tree in pre-order, descending
+  /// into mounted trees, relative to the root and with a trailing slash
+  /// for the directories: the format of a path list, which builds the
+  /// same tree back.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for line in ["src/", "src/main.rs", "empty/", "README.md"] {
+  ///   tree.insert(Node::from_path(line).unwrap());
+  /// }
+  /// assert_eq!(tree.to_paths(), ["src/", "src/main.rs", "empty/", "README.md"]);
+  ///
+  /// let mut copy = Tree::new();
+  /// for line in tree.to_paths() {
+  ///   copy.insert(Node::from_path(&line).unwrap());
+  /// }
+  /// assert!(copy == tree);
+  /// ```
+  pub fn to_paths(&self) -> Vec<String> {
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut paths = Vec::new();
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+        let slash = if node.node_type == NodeType::Directory() { "/" } else { "" };
+        paths.push(format!("{}{}", names.join("/"), slash));
+      }
+      true
+    });
+    paths
+  }
+
+  /// Finds a node from its path, descending into mounted trees. See
+  /// `Tree::find_all_by_path` for paths with wildcards.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::new_directory(
+  ///  NodeData { default_path: "src/".to_string(), length: 4 },
+  ///  1,
+  ///  "src".to_string(),
+  ///  "src".to_string()
+  /// ));
+  ///
+  /// assert_eq!(tree.find_by_path("./src/").unwrap().name(), "src");
+  /// ```
+  pub fn find_by_path(&self, path: &str) -> Option<&Node<T>> {
+    let route = self.route_to(&Tree::normalize_path(path))?;
+    self.follow(&route)
+  }
+
+  /// Finds a node from its path, allowing it to be updated in place.
+  ///
+  /// Renaming the node or changing its children through it leaves the path
+  /// index stale, see `Tree::invalidate_index`.
+  pub fn find_by_path_mut(&mut self, path: &str) -> Option<&mut Node<T>> {
+    let route = self.route_to(&Tree::normalize_path(path))?;
+    self.follow_mut(&route)
+  }
+
+  /// Finds all the nodes whose path matches `pattern`, in pre-order and
+  /// descending into mounted trees. The components of the pattern may hold
+  /// the wildcards of `.treeignore` patterns: `*` and `?` within a name,
+  /// `[...]` classes, and `**` for any number of directories.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["Cargo.toml", "src/", "src/mod.rs", "src/net/", "src/net/mod.rs", "cli/", "cli/Cargo.toml"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  /// let paths = |pattern| tree.find_all_by_path(pattern).iter().map(|node| node.path()).collect::<Vec<String>>();
+  /// assert_eq!(paths("src/**/mod.rs"), ["src/mod.rs", "src/net/mod.rs"]);
+  /// assert_eq!(paths("*/Cargo.toml"), ["cli/Cargo.toml"]);
+  /// assert_eq!(paths("./src/mod.rs"), ["src/mod.rs"]);
+  /// ```
+  pub fn find_all_by_path(&self, pattern: &str) -> Vec<&Node<T>> {
+    let pattern: Vec<char> = Tree::normalize_path(pattern).chars().collect();
+    if pattern == ['/'] {
+      return self.root.iter().collect();
+    }
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut found = Vec::new();
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+        let path: Vec<char> = names.join("/").chars().collect();
+        if glob_match(&pattern, &path) {
+          found.push(node);
+        }
+      }
+      true
+    });
+    found
+  }
+
+  /// Finds a node from its path whatever the case, descending into mounted
+  /// trees, the first one in pre-order when several match.
+  ///
+  /// The paths are compared one node at a time, without the index of
+  /// `Tree::find_by_path`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("Docs/").unwrap());
+  /// tree.insert(Node::from_path("Docs/Guide.md").unwrap());
+  /// assert_eq!(tree.find_by_path_ignore_case("./docs/guide.MD").unwrap().path(), "Docs/Guide.md");
+  /// ```
+  pub fn find_by_path_ignore_case(&self, path: &str) -> Option<&Node<T>> {
+    let path = Tree::normalize_path(path);
+    if path == "/" {
+      return self.root.as_ref();
+    }
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut found = None;
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+        if eq_ignore_case(&names.join("/"), &path) {
+          found = Some(node);
+        }
+      }
+      found.is_none()
+    });
+    found
+  }
+
+  /// Finds the parent directory of the node at `path`, the mount point
+  /// for the root children of a mounted tree.
+  ///
+  /// # Returns
+  ///
+  /// The parent, or `None` for the root and for paths not in the tree.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  ///
+  /// assert_eq!(tree.parent_of("src/main.rs").unwrap().path(), "src");
+  /// assert_eq!(tree.parent_of("src").unwrap().path(), "/");
+  /// assert!(tree.parent_of("/").is_none());
+  /// ```
+  pub fn parent_of(&self, path: &str) -> Option<&Node<T>> {
+    let route = self.route_to(&Tree::normalize_path(path))?;
+    let (_, parent_route) = route.split_last()?;
+    self.follow(parent_route)
+  }
+
+  /// Iterates over the ancestors of the node at `path`, from its parent to
+  /// the root, going up through the mount points. Nothing is iterated for
+  /// the root and for paths not in the tree.
+  ///
+  /// The nodes do not link to their parent, so every ancestor is found
+  /// again from the root: see `arena::ArenaTree` to walk up often.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/main.rs").unwrap());
+  ///
+  /// let ancestors: Vec<String> = tree.ancestors_of("src/bin/main.rs").map(|node| node.path()).collect();
+  /// assert_eq!(ancestors, ["src/bin", "src", "/"]);
+  /// ```
+  pub fn ancestors_of(&self, path: &str) -> impl Iterator<Item = &Node<T>> + '_ {
+    let route = self.route_to(&Tree::normalize_path(path)).unwrap_or_default();
+    (0..route.len()).rev().filter_map(move |len| self.follow(&route[..len]))
+  }
+
+  /// Finds the deepest directory holding the nodes at `path_a` and
+  /// `path_b`, which may be one of them, going up through the mount
+  /// points.
+  ///
+  /// # Returns
+  ///
+  /// The directory, or `None` if either path is not in the tree.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/main.rs").unwrap());
+  /// tree.insert(Node::from_path("src/lib.rs").unwrap());
+  ///
+  /// assert_eq!(tree.lowest_common_ancestor("src/bin/main.rs", "src/lib.rs").unwrap().path(), "src");
+  /// assert_eq!(tree.lowest_common_ancestor("src/bin", "src/bin/main.rs").unwrap().path(), "src/bin");
+  /// assert_eq!(tree.lowest_common_ancestor("src/lib.rs", "src/lib.rs").unwrap().path(), "src");
+  /// assert!(tree.lowest_common_ancestor("src/lib.rs", "missing").is_none());
+  /// ```
+  pub fn lowest_common_ancestor(&self, path_a: &str, path_b: &str) -> Option<&Node<T>> {
+    let route_a = self.route_to(&Tree::normalize_path(path_a))?;
+    let route_b = self.route_to(&Tree::normalize_path(path_b))?;
+    let shared = route_a.iter().zip(&route_b).take_while(|(a, b)| a == b).count();
+    let node = self.follow(&route_a[..shared])?;
+    match node.node_type {
+      NodeType::Directory() => Some(node),
+      // Both paths are the same file.
+      NodeType::File() => self.follow(&route_a[..shared.checked_sub(1)?]),
+    }
+  }
+
+  /// Iterates over the children of the directory at `path`, followed by
+  /// the root children of the tree mounted on it if any, without reading
+  /// their own children. Nothing is iterated for files and for paths not
+  /// in the tree.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// tree.insert(Node::from_path("README.md").unwrap());
+  ///
+  /// let names: Vec<String> = tree.children_of("/").map(|node| node.name()).collect();
+  /// assert_eq!(names, ["src", "README.md"]);
+  /// assert_eq!(tree.children_of("src/main.rs").count(), 0);
+  /// ```
+  pub fn children_of(&self, path: &str) -> impl Iterator<Item = &Node<T>> + '_ {
+    let found = self.route_to(&Tree::normalize_path(path)).and_then(|route| self.follow_with_tree(&route));
+    let mounted = found.and_then(|(tree, node)| tree.mounts.get(&node.path)?.root.as_ref());
+    found.into_iter().flat_map(|(_, node)| node.children.iter())
+      .chain(mounted.into_iter().flat_map(|root| root.children.iter()))
+  }
+
+  /// Iterates over the other children of the parent of the node at `path`,
+  /// in the order of `Tree::children_of`. Nothing is iterated for the root
+  /// and for paths not in the tree.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// tree.insert(Node::from_path("src/lib.rs").unwrap());
+  ///
+  /// let names: Vec<String> = tree.siblings_of("src/lib.rs").map(|node| node.name()).collect();
+  /// assert_eq!(names, ["main.rs"]);
+  /// assert_eq!(tree.siblings_of("/").count(), 0);
+  /// ```
+  pub fn siblings_of(&self, path: &str) -> impl Iterator<Item = &Node<T>> + '_ {
+    let path = Tree::normalize_path(path);
+    let node = self.find_by_path(&path).filter(|_| path != "/");
+    let parent_path = match path.rsplit_once('/') {
+      Some((parent, _)) => parent,
+      None => "/",
+    };
+    let siblings = node.map(|_| self.children_of(parent_path));
+    siblings.into_iter().flatten()
+      .filter(move |sibling| !node.is_some_and(|node| ptr::eq(node, *sibling)))
+  }
+
+  /// Detaches the node at `path` and its subtree from the tree.
+  ///
+  /// Removing the root path `/` empties the tree.
+  ///
+  /// # Returns
+  ///
+  /// The removed node, with its children, if there was a node at `path`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::new_directory(
+  ///  NodeData { default_path: "src/".to_string(), length: 4 },
+  ///  1,
+  ///  "src".to_string(),
+  ///  "src".to_string()
+  /// ));
+  ///
+  /// tree.insert(Node::from_path("docs/").unwrap());
+  ///
+  /// assert_eq!(tree.remove_by_path("src").unwrap().name(), "src");
+  /// assert!(tree.find_by_path("src").is_none());
+  /// assert_eq!(tree.find_by_path("docs").unwrap().name(), "docs");
+  /// ```
+  pub fn remove_by_path(&mut self, path: &str) -> Option<Node<T>> {
+    let route = self.route_to(&Tree::normalize_path(path))?;
+
+    let (last, parent_route) = match route.split_last() {
+      Some(split) => split,
+      None => {
+        self.invalidate_index();
+        return self.root.take();
+      },
+    };
+    let (parent, mounts) = self.follow_mut_with_mounts(parent_route)?;
+    let siblings = match last {
+      Step::Child(_) => &mut parent.children,
+      Step::Mount(_) => {
+        let root = mounts.get_mut(&parent.path)?.root.as_mut()?;
+        root.invalidate_stats();
+        &mut root.children
+      },
+    };
+    let (Step::Child(i) | Step::Mount(i)) = *last;
+    if i >= siblings.len() {
+      return None;
+    }
+    let removed = siblings.remove(i);
+
+    // The routes below the node are dropped, the ones going through its
+    // next siblings are shifted.
+    if let Some(index) = self.index.get_mut() {
+      let depth = parent_route.len();
+      index.retain(|_, entry| !entry.starts_with(&route));
+      for entry in index.values_mut().filter(|entry| entry.len() > depth && entry.starts_with(parent_route)) {
+        match (&mut entry[depth], last) {
+          (Step::Child(j), Step::Child(_)) | (Step::Mount(j), Step::Mount(_)) if *j > i => *j -= 1,
+          _ => {},
+        }
+      }
+    }
+    Some(removed)
+  }
+
+  /// Merges `other` into this tree.
+  ///
+  /// Directories present in both trees are merged recursively, other paths
+  /// present in both trees are resolved with `policy`. Mounts of `other`
+  /// are merged the same way.
+  ///
+  /// # Returns
+  ///
+  /// With `ConflictPolicy::Error`, the first conflicting path.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  ///
+  /// let mut other = Tree::new();
+  /// other.insert(Node::from_path("src/").unwrap());
+  /// other.insert(Node::from_path("src/lib.rs").unwrap());
+  ///
+  /// tree.merge(other, ConflictPolicy::Error).unwrap();
+  /// assert_eq!(tree.find_by_path("src").unwrap().children.len(), 2);
+  /// ```
+  ///
+  /// Deep trees are merged without recursion, like the subtrees merged by
+  /// `Tree::insert`:
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// // A chain of directories 100000 levels deep, ending with a file.
+  /// let chain = |file: &str| {
+  ///   let data = |name: &str| NodeData { default_path: name.to_string(), length: name.len() as u64 };
+  ///   let mut node = Node::new_file(data(file), 100_001, file.to_string(), file.to_string());
+  ///   for depth in (1..=100_000).rev() {
+  ///     let mut directory = Node::new_directory(data("d"), depth, "d".to_string(), "d".to_string());
+  ///     directory.children.push(node);
+  ///     node = directory;
+  ///   }
+  ///   let mut tree = Tree::with_root(".", None);
+  ///   tree.root.as_mut().unwrap().children.push(node);
+  ///   tree
+  /// };
+  ///
+  /// let mut tree = chain("a");
+  /// tree.merge(chain("b"), ConflictPolicy::Error).unwrap();
+  /// assert!(tree.merge(chain("b"), ConflictPolicy::Error).is_err());
+  /// let mut other = chain("c");
+  /// tree.insert(other.root.as_mut().unwrap().children.pop().unwrap());
+  /// assert_eq!(tree.stats().files, 3);
+  /// ```
+  pub fn merge(&mut self, other: Tree<T>, policy: ConflictPolicy) -> Result<(), String> {
+    self.invalidate_index();
+    if policy == ConflictPolicy::Error {
+      if let (Some(root), Some(other_root)) = (&self.root, &other.root) {
+        if let Some(path) = Self::find_conflict(root, other_root) {
+          return Err(format!("{}: exists in both trees", path));
+        }
+      }
+      if let Some(path) = other.mounts.keys().find(|path| self.mounts.contains_key(*path)) {
+        return Err(format!("{}: mounted in both trees", path));
+      }
+    }
+
+    match (&mut self.root, other.root) {
+      (Some(root), Some(other_root)) => {
+        Self::merge_nodes(root, other_root, policy);
+      },
+      (None, other_root) => {
+        self.root = other_root;
+      },
+      (Some(_), None) => {},
+    }
+
+    for (path, mounted) in other.mounts {
+      if policy == ConflictPolicy::KeepLast || !self.mounts.contains_key(&path) {
+        self.mounts.insert(path, mounted);
+      }
+    }
+    Ok(())
+  }
+
+  /// Finds the first path below `node` and `other` that is not a directory
+  /// in both.
+  fn find_conflict(node: &Node<T>, other: &Node<T>) -> Option<String> {
+    // The directories in both trees being compared, with the children of
+    // the other one left to compare.
+    let mut stack = vec![(node, other.children.iter())];
+    while let Some((node, other_children)) = stack.last_mut() {
+      let node: &Node<T> = node;
+      let Some(other_child) = other_children.next() else {
+        stack.pop();
+        continue;
+      };
+      if let Some(child) = node.children.iter().find(|child| child.name == other_child.name) {
+        match (&child.node_type, &other_child.node_type) {
+          (NodeType::Directory(), NodeType::Directory()) => stack.push((child, other_child.children.iter())),
+          _ => return Some(child.path.clone()),
+        }
+      }
+    }
+    None
+  }
+
+  /// Merges the children of `other` into the children of `node`, the
+  /// directories in both being merged the same way.
+  fn merge_nodes(node: &mut Node<T>, other: Node<T>, policy: ConflictPolicy) {
+    /// The children of a directory once the children of the other one are
+    /// added, with a hole for every directory to merge, taken out with
+    /// the directory to merge into it.
+    type Merged<T> = (Vec<Option<Node<T>>>, Vec<(usize, Node<T>, Node<T>)>);
+
+    enum Work<T> {
+      /// Merges the second directory into the first one.
+      Merge(Node<T>, Box<Node<T>>),
+      /// Gives back its children to a merged directory, the directories
+      /// merged below it being the last ones done, for the holes at these
+      /// indices.
+      Exit(Node<T>, Vec<Option<Node<T>>>, Vec<usize>),
+    }
+
+    let add_children = |node: &mut Node<T>, mut other: Node<T>| -> Merged<T> {
+      node.invalidate_stats();
+      let mut children = mem::take(&mut node.children);
+      let mut pending: Vec<(usize, Node<T>)> = Vec::new();
+      for mut other_child in mem::take(&mut other.children) {
+        let i = match children.iter().position(|child| child.name == other_child.name) {
+          Some(i) => i,
+          None => {
+            children.push(other_child);
+            continue;
+          },
+        };
+
+        match (&children[i].node_type, &other_child.node_type) {
+          (NodeType::Directory(), NodeType::Directory()) => match pending.iter_mut().find(|(j, _)| *j == i) {
+            Some((_, directory)) => directory.children.append(&mut other_child.children),
+            None => pending.push((i, other_child)),
+          },
+          _ => {
+            if policy == ConflictPolicy::KeepLast {
+              pending.retain(|(j, _)| *j != i);
+              children[i] = other_child;
+            }
+          },
+        }
+      }
+      let mut children: Vec<Option<Node<T>>> = children.into_iter().map(Some).collect();
+      let pending = pending.into_iter()
+        .filter_map(|(i, other_child)| Some((i, children[i].take()?, other_child)))
+        .collect();
+      (children, pending)
+    };
+    let fill = |mut children: Vec<Option<Node<T>>>, indices: Vec<usize>, done: &mut Vec<Node<T>>| {
+      let merged = done.split_off(done.len() - indices.len());
+      for (i, child) in indices.into_iter().zip(merged) {
+        children[i] = Some(child);
+      }
+      children.into_iter().flatten().collect()
+    };
+
+    // The nodes are owned by the stack, so that they can be given back to
+    // their parent once merged.
+    let (children, pending) = add_children(node, other);
+    let indices: Vec<usize> = pending.iter().map(|(i, _, _)| *i).collect();
+    let mut stack: Vec<Work<T>> = pending.into_iter().rev().map(|(_, child, other)| Work::Merge(child, Box::new(other))).collect();
+    let mut done: Vec<Node<T>> = Vec::new();
+    while let Some(work) = stack.pop() {
+      match work {
+        Work::Merge(mut child, other) => {
+          let (children, pending) = add_children(&mut child, *other);
+          stack.push(Work::Exit(child, children, pending.iter().map(|(i, _, _)| *i).collect()));
+          stack.extend(pending.into_iter().rev().map(|(_, child, other)| Work::Merge(child, Box::new(other))));
+        },
+        Work::Exit(mut child, children, indices) => {
+          child.children = fill(children, indices, &mut done);
+          done.push(child);
+        },
+      }
+    }
+    node.children = fill(children, indices, &mut done);
+  }
+
+  /// Moves the mounts at or below `old_path` to the same place below
+  /// `new_path`.
+  fn rekey_mounts(&mut self, old_path: &str, new_path: &str) {
+    let keys: Vec<String> = self.mounts.keys()
+      .filter(|key| Tree::relative_to(key, old_path).is_some())
+      .cloned()
+      .collect();
+    for key in keys {
+      let rest = Tree::relative_to(&key, old_path).unwrap_or("");
+      let new_key = match rest {
+        "" => new_path.to_string(),
+        _ => format!("{}/{}", new_path, rest),
+      };
+      let mounted = self.mounts.remove(&key).unwrap();
+      self.mounts.insert(new_key, mounted);
+    }
+  }
+
+  /// Removes the nodes not matching `predicate`, with their subtree, from
+  /// the tree and from the mounted trees, like `Vec::retain`. The root is
+  /// always kept, and `predicate` is called on the children of the kept
+  /// nodes only, in pre-order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// tree.insert(Node::from_path("target/").unwrap());
+  /// tree.insert(Node::from_path("target/main.o").unwrap());
+  ///
+  /// tree.retain(|node| node.name != "target");
+  /// assert!(tree.find_by_path("target").is_none());
+  /// assert!(tree.find_by_path("src/main.rs").is_some());
+  /// ```
+  pub fn retain<P: FnMut(&Node<T>) -> bool>(&mut self, predicate: P) {
+    self.retain_with(predicate, false);
+  }
+
+  /// Removes the nodes not matching `predicate` like `Tree::retain`, and
+  /// with `prune` the directories left empty by the removal of all their
+  /// children too. The directories which were already empty are kept.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// tree.insert(Node::from_path("logs/").unwrap());
+  /// tree.insert(Node::from_path("logs/1.log").unwrap());
+  /// tree.insert(Node::from_path("empty/").unwrap());
+  ///
+  /// tree.retain_with(|node| !node.name.ends_with(".log"), true);
+  /// assert!(tree.find_by_path("logs").is_none());
+  /// assert!(tree.find_by_path("empty").is_some());
+  /// ```
+  pub fn retain_with<P: FnMut(&Node<T>) -> bool>(&mut self, mut predicate: P, prune: bool) {
+    self.retain_nodes(&mut predicate, prune);
+  }
+
+  fn retain_nodes<P: FnMut(&Node<T>) -> bool>(&mut self, predicate: &mut P, prune: bool) {
+    enum Work<T> {
+      Enter(Node<T>),
+      /// Gives back its children to a kept node, the kept children being
+      /// the nodes from this index of the kept nodes.
+      Exit(Node<T>, usize, bool),
+    }
+
+    self.invalidate_index();
+    for mounted in self.mounts.values_mut() {
+      mounted.retain_nodes(predicate, prune);
+    }
+    let root = match &mut self.root {
+      Some(root) => root,
+      None => return,
+    };
+    root.invalidate_stats();
+
+    // The nodes are owned by the stack, so that they can be dropped or
+    // given back to their parent once their children are done.
+    let mut kept: Vec<Node<T>> = Vec::new();
+    let mut stack: Vec<Work<T>> = mem::take(&mut root.children).into_iter().rev().map(Work::Enter).collect();
+    while let Some(work) = stack.pop() {
+      match work {
+        Work::Enter(mut node) => {
+          if predicate(&node) {
+            node.invalidate_stats();
+            let children = mem::take(&mut node.children);
+            stack.push(Work::Exit(node, kept.len(), !children.is_empty()));
+            stack.extend(children.into_iter().rev().map(Work::Enter));
+          }
+        },
+        Work::Exit(mut node, start, had_children) => {
+          node.children = kept.split_off(start);
+          let mounted = self.mounts.get(&node.path)
+            .is_some_and(|mounted| mounted.root.as_ref().is_some_and(|root| !root.children.is_empty()));
+          if !(prune && had_children && node.children.is_empty() && !mounted) {
+            kept.push(node);
+          }
+        },
+      }
+    }
+    root.children = kept;
+  }
+
+  /// Builds a tree with the same nodes and mounted trees, the data of
+  /// every node being given by `f`, like an analysis result computed from
+  /// the node. `f` is called on the nodes of the tree in pre-order, then
+  /// on the ones of the mounted trees.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  ///
+  /// let lengths: Tree<usize> = tree.map(|node| node.name.len());
+  /// assert_eq!(lengths.find_by_path("src/main.rs").unwrap().data, 7);
+  /// ```
+  pub fn map<U, F: FnMut(&Node<T>) -> U>(&self, mut f: F) -> Tree<U> {
+    self.map_nodes(&mut f)
+  }
+
+  fn map_nodes<U, F: FnMut(&Node<T>) -> U>(&self, f: &mut F) -> Tree<U> {
+    let mut mapped = Tree { base: self.base.clone(), ..Tree::default() };
+    let root = match &self.root {
+      Some(root) => root,
+      None => return mapped,
+    };
+
+    // A node is pushed back above its children with its data and the
+    // number of nodes mapped before them.
+    let mut done: Vec<Node<U>> = Vec::new();
+    let mut stack = vec![(root, None)];
+    while let Some((node, mapped_data)) = stack.pop() {
+      match mapped_data {
+        None => {
+          let data = f(node);
+          stack.push((node, Some((data, done.len()))));
+          stack.extend(node.children.iter().rev().map(|child| (child, None)));
+        },
+        Some((data, start)) => {
+          let children = done.split_off(start);
+          done.push(Node {
+            node_type: node.node_type.clone(),
+            data,
+            depth: node.depth,
+            path: node.path.clone(),
+            name: node.name.clone(),
+            children,
+            meta: node.meta.clone(),
+            stats: Cell::new(None),
+          });
+        },
+      }
+    }
+    mapped.root = done.pop();
+    for (path, mounted) in &self.mounts {
+      mapped.mounts.insert(path.clone(), mounted.map_nodes(f));
+    }
+    mapped
+  }
+
+  /// Computes a value for every node from the node and the values of its
+  /// children, in post-order, the root children of a mounted tree being
+  /// children of its mount point.
+  ///
+  /// # Returns
+  ///
+  /// The value of the root, or `None` for an empty tree.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/").unwrap());
+  /// tree.insert(Node::from_path("src/bin/main.rs").unwrap());
+  /// tree.insert(Node::from_path("README.md").unwrap());
+  ///
+  /// let height = tree.fold(|_, children: Vec<u64>| children.into_iter().max().map_or(0, |height| height + 1));
+  /// assert_eq!(height, Some(3));
+  /// ```
+  pub fn fold<A, F: FnMut(&Node<T>, Vec<A>) -> A>(&self, mut f: F) -> Option<A> {
+    let root = self.root.as_ref()?;
+    // A node is pushed back with the number of values it is waiting for,
+    // above its children and mounted nodes.
+    let mut stack = vec![(self, root, None)];
+    let mut values: Vec<A> = Vec::new();
+    while let Some((tree, node, pending)) = stack.pop() {
+      match pending {
+        Some(count) => {
+          let children = values.split_off(values.len() - count);
+          values.push(f(node, children));
+        },
+        None => {
+          let mounted = tree.mounts.get(&node.path)
+            .and_then(|mounted| Some((mounted, mounted.root.as_ref()?)));
+          let count = node.children.len() + mounted.map_or(0, |(_, root)| root.children.len());
+          stack.push((tree, node, Some(count)));
+          if let Some((mounted, root)) = mounted {
+            stack.extend(root.children.iter().rev().map(|child| (mounted, child, None)));
+          }
+          stack.extend(node.children.iter().rev().map(|child| (tree, child, None)));
+        },
+      }
+    }
+    values.pop()
+  }
+
+  /// Finds the first node, in pre-order, at the given depth.
+  pub fn find_by_depth(&self, depth: u64) -> Option<&Node<T>> {
+    self.find(|node| node.depth == depth)
+  }
+
+  /// Finds the first node, in pre-order, matching `predicate`, allowing it
+  /// to be updated in place.
+  ///
+  /// The cached statistics of the node and its ancestors are invalidated,
+  /// the path index is not, see `Tree::find_by_path_mut`.
+  pub fn find_mut<P: FnMut(&Node<T>) -> bool>(&mut self, mut predicate: P) -> Option<&mut Node<T>> {
+    let mut route = Vec::new();
+    let root = self.root.as_ref()?;
+    if self.route_by(root, &mut predicate, &mut route) {
+      self.follow_mut(&route)
+    } else {
+      None
+    }
+  }
+
+  /// Checks the internal invariants of the tree and its mounted trees.
+  ///
+  /// This is a debugging aid: every child must be one level deeper than
+  /// its parent, have a path made of its parent path and its name and a
+  /// name unique among its siblings, files must have no children and
+  /// cached statistics must match the actual content.
+  ///
+  /// # Returns
+  ///
+  /// A description of the first broken invariant, if any.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// assert!(tree.check_invariants().is_ok());
+  /// ```
+  pub fn check_invariants(&self) -> Result<(), String> {
+    if let Some(root) = &self.root {
+      if root.depth != 0 || root.path != "/" {
+        return Err(format!("root has depth {} and path {}", root.depth, root.path));
+      }
+      Self::check_invariants_recursive(root)?;
+    }
+
+    for (path, mounted) in &self.mounts {
+      mounted.check_invariants().map_err(|e| format!("tree mounted at {}: {}", path, e))?;
+    }
+    Ok(())
+  }
+
+  fn check_invariants_recursive(root: &Node<T>) -> Result<(), String> {
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+      if let NodeType::File() = node.node_type {
+        if !node.children.is_empty() {
+          return Err(format!("file {} has children", node.path));
+        }
+      }
+
+      if let Some(cached) = node.stats.get() {
+        node.invalidate_stats();
+        let actual = node.stats();
+        node.stats.set(Some(cached));
+        if cached != actual {
+          return Err(format!("stale statistics on {}: {:?} instead of {:?}", node.path, cached, actual));
+        }
+      }
+
+      let mut names = Vec::new();
+      for child in &node.children {
+        if child.depth != node.depth + 1 {
+          return Err(format!("{} has depth {} under a parent of depth {}", child.path, child.depth, node.depth));
+        }
+
+        let expected = if node.depth == 0 {
+          child.name.clone()
+        } else {
+          format!("{}/{}", node.path, child.name)
+        };
+        if child.path != expected {
+          return Err(format!("{} should have path {}", child.path, expected));
+        }
+
+        if names.contains(&&child.name) {
+          return Err(format!("{} appears more than once", child.path));
+        }
+        names.push(&child.name);
+      }
+      stack.extend(node.children.iter().rev());
+    }
+    Ok(())
+  }
+
+  /// Calls `f` on every node of the tree in pre-order, descending into
+  /// mounted trees.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// let mut count = 0;
+  /// tree.visit(|_| count += 1);
+  /// assert_eq!(count, 0);
+  /// ```
+  pub fn visit<'a, F: FnMut(&'a Node<T>)>(&'a self, mut f: F) {
+    self.walk(|_, node, _| {
+      f(node);
+      true
+    });
+  }
+
+  /// Calls `f` on every node of the tree in pre-order, descending into
+  /// mounted trees, allowing the nodes to be updated in place.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.visit_mut(|node| node.name = node.name.to_uppercase());
+  /// ```
+  pub fn visit_mut<F: FnMut(&mut Node<T>)>(&mut self, mut f: F) {
+    self.invalidate_index();
+    if let Some(root) = &mut self.root {
+      Self::visit_mut_nodes(slice::from_mut(root), &mut self.mounts, &mut f);
+    }
+  }
+
+  /// Calls `f` on every node in pre-order, descending into mounted trees,
+  /// until it returns `false`. `f` also gets the tree holding the node and
+  /// the extra indentation of the node, see `Tree::write_to`.
+  ///
+  /// The traversal uses an explicit stack, so its depth is only limited by
+  /// memory.
+  pub(crate) fn walk<'a, F: FnMut(&'a Tree<T>, &'a Node<T>, u64) -> bool>(&'a self, mut f: F) {
+    let root = match &self.root {
+      Some(root) => root,
+      None => return,
+    };
+
+    // The mounted nodes are pushed first so that they come after the
+    // children of the mount point.
+    let mut stack = vec![(self, root, 0)];
+    while let Some((tree, node, offset)) = stack.pop() {
+      if !f(tree, node, offset) {
+        return;
+      }
+
+      if let Some(mounted) = tree.mounts.get(&node.path) {
+        if let Some(root) = &mounted.root {
+          stack.extend(root.children.iter().rev().map(|child| (mounted, child, offset + node.depth)));
+        }
+      }
+      stack.extend(node.children.iter().rev().map(|child| (tree, child, offset)));
+    }
+  }
+
+  /// Calls `f` on `nodes` and their descendants in pre-order, descending
+  /// into the trees of `mounts` mounted on them.
+  ///
+  /// Only mounted trees are visited recursively, the nodes of a tree use an
+  /// explicit stack.
+  fn visit_mut_nodes<F: FnMut(&mut Node<T>)>(nodes: &mut [Node<T>], mounts: &mut Mounts<T>, f: &mut F) {
+    enum Work<'a, T> {
+      Node(&'a mut Node<T>),
+      Mount(String),
+    }
+
+    let mut stack: Vec<Work<T>> = nodes.iter_mut().rev().map(Work::Node).collect();
+    while let Some(work) = stack.pop() {
+      match work {
+        Work::Node(node) => {
+          f(node);
+          node.invalidate_stats();
+          if mounts.contains_key(&node.path) {
+            stack.push(Work::Mount(node.path.clone()));
+          }
+          stack.extend(node.children.iter_mut().rev().map(Work::Node));
+        },
+        Work::Mount(path) => {
+          if let Some(mounted) = mounts.get_mut(&path) {
+            if let Some(root) = &mut mounted.root {
+              Self::visit_mut_nodes(&mut root.children, &mut mounted.mounts, f);
+            }
+          }
+        },
+      }
+    }
+  }
+
+  /// Gets the statistics of `node`, using the cached ones below the nodes
+  /// no tree is mounted under.
+  pub(crate) fn subtree_stats(&self, node: &Node<T>) -> SubtreeStats {
+    // A node with mounts below is pushed back with the number of results
+    // it is waiting for, above its children and mounted nodes.
+    let mut stack = vec![(self, node, None)];
+    let mut results: Vec<SubtreeStats> = Vec::new();
+    while let Some((tree, node, pending)) = stack.pop() {
+      match pending {
+        Some(count) => {
+          let mut stats = node.own_stats();
+          for child_stats in results.drain(results.len() - count..) {
+            stats.add_child(child_stats);
+          }
+          results.push(stats);
+        },
+        None if !tree.has_mounts_below(node) => {
+          results.push(node.stats());
+        },
+        None => {
+          let mounted = tree.mounts.get(&node.path)
+            .and_then(|mounted| Some((mounted, mounted.root.as_ref()?)));
+          let count = node.children.len() + mounted.map_or(0, |(_, root)| root.children.len());
+          stack.push((tree, node, Some(count)));
+          stack.extend(node.children.iter().map(|child| (tree, child, None)));
+          if let Some((mounted, root)) = mounted {
+            stack.extend(root.children.iter().map(|child| (mounted, child, None)));
+          }
+        },
+      }
+    }
+    results.pop().unwrap_or_default()
+  }
+
+  /// Checks whether a tree is mounted on `node` or below it.
+  fn has_mounts_below(&self, node: &Node<T>) -> bool {
+    let prefix = format!("{}/", node.path);
+    self.mounts.keys().any(|path| {
+      node.path == "/" || *path == node.path || path.starts_with(&prefix)
+    })
+  }
+
+  /// Drops the path index, which is rebuilt on the next lookup by path.
+  ///
+  /// The `Tree` API keeps the index up to date, this must only be called
+  /// after nodes are added, removed or renamed directly through `root` or
+  /// through the nodes handed out by `Tree::find_by_path_mut` and
+  /// `Tree::find_mut`.
+  pub fn invalidate_index(&self) {
+    self.index.replace(None);
+  }
+
+  /// Gets the route to the node at the normalized `path` from the path
+  /// index, building the index if needed.
+  fn route_to(&self, path: &str) -> Option<Vec<Step>> {
+    self.root.as_ref()?;
+    if path == "/" {
+      return Some(Vec::new());
+    }
+
+    for _ in 0..2 {
+      let route = self.index.borrow_mut().get_or_insert_with(|| self.build_index()).get(path).cloned()?;
+      // A route broken by a direct change to the nodes leads elsewhere.
+      let leads_to_path = self.follow(&route).is_some_and(|node| {
+        path.strip_suffix(node.path.as_str()).is_some_and(|rest| rest.is_empty() || rest.ends_with('/'))
+      });
+      if leads_to_path {
+        return Some(route);
+      }
+      self.invalidate_index();
+    }
+    None
+  }
+
+  /// Builds the routes to every node, keeping the first node in pre-order
+  /// when several have the same path.
+  fn build_index(&self) -> HashMap<String, Vec<Step>> {
+    let mut index = HashMap::new();
+    self.index_nodes(Vec::new(), &mut index);
+    index
+  }
+
+  /// Adds the routes to the node at `route` and to its descendants to the
+  /// path index, if it is built, after it was added or merged with another.
+  fn index_subtree(&mut self, route: Vec<Step>) {
+    if let Some(mut index) = self.index.get_mut().take() {
+      self.index_nodes(route, &mut index);
+      *self.index.get_mut() = Some(index);
+    }
+  }
+
+  /// Adds the routes to the node at `route` and to its descendants to
+  /// `index`, keeping the routes already there.
+  fn index_nodes(&self, route: Vec<Step>, index: &mut HashMap<String, Vec<Step>>) {
+    let mut tree = self;
+    let mut node = match &self.root {
+      Some(root) => root,
+      None => return,
+    };
+    let mut prefix = String::new();
+    for step in &route {
+      let found = match *step {
+        Step::Child(i) => node.children.get(i),
+        Step::Mount(i) => tree.mounts.get(&node.path).and_then(|mounted| {
+          prefix = Tree::join(&prefix, &node.path);
+          tree = mounted;
+          mounted.root.as_ref()?.children.get(i)
+        }),
+      };
+      node = match found {
+        Some(found) => found,
+        None => return,
+      };
+    }
+
+    // Every entry holds the route to its node and the index of the mount
+    // path of its tree in `prefixes`.
+    let mut prefixes = vec![prefix];
+    let mut stack = vec![(tree, node, route, 0)];
+    while let Some((tree, node, route, prefix)) = stack.pop() {
+      if let Some(mounted) = tree.mounts.get(&node.path) {
+        if let Some(root) = &mounted.root {
+          prefixes.push(Tree::join(&prefixes[prefix], &node.path));
+          let mount_prefix = prefixes.len() - 1;
+          for (i, child) in root.children.iter().enumerate().rev() {
+            let mut route = route.clone();
+            route.push(Step::Mount(i));
+            stack.push((mounted, child, route, mount_prefix));
+          }
+        }
+      }
+      for (i, child) in node.children.iter().enumerate().rev() {
+        let mut route = route.clone();
+        route.push(Step::Child(i));
+        stack.push((tree, child, route, prefix));
+      }
+      if node.depth > 0 {
+        index.entry(Tree::join(&prefixes[prefix], &node.path)).or_insert(route);
+      }
+    }
+  }
+
+  /// Builds the route from `node` to the first node, in pre-order, matching
+  /// `predicate`.
+  fn route_by<P: FnMut(&Node<T>) -> bool>(&self, node: &Node<T>, predicate: &mut P, route: &mut Vec<Step>) -> bool {
+    // Every entry holds the length of the route to the parent of its node
+    // and the step from the parent.
+    let start = route.len();
+    let mut stack = vec![(self, node, start, None)];
+    while let Some((tree, node, len, step)) = stack.pop() {
+      route.truncate(len);
+      route.extend(step);
+      if predicate(node) {
+        return true;
+      }
+
+      let len = route.len();
+      if let Some(mounted) = tree.mounts.get(&node.path) {
+        if let Some(root) = &mounted.root {
+          for (i, child) in root.children.iter().enumerate().rev() {
+            stack.push((mounted, child, len, Some(Step::Mount(i))));
+          }
+        }
+      }
+      for (i, child) in node.children.iter().enumerate().rev() {
+        stack.push((tree, child, len, Some(Step::Child(i))));
+      }
+    }
+    route.truncate(start);
+    false
+  }
+
+  /// Follows a route from the root.
+  fn follow(&self, route: &[Step]) -> Option<&Node<T>> {
+    self.follow_with_tree(route).map(|(_, node)| node)
+  }
+
+  /// Follows a route from the root like `Tree::follow`, also returning the
+  /// tree holding the node.
+  fn follow_with_tree(&self, route: &[Step]) -> Option<(&Tree<T>, &Node<T>)> {
+    let mut tree = self;
+    let mut node = self.root.as_ref()?;
+    for step in route {
+      match *step {
+        Step::Child(i) => {
+          node = node.children.get(i)?;
+        },
+        Step::Mount(i) => {
+          tree = tree.mounts.get(&node.path)?;
+          node = tree.root.as_ref()?.children.get(i)?;
+        },
+      }
+    }
+    Some((tree, node))
+  }
+
+  /// Follows a route from the root, invalidating the cached statistics of
+  /// every node on the way.
+  fn follow_mut(&mut self, route: &[Step]) -> Option<&mut Node<T>> {
+    self.follow_mut_with_mounts(route).map(|(node, _)| node)
+  }
+
+  /// Follows a route from the root like `Tree::follow_mut`, also returning
+  /// the mounts of the tree holding the node.
+  ///
+  /// The path index is kept, the callers changing the structure update it.
+  fn follow_mut_with_mounts(&mut self, route: &[Step]) -> Option<(&mut Node<T>, &mut Mounts<T>)> {
+    let mut node = self.root.as_mut()?;
+    let mut mounts = &mut self.mounts;
+    for step in route {
+      node.invalidate_stats();
+      match *step {
+        Step::Child(i) => {
+          node = node.children.get_mut(i)?;
+        },
+        Step::Mount(i) => {
+          let mounted = mounts.get_mut(&node.path)?;
+          let root = mounted.root.as_mut()?;
+          root.invalidate_stats();
+          node = root.children.get_mut(i)?;
+          mounts = &mut mounted.mounts;
+        },
       }
     }
+    node.invalidate_stats();
+    Some((node, mounts))
   }
 }
\ No newline at end of file
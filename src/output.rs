@@ -0,0 +1,145 @@
+//! The formats a tree can be written in, and where to write it.
+//!
+//! A tree is scanned or loaded once, then every requested output is written
+//! from it, so several formats cost a single scan.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::node::*;
+use crate::render::{write_paths, RenderOptions};
+
+/// Represents a format a tree can be written in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+  /// The format of `Tree::render_to`.
+  Text,
+  /// The format of `Tree::write_json`.
+  Json,
+  /// The format of `Tree::write_cache`.
+  Cache,
+  /// The format of `Tree::write_xml`.
+  Xml,
+  /// The format of `Tree::write_html`, linking the entries under
+  /// `base_url`.
+  Html { base_url: String },
+  /// The format of `Tree::write_yaml`.
+  Yaml,
+  /// The format of `Tree::to_dot`.
+  Dot,
+  /// The format of `Tree::to_mermaid`.
+  Mermaid,
+  /// The format of `Tree::write_csv`.
+  Csv,
+  /// The format of `Tree::write_jsonl`.
+  Jsonl,
+  /// The lines of `Tree::to_paths`.
+  Paths,
+  /// The format of `Tree::write_script`.
+  Script,
+}
+
+impl Format {
+  /// The names of the formats, as accepted by `Format::parse`.
+  pub const NAMES: &'static [&'static str] = &["text", "json", "cache", "xml", "html", "yaml", "dot", "mermaid", "csv", "jsonl", "paths", "script"];
+
+  /// Parses the name of a format. HTML pages link the entries relatively
+  /// to the page.
+  ///
+  /// # Returns
+  ///
+  /// The format, or a description of the problem if the name is unknown.
+  pub fn parse(name: &str) -> Result<Format, String> {
+    match name {
+      "text" => Ok(Format::Text),
+      "json" => Ok(Format::Json),
+      "cache" => Ok(Format::Cache),
+      "xml" => Ok(Format::Xml),
+      "html" => Ok(Format::Html { base_url: ".".to_string() }),
+      "yaml" => Ok(Format::Yaml),
+      "dot" => Ok(Format::Dot),
+      "mermaid" => Ok(Format::Mermaid),
+      "csv" => Ok(Format::Csv),
+      "jsonl" => Ok(Format::Jsonl),
+      "paths" => Ok(Format::Paths),
+      "script" => Ok(Format::Script),
+      _ => Err(format!("unknown format '{}', expected one of {}", name, Format::NAMES.join(", "))),
+    }
+  }
+
+  /// Writes `tree` to `out` in this format, `options` being used by the
+  /// text format and the path list.
+  pub fn write_to<W: Write>(&self, tree: &Tree, out: &mut W, options: &RenderOptions) -> io::Result<()> {
+    match self {
+      Format::Text => tree.render_to(out, options),
+      Format::Json => tree.write_json(out),
+      Format::Cache => tree.write_cache(out),
+      Format::Xml => tree.write_xml(out),
+      Format::Html { base_url } => tree.write_html(out, base_url),
+      Format::Yaml => tree.write_yaml(out),
+      Format::Dot => out.write_all(tree.to_dot().as_bytes()),
+      Format::Mermaid => out.write_all(tree.to_mermaid().as_bytes()),
+      Format::Csv => tree.write_csv(out),
+      Format::Jsonl => tree.write_jsonl(out),
+      Format::Paths => write_paths(out, tree.to_paths(), options.print0),
+      Format::Script => tree.write_script(out),
+    }
+  }
+}
+
+/// Represents an output of a run: a format and its destination.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Output {
+  pub format: Format,
+  /// The file to write to, `None` for stdout.
+  pub path: Option<PathBuf>,
+}
+
+impl Output {
+  /// Parses an output written `FORMAT` or `FORMAT:FILE`, `-` being stdout.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::PathBuf;
+  /// use rust_tree::output::{Format, Output};
+  ///
+  /// let output = Output::parse("json:tree.json").unwrap();
+  /// assert_eq!(output.format, Format::Json);
+  /// assert_eq!(output.path, Some(PathBuf::from("tree.json")));
+  /// assert_eq!(Output::parse("text:-").unwrap().path, None);
+  /// assert!(Output::parse("svg").is_err());
+  /// ```
+  pub fn parse(spec: &str) -> Result<Output, String> {
+    let (format, path) = match spec.split_once(':') {
+      Some((format, path)) => (format, Some(path)),
+      None => (spec, None),
+    };
+    let path = match path {
+      Some("") => return Err(format!("'{}' needs a file after the colon", spec)),
+      Some("-") | None => None,
+      Some(path) => Some(PathBuf::from(path)),
+    };
+    Ok(Output {
+      format: Format::parse(format)?,
+      path,
+    })
+  }
+
+  /// Writes `tree` to the destination of the output.
+  pub fn write(&self, tree: &Tree, options: &RenderOptions) -> io::Result<()> {
+    match &self.path {
+      Some(path) => {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.format.write_to(tree, &mut out, options)?;
+        out.flush()
+      },
+      None => {
+        let mut out = io::stdout().lock();
+        self.format.write_to(tree, &mut out, options)?;
+        out.flush()
+      },
+    }
+  }
+}
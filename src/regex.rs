@@ -0,0 +1,443 @@
+//! Regular expressions matching the names and paths of a tree.
+//!
+//! The syntax is the common part of the POSIX extended and Perl ones:
+//! literals, `.`, `[...]` classes with ranges and `^` negation, the `\d`,
+//! `\w` and `\s` classes and their negations, the `^` and `$` anchors,
+//! `(...)` groups with `|` alternatives, and the `*`, `+`, `?` and
+//! `{n,m}` repetitions. A pattern matches a text if it matches a part of
+//! it, so `^` and `$` anchor it to the whole text.
+//!
+//! Patterns are compiled to an automaton run over every character of the
+//! text at once, so matching takes a time linear in the length of the text
+//! whatever the pattern.
+
+use std::mem;
+
+use crate::node::*;
+
+/// The most repetitions of `{n,m}`, every one being compiled.
+const MAX_REPEAT: u32 = 1000;
+
+/// Represents a character class like `[a-z]` or `\d`.
+#[derive(Clone, Debug)]
+struct Class {
+  items: Vec<ClassItem>,
+  negated: bool,
+}
+
+#[derive(Clone, Debug)]
+enum ClassItem {
+  Range(char, char),
+  /// One of the `d`, `w` and `s` classes, and their negation in uppercase.
+  Shorthand(char),
+}
+
+impl Class {
+  fn shorthand(kind: char) -> Class {
+    Class { items: vec![ClassItem::Shorthand(kind)], negated: false }
+  }
+
+  fn matches(&self, c: char) -> bool {
+    let found = self.items.iter().any(|item| match *item {
+      ClassItem::Range(low, high) => low <= c && c <= high,
+      ClassItem::Shorthand(kind) => {
+        let matched = match kind.to_ascii_lowercase() {
+          'd' => c.is_ascii_digit(),
+          'w' => c.is_alphanumeric() || c == '_',
+          _ => c.is_whitespace(),
+        };
+        matched != kind.is_ascii_uppercase()
+      },
+    });
+    found != self.negated
+  }
+}
+
+/// Represents a parsed pattern.
+#[derive(Clone, Debug)]
+enum Ast {
+  Literal(char),
+  Any,
+  Class(Class),
+  Start,
+  End,
+  Concat(Vec<Ast>),
+  Alternate(Vec<Ast>),
+  Repeat { ast: Box<Ast>, min: u32, max: Option<u32> },
+}
+
+/// Represents an instruction of a compiled pattern.
+#[derive(Clone, Debug)]
+enum Inst {
+  Char(char),
+  Any,
+  Class(Class),
+  Start,
+  End,
+  /// Go on at both instructions.
+  Split(usize, usize),
+  Jump(usize),
+  Match,
+}
+
+/// Represents a compiled regular expression.
+#[derive(Clone, Debug)]
+pub struct Regex {
+  pattern: String,
+  program: Vec<Inst>,
+}
+
+impl Regex {
+  /// Compiles `pattern`.
+  ///
+  /// # Returns
+  ///
+  /// The regular expression, or a description of the problem if the
+  /// pattern is invalid.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::Regex;
+  ///
+  /// let regex = Regex::new(r"^(main|lib)\.rs$").unwrap();
+  /// assert!(regex.is_match("main.rs"));
+  /// assert!(!regex.is_match("main.rs.bak"));
+  /// assert!(Regex::new(r"test_\d{2,}").unwrap().is_match("src/test_042.py"));
+  /// assert_eq!(Regex::new("(a").unwrap_err(), "(a: unclosed group");
+  /// ```
+  pub fn new(pattern: &str) -> Result<Regex, String> {
+    let mut parser = Parser { chars: pattern.chars().collect(), pos: 0 };
+    let ast = parser.alternation().map_err(|e| format!("{}: {}", pattern, e))?;
+    if parser.pos < parser.chars.len() {
+      return Err(format!("{}: unmatched ')'", pattern));
+    }
+    let mut program = Vec::new();
+    compile(&ast, &mut program);
+    program.push(Inst::Match);
+    Ok(Regex { pattern: pattern.to_string(), program })
+  }
+
+  /// Gets the pattern the regular expression was compiled from.
+  pub fn as_str(&self) -> &str {
+    &self.pattern
+  }
+
+  /// Checks whether the regular expression matches a part of `text`.
+  pub fn is_match(&self, text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let mut current = Vec::new();
+    let mut next = Vec::new();
+    // The last position every instruction was reached at.
+    let mut seen = vec![usize::MAX; self.program.len()];
+    for pos in 0..=chars.len() {
+      // A match may start at every position.
+      if self.add(&mut current, &mut seen, 0, pos, chars.len()) {
+        return true;
+      }
+      for &pc in &current {
+        let matched = match (&self.program[pc], chars.get(pos)) {
+          (Inst::Char(expected), Some(c)) => expected == c,
+          (Inst::Any, Some(_)) => true,
+          (Inst::Class(class), Some(c)) => class.matches(*c),
+          _ => false,
+        };
+        if matched && self.add(&mut next, &mut seen, pc + 1, pos + 1, chars.len()) {
+          return true;
+        }
+      }
+      current = mem::take(&mut next);
+    }
+    false
+  }
+
+  /// Adds the instruction `pc` at the position `pos` to `threads`,
+  /// following the jumps and the anchors.
+  ///
+  /// # Returns
+  ///
+  /// Whether the pattern matched.
+  fn add(&self, threads: &mut Vec<usize>, seen: &mut [usize], pc: usize, pos: usize, len: usize) -> bool {
+    if seen[pc] == pos {
+      return false;
+    }
+    seen[pc] = pos;
+    match self.program[pc] {
+      Inst::Match => true,
+      Inst::Jump(to) => self.add(threads, seen, to, pos, len),
+      Inst::Split(first, second) => self.add(threads, seen, first, pos, len) || self.add(threads, seen, second, pos, len),
+      Inst::Start => pos == 0 && self.add(threads, seen, pc + 1, pos, len),
+      Inst::End => pos == len && self.add(threads, seen, pc + 1, pos, len),
+      Inst::Char(_) | Inst::Any | Inst::Class(_) => {
+        threads.push(pc);
+        false
+      },
+    }
+  }
+}
+
+/// Appends the instructions of `ast` to `program`.
+fn compile(ast: &Ast, program: &mut Vec<Inst>) {
+  match ast {
+    Ast::Literal(c) => program.push(Inst::Char(*c)),
+    Ast::Any => program.push(Inst::Any),
+    Ast::Class(class) => program.push(Inst::Class(class.clone())),
+    Ast::Start => program.push(Inst::Start),
+    Ast::End => program.push(Inst::End),
+    Ast::Concat(asts) => asts.iter().for_each(|ast| compile(ast, program)),
+    Ast::Alternate(asts) => {
+      let mut jumps = Vec::new();
+      for (i, ast) in asts.iter().enumerate() {
+        if i + 1 == asts.len() {
+          compile(ast, program);
+          break;
+        }
+        let split = program.len();
+        program.push(Inst::Split(split + 1, 0));
+        compile(ast, program);
+        jumps.push(program.len());
+        program.push(Inst::Jump(0));
+        program[split] = Inst::Split(split + 1, program.len());
+      }
+      let end = program.len();
+      for jump in jumps {
+        program[jump] = Inst::Jump(end);
+      }
+    },
+    Ast::Repeat { ast, min, max } => {
+      for _ in 0..*min {
+        compile(ast, program);
+      }
+      match max {
+        None => {
+          let split = program.len();
+          program.push(Inst::Split(split + 1, 0));
+          compile(ast, program);
+          program.push(Inst::Jump(split));
+          program[split] = Inst::Split(split + 1, program.len());
+        },
+        Some(max) => {
+          for _ in *min..*max {
+            let split = program.len();
+            program.push(Inst::Split(split + 1, 0));
+            compile(ast, program);
+            program[split] = Inst::Split(split + 1, program.len());
+          }
+        },
+      }
+    },
+  }
+}
+
+/// The state of the parsing of a pattern.
+struct Parser {
+  chars: Vec<char>,
+  pos: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn alternation(&mut self) -> Result<Ast, String> {
+    let mut alternatives = vec![self.concat()?];
+    while self.peek() == Some('|') {
+      self.pos += 1;
+      alternatives.push(self.concat()?);
+    }
+    Ok(match alternatives.len() {
+      1 => alternatives.remove(0),
+      _ => Ast::Alternate(alternatives),
+    })
+  }
+
+  fn concat(&mut self) -> Result<Ast, String> {
+    let mut asts = Vec::new();
+    while let Some(c) = self.peek().filter(|c| *c != '|' && *c != ')') {
+      let atom = match c {
+        '*' | '+' | '?' => return Err("nothing to repeat".to_string()),
+        '{' if self.repetition().is_some() => return Err("nothing to repeat".to_string()),
+        _ => self.atom()?,
+      };
+      asts.push(self.repeated(atom)?);
+    }
+    Ok(Ast::Concat(asts))
+  }
+
+  /// Parses the repetitions following `ast`, if any.
+  fn repeated(&mut self, mut ast: Ast) -> Result<Ast, String> {
+    loop {
+      let (min, max) = match self.peek() {
+        Some('*') => (0, None),
+        Some('+') => (1, None),
+        Some('?') => (0, Some(1)),
+        Some('{') => match self.repetition() {
+          Some((min, max, len)) => {
+            self.pos += len - 1;
+            (min, max)
+          },
+          None => return Ok(ast),
+        },
+        _ => return Ok(ast),
+      };
+      self.pos += 1;
+      if max.is_some_and(|max| max < min) {
+        return Err("invalid repetition".to_string());
+      }
+      if min.max(max.unwrap_or(0)) > MAX_REPEAT {
+        return Err(format!("repetition over {}", MAX_REPEAT));
+      }
+      // The lazy repetitions match the same texts.
+      if self.peek() == Some('?') {
+        self.pos += 1;
+      }
+      ast = Ast::Repeat { ast: Box::new(ast), min, max };
+    }
+  }
+
+  /// Reads the `{n}`, `{n,}` or `{n,m}` repetition at the current position
+  /// without consuming it.
+  ///
+  /// # Returns
+  ///
+  /// Its bounds and length, or `None` if there is no repetition, the brace
+  /// being a literal.
+  fn repetition(&self) -> Option<(u32, Option<u32>, usize)> {
+    let rest: String = self.chars[self.pos..].iter().collect();
+    let end = rest.find('}')?;
+    let bounds = &rest[1..end];
+    let number = |text: &str| text.parse::<u32>().ok().filter(|_| text.chars().all(|c| c.is_ascii_digit()));
+    let (min, max) = match bounds.split_once(',') {
+      Some((min, "")) => (number(min)?, None),
+      Some((min, max)) => (number(min)?, Some(number(max)?)),
+      None => (number(bounds)?, Some(number(bounds)?)),
+    };
+    Some((min, max, bounds.chars().count() + 2))
+  }
+
+  fn atom(&mut self) -> Result<Ast, String> {
+    let c = self.peek().ok_or("unexpected end")?;
+    self.pos += 1;
+    match c {
+      '(' => {
+        // Groups do not capture anyway.
+        if self.chars[self.pos..].starts_with(&['?', ':']) {
+          self.pos += 2;
+        }
+        let ast = self.alternation()?;
+        if self.peek() != Some(')') {
+          return Err("unclosed group".to_string());
+        }
+        self.pos += 1;
+        Ok(ast)
+      },
+      '[' => self.class().map(Ast::Class),
+      '.' => Ok(Ast::Any),
+      '^' => Ok(Ast::Start),
+      '$' => Ok(Ast::End),
+      '\\' => match self.escape()? {
+        ClassItem::Shorthand(kind) => Ok(Ast::Class(Class::shorthand(kind))),
+        ClassItem::Range(c, _) => Ok(Ast::Literal(c)),
+      },
+      c => Ok(Ast::Literal(c)),
+    }
+  }
+
+  /// Parses the escape after a backslash, as a class or a character.
+  fn escape(&mut self) -> Result<ClassItem, String> {
+    let c = self.peek().ok_or("trailing backslash")?;
+    self.pos += 1;
+    Ok(match c {
+      'd' | 'w' | 's' | 'D' | 'W' | 'S' => ClassItem::Shorthand(c),
+      'n' => ClassItem::Range('\n', '\n'),
+      't' => ClassItem::Range('\t', '\t'),
+      'r' => ClassItem::Range('\r', '\r'),
+      c if c.is_ascii_alphanumeric() => return Err(format!("unknown escape '\\{}'", c)),
+      c => ClassItem::Range(c, c),
+    })
+  }
+
+  /// Parses a class after its opening bracket.
+  fn class(&mut self) -> Result<Class, String> {
+    let negated = self.peek() == Some('^');
+    if negated {
+      self.pos += 1;
+    }
+    let mut items = Vec::new();
+    loop {
+      let c = self.peek().ok_or("unclosed class")?;
+      self.pos += 1;
+      let low = match c {
+        // A bracket first is a literal.
+        ']' if !items.is_empty() => return Ok(Class { items, negated }),
+        '\\' => match self.escape()? {
+          ClassItem::Range(c, _) => c,
+          shorthand => {
+            items.push(shorthand);
+            continue;
+          },
+        },
+        c => c,
+      };
+      let high = match (self.peek(), self.chars.get(self.pos + 1)) {
+        (Some('-'), Some(&high)) if high != ']' => {
+          self.pos += 2;
+          match high {
+            '\\' => match self.escape()? {
+              ClassItem::Range(c, _) => c,
+              ClassItem::Shorthand(_) => return Err("invalid class range".to_string()),
+            },
+            high => high,
+          }
+        },
+        _ => low,
+      };
+      if high < low {
+        return Err("invalid class range".to_string());
+      }
+      items.push(ClassItem::Range(low, high));
+    }
+  }
+}
+
+impl Tree {
+  /// Finds all the nodes whose name or path, relative to the root, matches
+  /// the regular expression `pattern`, in pre-order and including mounted
+  /// trees.
+  ///
+  /// # Returns
+  ///
+  /// The nodes, or a description of the problem if the pattern is invalid.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["src/", "src/main.rs", "src/test_io.rs", "tests/", "tests/cli.rs"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  /// let found: Vec<String> = tree.find_regex("^test").unwrap().iter().map(|node| node.path()).collect();
+  /// assert_eq!(found, ["src/test_io.rs", "tests", "tests/cli.rs"]);
+  /// ```
+  pub fn find_regex(&self, pattern: &str) -> Result<Vec<&Node>, String> {
+    let regex = Regex::new(pattern)?;
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut found = Vec::new();
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth > 0 {
+        names.truncate(depth as usize - 1);
+        names.push(&node.name);
+        if regex.is_match(&node.name) || regex.is_match(&names.join("/")) {
+          found.push(node);
+        }
+      }
+      true
+    });
+    Ok(found)
+  }
+}
@@ -0,0 +1,254 @@
+//! Listing remote directories and buckets into trees.
+//!
+//! Remote hosts are listed with the `ssh` command, which runs `find` on them
+//! with the keys, agents and configuration of the user, and S3 buckets with
+//! the `aws` command, so its profiles, regions and credentials apply the
+//! same way.
+
+use std::collections::HashSet;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::date::utc_time;
+use crate::json::Json;
+use crate::log::{debug, warning};
+use crate::node::*;
+
+/// The most objects listed by a request to S3.
+const S3_PAGE: &str = "1000";
+
+/// Represents the location of a remote directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SshLocation {
+  /// The host, with the user to log in as if any, like `user@host`.
+  pub host: String,
+  pub port: Option<u16>,
+  /// The path of the directory on the host, relative to the home directory
+  /// of the user unless it starts with a slash.
+  pub path: String,
+}
+
+impl SshLocation {
+  /// Parses a location like `ssh://user@host:2222/srv/data`. A path
+  /// starting with `/~/` is relative to the home directory.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::remote::SshLocation;
+  ///
+  /// let location = SshLocation::parse("ssh://deploy@example.com:2222/srv/www").unwrap();
+  /// assert_eq!(location.host, "deploy@example.com");
+  /// assert_eq!(location.port, Some(2222));
+  /// assert_eq!(location.path, "/srv/www");
+  ///
+  /// assert_eq!(SshLocation::parse("ssh://example.com/~/logs").unwrap().path, "logs");
+  /// assert_eq!(SshLocation::parse("ssh://example.com").unwrap().path, ".");
+  /// assert!(SshLocation::parse("ssh://:22/srv").is_err());
+  /// assert!(SshLocation::parse("ssh://-oProxyCommand=id/srv").is_err());
+  /// ```
+  pub fn parse(url: &str) -> Result<SshLocation, String> {
+    let rest = url.strip_prefix("ssh://").ok_or_else(|| format!("{}: expected ssh://[user@]host[:port]/path", url))?;
+    let (authority, path) = match rest.find('/') {
+      Some(slash) => rest.split_at(slash),
+      None => (rest, ""),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+      Some((host, port)) => {
+        let port = port.parse().map_err(|_| format!("{}: invalid port '{}'", url, port))?;
+        (host, Some(port))
+      },
+      None => (authority, None),
+    };
+    if host.is_empty() || host.ends_with('@') {
+      return Err(format!("{}: missing host", url));
+    }
+    // It would be read as an option of ssh.
+    if host.starts_with('-') {
+      return Err(format!("{}: invalid host '{}'", url, host));
+    }
+    let path = match path {
+      "" | "/~" => ".".to_string(),
+      path => path.strip_prefix("/~/").unwrap_or(path).to_string(),
+    };
+    Ok(SshLocation { host: host.to_string(), port, path })
+  }
+}
+
+/// Represents the location of objects in an S3 bucket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct S3Location {
+  pub bucket: String,
+  /// The prefix of the keys of the objects, empty or ending with a slash.
+  pub prefix: String,
+}
+
+impl S3Location {
+  /// Parses a location like `s3://bucket/prefix`, the prefix being a
+  /// directory of the bucket.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::remote::S3Location;
+  ///
+  /// let location = S3Location::parse("s3://lake/raw/2024").unwrap();
+  /// assert_eq!(location.bucket, "lake");
+  /// assert_eq!(location.prefix, "raw/2024/");
+  /// assert_eq!(S3Location::parse("s3://lake").unwrap().prefix, "");
+  /// assert!(S3Location::parse("s3:///raw").is_err());
+  /// ```
+  pub fn parse(url: &str) -> Result<S3Location, String> {
+    let rest = url.strip_prefix("s3://").ok_or_else(|| format!("{}: expected s3://bucket/prefix", url))?;
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+      return Err(format!("{}: missing bucket", url));
+    }
+    let prefix = match prefix.trim_start_matches('/') {
+      "" => String::new(),
+      prefix if prefix.ends_with('/') => prefix.to_string(),
+      prefix => format!("{}/", prefix),
+    };
+    Ok(S3Location { bucket: bucket.to_string(), prefix })
+  }
+}
+
+/// Parses the ISO 8601 dates of S3, like `2024-03-09T14:05:00.000Z`,
+/// which are in UTC.
+fn parse_s3_date(date: &str) -> Option<SystemTime> {
+  let field = |range: std::ops::Range<usize>| date.get(range)?.parse::<u32>().ok();
+  let year = field(0..4)?;
+  Some(utc_time(i64::from(year), field(5..7)?, field(8..10)?, field(11..13)?, field(14..16)?, field(17..19)?))
+}
+
+/// Quotes `text` for a POSIX shell.
+fn shell_quote(text: &str) -> String {
+  format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+impl Tree {
+  /// Builds the tree of a remote directory from the output of `find`, run
+  /// on the host with `ssh`, with the sizes, modification times and modes
+  /// of the entries. The root is named after `url`, see
+  /// `SshLocation::parse`.
+  ///
+  /// The host needs the `-printf` action of GNU find. Entries `find` cannot
+  /// read are left out with a warning.
+  pub fn from_ssh(url: &str) -> Result<Tree, String> {
+    let location = SshLocation::parse(url)?;
+    let mut command = Command::new("ssh");
+    if let Some(port) = location.port {
+      command.arg("-p").arg(port.to_string());
+    }
+    // The remote shell gets the words joined with spaces.
+    command.arg("--").arg(&location.host).args([
+      "find".to_string(),
+      shell_quote(&location.path),
+      "-mindepth 1".to_string(),
+      "-printf".to_string(),
+      shell_quote("%y %s %T@ %m %P\\0"),
+    ]);
+    let output = command.output().map_err(|e| format!("ssh: {}", e))?;
+    let errors = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+      if output.stdout.is_empty() {
+        return Err(errors.trim().to_string());
+      }
+      errors.lines().for_each(|line| warning!("{}", line));
+    }
+
+    let mut tree = Tree::with_root(url, None);
+    for record in output.stdout.split(|&byte| byte == 0).filter(|record| !record.is_empty()) {
+      let record = String::from_utf8_lossy(record);
+      // <type> <size> <mtime> <mode> <path>
+      let fields: Vec<&str> = record.splitn(5, ' ').collect();
+      let [kind, size, modified, permissions, path] = fields[..] else {
+        return Err(format!("ssh: unexpected find entry '{}'", record));
+      };
+      let file_type = match kind {
+        "d" => 0o040000,
+        "l" => 0o120000,
+        "p" => 0o010000,
+        "c" => 0o020000,
+        "b" => 0o060000,
+        "s" => 0o140000,
+        _ => 0o100000,
+      };
+      let line = match kind {
+        "d" => format!("{}/", path),
+        _ => path.to_string(),
+      };
+      if let Some(mut node) = Node::from_path(&line) {
+        node.meta.size = size.parse().ok();
+        node.meta.modified = modified.parse::<f64>().ok()
+          .filter(|secs| *secs >= 0.0)
+          .map(|secs| UNIX_EPOCH + Duration::from_secs_f64(secs));
+        node.meta.mode = u32::from_str_radix(permissions, 8).ok().map(|permissions| file_type | permissions);
+        tree.insert(node);
+      }
+    }
+    Ok(tree)
+  }
+
+  /// Builds the tree of the objects of an S3 bucket below a prefix, listed
+  /// page by page with `aws s3api list-objects-v2`, with their sizes and
+  /// modification times. The directories are the prefixes of the keys up
+  /// to a slash. The root is named after `url`, see `S3Location::parse`.
+  pub fn from_s3(url: &str) -> Result<Tree, String> {
+    let location = S3Location::parse(url)?;
+    let mut tree = Tree::with_root(url, None);
+    let mut directories: HashSet<String> = HashSet::new();
+    let mut token: Option<String> = None;
+    loop {
+      let mut command = Command::new("aws");
+      command.args(["s3api", "list-objects-v2", "--output", "json", "--max-items", S3_PAGE])
+        .args(["--bucket", &location.bucket, "--prefix", &location.prefix]);
+      if let Some(token) = &token {
+        command.args(["--starting-token", token]);
+      }
+      let output = command.output().map_err(|e| format!("aws: {}", e))?;
+      if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+      }
+      // An empty listing has no output at all.
+      let text = String::from_utf8_lossy(&output.stdout);
+      if text.trim().is_empty() {
+        break;
+      }
+      let page = Json::parse(&text).map_err(|e| format!("aws: {}", e))?;
+
+      let objects = page.get("Contents").and_then(Json::as_array).unwrap_or(&[]);
+      debug!("Listed {} objects of {}", objects.len(), url);
+      for object in objects {
+        let Some(key) = object.get("Key").and_then(Json::as_str).and_then(|key| key.strip_prefix(location.prefix.as_str())) else {
+          continue;
+        };
+        // The missing directories of the key, from the top.
+        let mut end = 0;
+        while let Some(slash) = key[end..].find('/') {
+          end += slash + 1;
+          if directories.insert(key[..end].to_string()) {
+            if let Some(node) = Node::from_path(&key[..end]) {
+              tree.insert(node);
+            }
+          }
+        }
+        // The keys ending with a slash are folders of the S3 console.
+        if key.ends_with('/') {
+          continue;
+        }
+        if let Some(mut node) = Node::from_path(key) {
+          node.meta.size = object.get("Size").and_then(Json::as_u64);
+          node.meta.modified = object.get("LastModified").and_then(Json::as_str).and_then(parse_s3_date);
+          tree.insert(node);
+        }
+      }
+
+      match page.get("NextToken").and_then(Json::as_str) {
+        Some(next) => token = Some(next.to_string()),
+        None => break,
+      }
+    }
+    Ok(tree)
+  }
+}
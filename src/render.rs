@@ -0,0 +1,508 @@
+//! Options of the text output, the format of `Tree::display`.
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use crate::date::{format_date, DateStyle};
+use crate::git::GitStatuses;
+use crate::ignore::IgnoreRules;
+use crate::node::*;
+use crate::size::format_size;
+use crate::xattr::marker;
+
+/// Represents how the names of the entries are written, for the ones
+/// holding control characters which would mess up terminals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameStyle {
+  /// As they are.
+  #[default]
+  Literal,
+  /// With the control characters replaced by `?`, like `ls -q`.
+  Hidden,
+  /// Between double quotes, with the quotes, the backslashes and the
+  /// control characters escaped by backslashes, like `ls -Q`.
+  Quoted,
+}
+
+impl NameStyle {
+  /// Writes `name` in this style.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::render::NameStyle;
+  ///
+  /// assert_eq!(NameStyle::Literal.apply("a\x1b[2Jb"), "a\x1b[2Jb");
+  /// assert_eq!(NameStyle::Hidden.apply("a\x1b[2Jb\n"), "a?[2Jb?");
+  /// assert_eq!(NameStyle::Quoted.apply("say \"hi\"\n\x7f"), "\"say \\\"hi\\\"\\n\\177\"");
+  /// ```
+  pub fn apply(&self, name: &str) -> String {
+    match self {
+      NameStyle::Literal => name.to_string(),
+      NameStyle::Hidden => name.chars().map(|c| if c.is_control() { '?' } else { c }).collect(),
+      NameStyle::Quoted => {
+        let mut quoted = String::from('"');
+        for c in name.chars() {
+          match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\{:03o}", c as u32)),
+            c => quoted.push(c),
+          }
+        }
+        quoted.push('"');
+        quoted
+      },
+    }
+  }
+}
+
+/// Represents the options of the text output.
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+  /// How the modification times are rendered, `None` to leave them out.
+  pub date_style: Option<DateStyle>,
+  /// The width to lay the files of every directory out in columns within,
+  /// like `ls -C`, rather than one entry per file. `None` for entries.
+  pub columns: Option<usize>,
+  /// The depth below which directories are collapsed into a summary of
+  /// their content, `None` to expand everything.
+  pub collapse_depth: Option<u64>,
+  /// The depth of the shallowest entries shown, named by their path from
+  /// the root, 0 to show the root.
+  pub min_depth: u64,
+  /// The depth of the deepest entries shown, `None` for no limit.
+  pub max_depth: Option<u64>,
+  /// Whether the directories with a single entry, a directory, are merged
+  /// with it, like `src/main/java`.
+  pub compact: bool,
+  /// Whether the entries are named by their full path, starting with the
+  /// name of the root, rather than by their name.
+  pub full_path: bool,
+  /// How the names of the entries and the targets of the links are
+  /// written.
+  pub names: NameStyle,
+  /// Whether the paths of `Tree::write_leaves` and of the path lists end
+  /// with a NUL byte rather than a line break, for `xargs -0`.
+  pub print0: bool,
+  /// The most entries of a directory whose content is shown, the larger
+  /// directories being marked instead. `None` for no limit.
+  pub file_limit: Option<usize>,
+  /// The git statuses the entries are marked with, by path relative to the
+  /// root, `None` for no marks. The files laid out in columns are not
+  /// marked.
+  pub git_status: Option<GitStatuses>,
+  /// The patterns of the entries highlighted, in reverse video with
+  /// colors and marked `[match]` otherwise. The files laid out in columns
+  /// are not highlighted.
+  pub highlight: IgnoreRules,
+  /// Whether the marks are colored with ANSI escape codes.
+  pub color: bool,
+  /// Whether the names of the extended attributes of the entries are
+  /// shown, along with their marks.
+  pub xattrs: bool,
+  /// Whether the inode numbers of the entries are shown.
+  pub inodes: bool,
+  /// Whether the device numbers of the entries are shown, and the mount
+  /// points marked.
+  pub devices: bool,
+  /// Whether the numbers of hard links of the entries are shown, and the
+  /// files met more than once in the tree marked.
+  pub links: bool,
+}
+
+impl Node {
+  /// Writes this node only to `out`, without its children, in the format
+  /// of `Tree::render_to`. Full paths are the path of the node in its
+  /// tree.
+  pub fn render_entry_to<W: Write>(&self, out: &mut W, options: &RenderOptions) -> io::Result<()> {
+    let label = if options.full_path { &self.path } else { &self.name };
+    self.render_entry(out, self.depth, &options.names.apply(label), options, SystemTime::now())
+  }
+
+  /// Writes this node only, indented by `level` levels and named `label`,
+  /// `now` being the reference of relative dates.
+  fn render_entry<W: Write>(&self, out: &mut W, level: u64, label: &str, options: &RenderOptions, now: SystemTime) -> io::Result<()> {
+    let linked;
+    let label = match &self.meta.link {
+      Some(link) => {
+        let target = options.names.apply(&link.target);
+        linked = match (link.broken, options.color) {
+          (true, true) => format!("{} -> {} \x1b[31m[broken]\x1b[0m", label, target),
+          (true, false) => format!("{} -> {} [broken]", label, target),
+          (false, _) => format!("{} -> {}", label, target),
+        };
+        &linked
+      },
+      None => label,
+    };
+    let marked;
+    let label = match marker(&self.meta.xattrs) {
+      Some(marker) => {
+        marked = format!("{} [{}]", label, marker);
+        &marked
+      },
+      None => label,
+    };
+    self.write_entry(out, level, label, &options.names.apply(&self.path))?;
+    let ds = "  ".repeat(level as usize);
+    if self.meta.error.is_some() {
+      let marker = match self.node_type {
+        NodeType::File() => "[error reading entry]",
+        NodeType::Directory() => "[error opening dir]",
+      };
+      match options.color {
+        true => writeln!(out, "{} \x1b[31m{}\x1b[0m", ds, marker)?,
+        false => writeln!(out, "{} {}", ds, marker)?,
+      }
+    }
+    match (self.meta.size, self.meta.compressed_size) {
+      (Some(size), Some(compressed)) => {
+        writeln!(out, "{} Size: {}, compressed: {}", ds, format_size(size), format_size(compressed))?;
+      },
+      (Some(size), None) => writeln!(out, "{} Size: {}", ds, format_size(size))?,
+      (None, _) => {},
+    }
+    if let Some(checksum) = &self.meta.checksum {
+      writeln!(out, "{} Checksum: {}", ds, checksum)?;
+    }
+    if options.xattrs && !self.meta.xattrs.is_empty() {
+      writeln!(out, "{} Xattrs: {}", ds, self.meta.xattrs.join(", "))?;
+    }
+    if let (true, Some((device, _))) = (options.devices, self.meta.inode) {
+      writeln!(out, "{} Device: {}", ds, device)?;
+    }
+    if let (true, Some((_, inode))) = (options.inodes, self.meta.inode) {
+      writeln!(out, "{} Inode: {}", ds, inode)?;
+    }
+    if let (true, Some(links)) = (options.links, self.meta.links) {
+      writeln!(out, "{} Links: {}", ds, links)?;
+    }
+    if let (Some(style), Some(modified)) = (options.date_style, self.meta.modified) {
+      writeln!(out, "{} Modified: {}", ds, format_date(modified, style, now))?;
+    }
+    Ok(())
+  }
+}
+
+impl Tree {
+  /// Writes the tree, including mounted trees, to `out` in the format of
+  /// `Tree::display` with the details selected by `options`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::time::UNIX_EPOCH;
+  /// use rust_tree::date::DateStyle;
+  /// use rust_tree::prelude::*;
+  /// use rust_tree::render::RenderOptions;
+  ///
+  /// let mut tree = Tree::new();
+  /// let mut node = Node::from_path("main.rs").unwrap();
+  /// node.meta.modified = Some(UNIX_EPOCH);
+  /// tree.insert(node);
+  ///
+  /// let options = RenderOptions { date_style: Some(DateStyle::Iso), ..RenderOptions::default() };
+  /// let mut out = Vec::new();
+  /// tree.render_to(&mut out, &options).unwrap();
+  /// assert!(String::from_utf8(out).unwrap().contains("Modified: 1970-01-01T00:00:00Z"));
+  /// ```
+  pub fn render_to<W: Write>(&self, out: &mut W, options: &RenderOptions) -> io::Result<()> {
+    if self.root.is_none() {
+      return writeln!(out, "No root node.");
+    }
+
+    let now = SystemTime::now();
+    // The full paths of the directories leading to the current node.
+    let mut paths: Vec<String> = Vec::new();
+    // The paths relative to the root of the directories leading to the
+    // current node, for the git statuses and the highlights.
+    let mut relatives: Vec<String> = Vec::new();
+    // The depth of the directory over the file limit being skipped.
+    let mut skipped: Option<u64> = None;
+    // The devices of the directories leading to the current node, for the
+    // mount points.
+    let mut devices: Vec<Option<u64>> = Vec::new();
+    // The names of the directories leading to the current node, for the
+    // entries named by their path at the min depth.
+    let mut names: Vec<String> = Vec::new();
+    // The labels of the directories leading to the current node merged
+    // with their only entry, and the numbers of levels they remove.
+    let mut chains: Vec<Option<String>> = Vec::new();
+    let mut shifts: Vec<u64> = Vec::new();
+    let shared = match options.links {
+      true => self.shared_inodes(),
+      false => HashSet::new(),
+    };
+    let mut result = Ok(());
+    self.walk(|tree, node, offset| {
+      let depth = node.depth + offset;
+      if skipped.is_some_and(|skipped| depth <= skipped) {
+        skipped = None;
+      }
+      let mounted = tree.mounts.get(&node.path).and_then(|mounted| mounted.root.as_ref());
+      let entries = node.children.len() + mounted.map_or(0, |root| root.children.len());
+      let label = match depth {
+        depth if options.full_path && depth > 0 => join(&paths[depth as usize - 1], &node.name),
+        _ => node.name.clone(),
+      };
+      let parent = depth.checked_sub(1).map(|parent| parent as usize);
+      let shift = parent.map_or(0, |parent| shifts[parent]);
+      let label = match parent.and_then(|parent| chains[parent].as_ref()) {
+        Some(chain) if !options.full_path => format!("{}/{}", chain, label),
+        _ => label,
+      };
+      let mut only_entries = node.children.iter().chain(mounted.into_iter().flat_map(|root| &root.children));
+      let merged = options.compact && node.node_type == NodeType::Directory() && depth >= options.min_depth.max(1)
+        && options.max_depth != Some(depth) && options.collapse_depth != Some(depth)
+        && entries == 1 && only_entries.next().is_some_and(|entry| entry.node_type == NodeType::Directory());
+      if node.node_type == NodeType::Directory() {
+        chains.truncate(depth as usize);
+        chains.push(merged.then(|| label.clone()));
+        shifts.truncate(depth as usize);
+        shifts.push(shift + u64::from(merged));
+      }
+      if options.full_path && node.node_type == NodeType::Directory() {
+        paths.truncate(depth as usize);
+        paths.push(label.clone());
+      }
+      let min = options.min_depth;
+      if depth < min && node.node_type == NodeType::Directory() {
+        names.truncate(depth as usize);
+        names.push(node.name.clone());
+      }
+      let label = match depth {
+        depth if depth == min && depth > 1 && !options.full_path => format!("{}/{}", names[1..].join("/"), label),
+        _ => label,
+      };
+      // The names are escaped once the paths made of them are built.
+      let label = options.names.apply(&label);
+      // The entries above the min depth and the merged directories are not
+      // shown.
+      let level = depth.saturating_sub(min) - shift;
+      let is_dir = node.node_type == NodeType::Directory();
+      let relative = match depth {
+        _ if options.git_status.is_none() && options.highlight.is_empty() => String::new(),
+        0 => String::new(),
+        depth if relatives[depth as usize - 1].is_empty() => node.name.clone(),
+        depth => format!("{}/{}", relatives[depth as usize - 1], node.name),
+      };
+      let label = match depth > 0 && options.highlight.is_ignored(&relative, is_dir) {
+        true if options.color => format!("\x1b[7m{}\x1b[0m", label),
+        true => format!("{} [match]", label),
+        false => label,
+      };
+      let label = match options.git_status.as_ref().and_then(|statuses| statuses.get(&relative)) {
+        Some(status) if options.color => format!("{} \x1b[{}m[{}]\x1b[0m", label, status.color(), status.marker()),
+        Some(status) => format!("{} [{}]", label, status.marker()),
+        None => label,
+      };
+      if is_dir {
+        relatives.truncate(depth as usize);
+        relatives.push(relative);
+      }
+      let label = match node.meta.inode {
+        Some(inode) if shared.contains(&inode) => format!("{} [hard link]", label),
+        _ => label,
+      };
+      let label = match options.devices {
+        true => {
+          let device = node.meta.inode.map(|(device, _)| device);
+          let parent = depth.checked_sub(1).and_then(|parent| devices[parent as usize]);
+          if node.node_type == NodeType::Directory() {
+            devices.truncate(depth as usize);
+            devices.push(device);
+          }
+          match (parent, device) {
+            (Some(parent), Some(device)) if parent != device && options.color => format!("{} \x1b[33m[mount point]\x1b[0m", label),
+            (Some(parent), Some(device)) if parent != device => format!("{} [mount point]", label),
+            _ => label,
+          }
+        },
+        false => label,
+      };
+      result = match (options.columns, &node.node_type) {
+        // The content of collapsed directories is summarized.
+        _ if merged || depth < min || options.max_depth.is_some_and(|max| depth > max) => Ok(()),
+        _ if skipped.is_some() || options.collapse_depth.is_some_and(|max| depth > max) => Ok(()),
+        (_, NodeType::Directory()) if options.collapse_depth == Some(depth) => {
+          node.render_entry(out, level, &label, options, now).and_then(|_| {
+            let ds = "  ".repeat(level as usize);
+            let stats = tree.subtree_stats(node);
+            match (stats.files, stats.directories - 1) {
+              (0, 0) => Ok(()),
+              (files, directories) => {
+                writeln!(out, "{} Collapsed: {} files, {} directories, {}", ds, files, directories, format_size(stats.size))
+              },
+            }
+          })
+        },
+        (_, NodeType::Directory()) if options.file_limit.is_some_and(|limit| entries > limit) => {
+          skipped = Some(depth);
+          node.render_entry(out, level, &label, options, now).and_then(|_| {
+            writeln!(out, "{} [{} entries exceeds filelimit]", "  ".repeat(level as usize), entries)
+          })
+        },
+        (None, _) => node.render_entry(out, level, &label, options, now),
+        // The files were laid out with their directory.
+        (Some(_), NodeType::File()) if depth > min => Ok(()),
+        (Some(_), NodeType::Directory()) if options.max_depth == Some(depth) => node.render_entry(out, level, &label, options, now),
+        (Some(width), _) => {
+          node.render_entry(out, level, &label, options, now).and_then(|_| {
+            let mut files: Vec<String> = Vec::new();
+            for child in node.children.iter().chain(mounted.into_iter().flat_map(|root| &root.children)) {
+              match (&child.node_type, options.full_path) {
+                (NodeType::File(), false) => files.push(options.names.apply(&child.name)),
+                (NodeType::File(), true) => files.push(join(&label, &options.names.apply(&child.name))),
+                (NodeType::Directory(), _) => {},
+              }
+            }
+            let indent = "  ".repeat((level + 1) as usize);
+            write_columns(out, &files, &indent, width)
+          })
+        },
+      };
+      result.is_ok()
+    });
+    result
+  }
+
+  /// Gets the path of every file of the tree, including mounted trees,
+  /// under the name of the root like the full paths, as `find -type f`
+  /// lists them.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::with_root(".", None);
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/main.rs").unwrap());
+  /// assert_eq!(tree.leaf_paths(), ["./src/main.rs"]);
+  /// ```
+  pub fn leaf_paths(&self) -> Vec<String> {
+    let root = self.root.as_ref().map_or("", |root| root.name.as_str());
+    self.leaves().map(|(path, _)| match path.is_empty() {
+      true => root.to_string(),
+      false => join(root, &path),
+    }).collect()
+  }
+
+  /// Writes the paths of `Tree::leaf_paths` one per line, or ending with a
+  /// NUL byte with `options.print0`.
+  ///
+  /// # Output
+  ///
+  /// ```text
+  /// ./README.md
+  /// ./src/main.rs
+  /// ```
+  pub fn write_leaves<W: Write>(&self, out: &mut W, options: &RenderOptions) -> io::Result<()> {
+    write_paths(out, self.leaf_paths().iter().map(|path| options.names.apply(path)), options.print0)
+  }
+}
+
+impl Tree {
+  /// Gets the device and inode numbers of the files met more than once in
+  /// the tree, including mounted trees, which are hard links to each
+  /// other.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::path::Path;
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut meta = NodeMeta::default();
+  /// meta.inode = Some((1, 42));
+  /// meta.links = Some(2);
+  ///
+  /// let mut source = MockSource::new();
+  /// source.add("a.bin", NodeType::File(), meta.clone());
+  /// source.add("copy/a.bin", NodeType::File(), meta);
+  /// source.add_file("b.bin", 10);
+  /// let tree = scan(&source, Path::new("")).unwrap();
+  ///
+  /// assert_eq!(tree.shared_inodes().into_iter().collect::<Vec<_>>(), [(1, 42)]);
+  /// ```
+  pub fn shared_inodes(&self) -> HashSet<(u64, u64)> {
+    let mut seen = HashSet::new();
+    let mut shared = HashSet::new();
+    self.walk(|_, node, _| {
+      if let (NodeType::File(), Some(inode)) = (&node.node_type, node.meta.inode) {
+        if node.meta.links.unwrap_or(1) > 1 && !seen.insert(inode) {
+          shared.insert(inode);
+        }
+      }
+      true
+    });
+    shared
+  }
+}
+
+/// Joins the full path of a directory and the name of one of its entries.
+fn join(parent: &str, name: &str) -> String {
+  format!("{}/{}", parent.trim_end_matches('/'), name)
+}
+
+/// Writes `paths` one per line, or each followed by a NUL byte with
+/// `print0`.
+pub fn write_paths<W: Write, I: IntoIterator<Item = String>>(out: &mut W, paths: I, print0: bool) -> io::Result<()> {
+  let end = if print0 { '\0' } else { '\n' };
+  for path in paths {
+    write!(out, "{}{}", path, end)?;
+  }
+  Ok(())
+}
+
+/// Writes `names` in as many columns as fit in `width`, filled column by
+/// column like `ls -C`, every line starting with `indent`.
+///
+/// # Output
+///
+/// ```text
+///     build.rs   lib.rs   mod.rs
+///     config.rs  main.rs  util.rs
+/// ```
+fn write_columns<W: Write>(out: &mut W, names: &[String], indent: &str, width: usize) -> io::Result<()> {
+  const GAP: usize = 2;
+  if names.is_empty() {
+    return Ok(());
+  }
+
+  let lengths: Vec<usize> = names.iter().map(|name| name.chars().count()).collect();
+  let available = width.saturating_sub(indent.len());
+  // The most columns whose widths fit, a single column if none does.
+  let (rows, widths) = (1..=names.len()).rev()
+    .map(|columns| {
+      let rows = names.len().div_ceil(columns);
+      let widths: Vec<usize> = lengths.chunks(rows).map(|column| column.iter().copied().max().unwrap_or(0)).collect();
+      (rows, widths)
+    })
+    .find(|(_, widths)| widths.len() == 1 || widths.iter().sum::<usize>() + GAP * (widths.len() - 1) <= available)
+    .unwrap_or_default();
+
+  for row in 0..rows {
+    let mut line = indent.to_string();
+    for (column, column_width) in widths.iter().enumerate() {
+      let i = column * rows + row;
+      if i >= names.len() {
+        break;
+      }
+      if column > 0 {
+        line.push_str(&" ".repeat(GAP));
+      }
+      line.push_str(&names[i]);
+      if column + 1 < widths.len() && i + rows < names.len() {
+        line.push_str(&" ".repeat(column_width - lengths[i]));
+      }
+    }
+    writeln!(out, "{}", line)?;
+  }
+  Ok(())
+}
@@ -0,0 +1,184 @@
+//! Creating the directories and the files of a tree on disk, the inverse
+//! of a scan, to start projects from templates written as path lists or
+//! JSON trees, directly or with a shell script run on another machine.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::node::*;
+
+/// Represents what `Tree::scaffold` did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScaffoldReport {
+  /// The number of directories created.
+  pub directories: u64,
+  /// The number of empty files created.
+  pub files: u64,
+  /// The number of entries which were already there, left as they are.
+  pub existing: u64,
+}
+
+/// Checks that `name` names an entry of its directory, so a tree cannot
+/// create anything outside of the directory it is scaffolded in.
+fn check_name(name: &str) -> io::Result<()> {
+  let invalid = matches!(name, "" | "." | "..") || name.contains(['/', '\\', '\0']);
+  match invalid {
+    true => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}': invalid name", name))),
+    false => Ok(()),
+  }
+}
+
+/// Quotes `text` for a POSIX shell, between single quotes, the single
+/// quotes of the text being written `'\''`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::scaffold::shell_quote;
+///
+/// assert_eq!(shell_quote("src/main.rs"), "'src/main.rs'");
+/// assert_eq!(shell_quote("it's $HOME"), "'it'\\''s $HOME'");
+/// ```
+pub fn shell_quote(text: &str) -> String {
+  format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+impl Tree {
+  /// Creates the directories and the files of the tree, including mounted
+  /// trees, in `dir`, which stands for the root and is created if needed.
+  /// The files are created empty, and the entries already there are left
+  /// as they are.
+  ///
+  /// # Returns
+  ///
+  /// What was created, or the first error met, like a name holding a slash
+  /// or a file in the way of a directory.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["src/", "src/main.rs", "README.md"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  ///
+  /// let dir = std::env::temp_dir().join(format!("rust-tree-scaffold-{}", std::process::id()));
+  /// let report = tree.scaffold(&dir).unwrap();
+  /// assert!(dir.join("src/main.rs").is_file());
+  /// assert_eq!((report.directories, report.files), (2, 2));
+  /// assert_eq!(tree.scaffold(&dir).unwrap().existing, 4);
+  /// std::fs::remove_dir_all(&dir).unwrap();
+  /// ```
+  pub fn scaffold(&self, dir: &Path) -> io::Result<ScaffoldReport> {
+    let mut report = ScaffoldReport::default();
+    // The paths of the directories leading to the current node.
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut result = Ok(());
+    self.walk(|_, node, offset| {
+      let depth = (node.depth + offset) as usize;
+      let path = match depth {
+        0 => dir.to_path_buf(),
+        depth => {
+          if let Err(e) = check_name(&node.name) {
+            result = Err(e);
+            return false;
+          }
+          paths[depth - 1].join(&node.name)
+        },
+      };
+      let created = match node.node_type {
+        NodeType::Directory() if path.is_dir() => Ok(false),
+        NodeType::Directory() => fs::create_dir_all(&path).map(|_| true),
+        NodeType::File() => match OpenOptions::new().write(true).create_new(true).open(&path) {
+          Ok(_) => Ok(true),
+          Err(e) if e.kind() == io::ErrorKind::AlreadyExists && path.is_file() => Ok(false),
+          Err(e) => Err(e),
+        },
+      };
+      match (created, &node.node_type) {
+        (Ok(true), NodeType::Directory()) => report.directories += 1,
+        (Ok(true), NodeType::File()) => report.files += 1,
+        (Ok(false), _) => report.existing += 1,
+        (Err(e), _) => {
+          result = Err(io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+          return false;
+        },
+      }
+      if node.node_type == NodeType::Directory() {
+        paths.truncate(depth);
+        paths.push(path);
+      }
+      true
+    });
+    result.map(|_| report)
+  }
+
+  /// Writes a POSIX shell script creating the directories and the files of
+  /// the tree, including mounted trees, with `mkdir -p` and `touch`, in the
+  /// directory given as its argument or in the current one, like
+  /// `Tree::scaffold` on another machine. Every path is quoted, see
+  /// `shell_quote`.
+  ///
+  /// # Returns
+  ///
+  /// The error writing to `out`, or a name which cannot be created like
+  /// with `Tree::scaffold`.
+  ///
+  /// # Output
+  ///
+  /// ```text
+  /// #!/bin/sh
+  /// # Created by rust-tree, run as: sh script.sh [DIR]
+  /// set -e
+  /// mkdir -p -- "${1:-.}"
+  /// cd -- "${1:-.}"
+  /// mkdir -p -- 'src'
+  /// touch -- 'src/main.rs'
+  /// ```
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["src/", "src/main.rs", "it's.txt"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  ///
+  /// let mut out = Vec::new();
+  /// tree.write_script(&mut out).unwrap();
+  /// let script = String::from_utf8(out).unwrap();
+  /// assert!(script.contains("\nmkdir -p -- 'src'\ntouch -- 'src/main.rs'\n"));
+  /// assert!(script.ends_with("touch -- 'it'\\''s.txt'\n"));
+  /// ```
+  pub fn write_script<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    writeln!(out, "#!/bin/sh")?;
+    writeln!(out, "# Created by rust-tree, run as: sh script.sh [DIR]")?;
+    writeln!(out, "set -e")?;
+    writeln!(out, "mkdir -p -- \"${{1:-.}}\"")?;
+    writeln!(out, "cd -- \"${{1:-.}}\"")?;
+    // The names of the directories leading to the current node.
+    let mut names: Vec<&str> = Vec::new();
+    let mut result = Ok(());
+    self.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      if depth == 0 {
+        return true;
+      }
+      names.truncate(depth as usize - 1);
+      names.push(&node.name);
+      let command = match node.node_type {
+        NodeType::Directory() => "mkdir -p",
+        NodeType::File() => "touch",
+      };
+      result = check_name(&node.name)
+        .and_then(|_| writeln!(out, "{} -- {}", command, shell_quote(&names.join("/"))));
+      result.is_ok()
+    });
+    result
+  }
+}
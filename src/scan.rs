@@ -0,0 +1,490 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::filter::Filter;
+use crate::ignore::{IgnoreRules, IGNORE_FILE};
+use crate::log::{debug, warning};
+use crate::node::*;
+
+/// Represents an entry listed by a `Source`.
+#[derive(Clone, Debug)]
+pub struct Entry {
+  /// The name of the entry.
+  pub name: String,
+  /// Whether the entry is a file or a directory.
+  pub node_type: NodeType,
+  /// The metadata of the entry.
+  pub meta: NodeMeta,
+}
+
+/// A backend the scanner reads directories from.
+pub trait Source {
+  /// Lists the entries of the directory at `path`.
+  fn read_dir(&self, path: &Path) -> io::Result<Vec<Entry>>;
+
+  /// Reads the metadata of the entry at `path`, like the scan root.
+  ///
+  /// Sources that cannot do it report no metadata.
+  fn metadata(&self, _path: &Path) -> io::Result<NodeMeta> {
+    Ok(NodeMeta::default())
+  }
+
+  /// Reads the file at `path`, like the `.treeignore` of the scan root.
+  ///
+  /// Sources that cannot read files report every file as missing.
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: cannot read files", path.display())))
+  }
+}
+
+/// Reads directories from the real filesystem.
+#[derive(Clone, Debug, Default)]
+pub struct FsSource;
+
+impl FsSource {
+  #[cfg(unix)]
+  fn meta(metadata: &fs::Metadata) -> NodeMeta {
+    use std::os::unix::fs::MetadataExt;
+
+    NodeMeta {
+      size: Some(metadata.len()),
+      compressed_size: None,
+      modified: metadata.modified().ok(),
+      inode: Some((metadata.dev(), metadata.ino())),
+      links: Some(metadata.nlink()),
+      mode: Some(metadata.mode()),
+      owner: Some((metadata.uid(), metadata.gid())),
+      checksum: None,
+      link: None,
+      error: None,
+      xattrs: Vec::new(),
+      attrs: BTreeMap::new(),
+    }
+  }
+
+  #[cfg(not(unix))]
+  fn meta(metadata: &fs::Metadata) -> NodeMeta {
+    NodeMeta {
+      size: Some(metadata.len()),
+      modified: metadata.modified().ok(),
+      ..NodeMeta::default()
+    }
+  }
+}
+
+impl Source for FsSource {
+  fn read_dir(&self, path: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path)? {
+      let entry = entry?;
+      let name = entry.file_name().to_string_lossy().into_owned();
+      let metadata = match entry.metadata() {
+        Ok(metadata) => metadata,
+        Err(e) => {
+          let meta = NodeMeta { error: Some(e.to_string()), ..NodeMeta::default() };
+          entries.push(Entry { name, node_type: NodeType::File(), meta });
+          continue;
+        },
+      };
+      let node_type = if metadata.is_dir() {
+        NodeType::Directory()
+      } else {
+        NodeType::File()
+      };
+      let mut meta = FsSource::meta(&metadata);
+      // Links are not followed, their targets are only checked.
+      if metadata.file_type().is_symlink() {
+        meta.link = Some(Link {
+          target: fs::read_link(entry.path())?.to_string_lossy().into_owned(),
+          broken: fs::metadata(entry.path()).is_err(),
+        });
+      }
+
+      entries.push(Entry { name, node_type, meta });
+    }
+    Ok(entries)
+  }
+
+  fn metadata(&self, path: &Path) -> io::Result<NodeMeta> {
+    Ok(FsSource::meta(&fs::metadata(path)?))
+  }
+
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    fs::read_to_string(path)
+  }
+}
+
+/// An in-memory directory description with configurable errors and
+/// latency, so error paths can be exercised without touching the disk.
+///
+/// Paths are relative to the root of the mock, which is the empty path.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use std::path::Path;
+/// use rust_tree::prelude::*;
+///
+/// let mut source = MockSource::new();
+/// source.add_dir("src");
+/// source.add_file("src/main.rs", 120);
+/// source.add_dir("secret");
+/// source.fail("secret", ErrorKind::PermissionDenied);
+///
+/// let tree = scan(&source, Path::new("")).unwrap();
+/// assert!(tree.find_by_path("secret").unwrap().meta.error.is_some());
+/// assert_eq!(tree.errors(), [("secret".to_string(), "secret: injected error".to_string())]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockSource {
+  entries: BTreeMap<PathBuf, Entry>,
+  contents: HashMap<PathBuf, String>,
+  errors: HashMap<PathBuf, io::ErrorKind>,
+  latency: Option<Duration>,
+}
+
+impl MockSource {
+  pub fn new() -> MockSource {
+    MockSource::default()
+  }
+
+  /// Adds a directory, creating its missing parents.
+  pub fn add_dir(&mut self, path: &str) {
+    self.add(path, NodeType::Directory(), NodeMeta::default());
+  }
+
+  /// Adds a file of `size` bytes, creating its missing parents.
+  pub fn add_file(&mut self, path: &str, size: u64) {
+    self.add(path, NodeType::File(), NodeMeta {
+      size: Some(size),
+      ..NodeMeta::default()
+    });
+  }
+
+  /// Adds a file holding `contents`, creating its missing parents.
+  pub fn add_text(&mut self, path: &str, contents: &str) {
+    self.add_file(path, contents.len() as u64);
+    self.contents.insert(PathBuf::from(path.trim_matches('/')), contents.to_string());
+  }
+
+  /// Adds an entry with the given metadata, creating its missing parents.
+  pub fn add(&mut self, path: &str, node_type: NodeType, meta: NodeMeta) {
+    let path = PathBuf::from(path.trim_matches('/'));
+    if let Some(parent) = path.parent() {
+      if !parent.as_os_str().is_empty() && !self.entries.contains_key(parent) {
+        self.add_dir(&parent.to_string_lossy());
+      }
+    }
+
+    let name = path.file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    self.entries.insert(path, Entry { name, node_type, meta });
+  }
+
+  /// Makes reading the directory at `path` fail with `kind`.
+  pub fn fail(&mut self, path: &str, kind: io::ErrorKind) {
+    self.errors.insert(PathBuf::from(path.trim_matches('/')), kind);
+  }
+
+  /// Makes every read sleep for `latency` before answering.
+  pub fn set_latency(&mut self, latency: Duration) {
+    self.latency = Some(latency);
+  }
+}
+
+impl Source for MockSource {
+  fn read_dir(&self, path: &Path) -> io::Result<Vec<Entry>> {
+    if let Some(latency) = self.latency {
+      thread::sleep(latency);
+    }
+
+    if let Some(kind) = self.errors.get(path) {
+      return Err(io::Error::new(*kind, format!("{}: injected error", path.display())));
+    }
+
+    let is_dir = path.as_os_str().is_empty() || matches!(
+      self.entries.get(path),
+      Some(Entry { node_type: NodeType::Directory(), .. })
+    );
+    if !is_dir {
+      return Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: no such directory", path.display())));
+    }
+
+    Ok(self.entries.iter()
+      .filter(|(entry_path, _)| entry_path.parent() == Some(path))
+      .map(|(_, entry)| entry.clone())
+      .collect())
+  }
+
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    match self.contents.get(path) {
+      Some(contents) => Ok(contents.clone()),
+      None => Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: no such file", path.display()))),
+    }
+  }
+}
+
+/// Builds a tree from the directory at `root` of `source`.
+///
+/// Entries are inserted in name order. The directories below the root which
+/// cannot be read are kept with their error, see `Tree::errors`, the other
+/// errors abort the scan.
+/// Entries excluded by the `.treeignore` file at `root`, if any, are left
+/// out, see the `ignore` module.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use rust_tree::prelude::*;
+///
+/// let tree = scan(&FsSource, Path::new("src")).unwrap();
+/// assert!(tree.find_by_name("main.rs").is_some());
+/// ```
+pub fn scan<S: Source>(source: &S, root: &Path) -> io::Result<Tree> {
+  scan_only(source, root, &[])
+}
+
+/// Builds a tree from the subpaths `only` of the directory at `root` of
+/// `source`, or from the whole directory if `only` is empty.
+///
+/// Only the directories leading to the subpaths are read on the way, the
+/// tree holds the subpaths and their ancestors.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use rust_tree::prelude::*;
+///
+/// let mut source = MockSource::new();
+/// source.add_file("src/main.rs", 120);
+/// source.add_file("docs/index.md", 80);
+/// source.add_file("target/debug/app", 4096);
+///
+/// let tree = scan_only(&source, Path::new(""), &[PathBuf::from("src")]).unwrap();
+/// assert!(tree.find_by_path("src/main.rs").is_some());
+/// assert!(tree.find_by_path("target").is_none());
+/// ```
+pub fn scan_only<S: Source>(source: &S, root: &Path, only: &[PathBuf]) -> io::Result<Tree> {
+  let mut tree = Tree::new();
+  let options = ScanOptions { only: only.to_vec(), ..ScanOptions::default() };
+  scan_into(source, root, &options, &mut tree, |_| {})?;
+  Ok(tree)
+}
+
+/// Represents the settings of a scan.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use std::path::Path;
+/// use rust_tree::prelude::*;
+///
+/// let mut source = MockSource::new();
+/// source.add_file("src/main.rs", 120);
+/// source.add_file("docs/index.md", 80);
+/// source.add_file("target/debug/app", 4096);
+/// source.fail("target", ErrorKind::PermissionDenied);
+///
+/// let mut options = ScanOptions::default();
+/// options.filter.exclude("target/");
+/// options.filter.include("*.rs");
+/// let mut tree = Tree::new();
+/// scan_into(&source, Path::new(""), &options, &mut tree, |_| {}).unwrap();
+/// assert!(tree.find_by_path("src/main.rs").is_some());
+/// assert!(tree.find_by_path("docs").is_none());
+/// // The excluded directory is not read.
+/// assert!(tree.errors().is_empty());
+/// assert_eq!(tree.stats().files, 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+  /// The subpaths to restrict the scan to, the whole directory if empty.
+  pub only: Vec<PathBuf>,
+  /// Whether the directories on another device than the root are listed
+  /// without being read, like mount points with `find -xdev`.
+  pub one_file_system: bool,
+  /// The entries to keep, the others being left out of the tree and the
+  /// excluded directories not read. The directories dropped once read, with
+  /// nothing kept below them, have already been reported by `scan_into`.
+  pub filter: Filter,
+}
+
+/// Scans the directory at `root` of `source` into the root of `tree` with
+/// `options`, calling `on_entry` on every scanned node as soon as its own
+/// children are listed.
+///
+/// The nodes are reported in the order of `Tree::display`, so the tree
+/// can be rendered while it is being scanned.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use rust_tree::prelude::*;
+///
+/// let mut source = MockSource::new();
+/// source.add_file("src/main.rs", 120);
+///
+/// let mut paths = Vec::new();
+/// let mut tree = Tree::new();
+/// scan_into(&source, Path::new(""), &ScanOptions::default(), &mut tree, |node| paths.push(node.path())).unwrap();
+/// assert_eq!(paths, ["/", "src", "src/main.rs"]);
+/// ```
+pub fn scan_into<S: Source, F: FnMut(&Node)>(
+  source: &S,
+  root: &Path,
+  options: &ScanOptions,
+  tree: &mut Tree,
+  mut on_entry: F,
+) -> io::Result<()> {
+  let only: Vec<PathBuf> = options.only.iter()
+    .map(|path| path.components().filter(|c| !matches!(c, Component::CurDir)).collect())
+    .collect();
+
+  let ignore = match source.read_to_string(&root.join(IGNORE_FILE)) {
+    Ok(text) => IgnoreRules::parse(&text),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => IgnoreRules::new(),
+    Err(e) => return Err(e),
+  };
+
+  let meta = source.metadata(root)?;
+  let device = match meta.inode {
+    Some((device, _)) if options.one_file_system => Some(device),
+    _ => None,
+  };
+  let scanner = Scanner { source, root, only: &only, ignore: &ignore, filter: &options.filter, device };
+  let root_node = tree.root_or_insert();
+  root_node.meta = meta;
+  scanner.scan_recursive(Path::new(""), root_node, false, &mut on_entry)?;
+
+  for path in &only {
+    if tree.find_by_path(&path.to_string_lossy()).is_none() {
+      warning!("{} is not in {}", path.display(), root.display());
+    }
+  }
+  Ok(())
+}
+
+/// The settings of a scan.
+struct Scanner<'a, S: Source> {
+  source: &'a S,
+  root: &'a Path,
+  only: &'a [PathBuf],
+  ignore: &'a IgnoreRules,
+  filter: &'a Filter,
+  /// The device the scan stays on, `None` to cross devices.
+  device: Option<u64>,
+}
+
+impl<S: Source> Scanner<'_, S> {
+  /// Lists the directory `node` at `relative`, reports it and scans its
+  /// subdirectories, `included` telling whether the filter includes it or
+  /// one of its ancestors.
+  fn scan_recursive<F: FnMut(&Node)>(&self, relative: &Path, node: &mut Node, included: bool, on_entry: &mut F) -> io::Result<()> {
+    let mut entries = match self.source.read_dir(&self.root.join(relative)) {
+      Ok(entries) => entries,
+      Err(e) if !relative.as_os_str().is_empty() => {
+        debug!("Could not read {}: {}", node.path, e);
+        node.meta.error = Some(e.to_string());
+        on_entry(node);
+        return Ok(());
+      },
+      Err(e) => return Err(e),
+    };
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let scanned = node.children.len();
+    // Whether the filter includes each scanned child.
+    let mut includes = Vec::new();
+    for entry in entries {
+      let relative = relative.join(&entry.name);
+      // Keep the entries inside a subpath and the directories leading to one.
+      let wanted = self.only.is_empty()
+        || self.only.iter().any(|path| relative.starts_with(path) || path.starts_with(&relative));
+      if !wanted {
+        continue;
+      }
+      let path = relative.to_string_lossy().replace('\\', "/");
+      let is_dir = entry.node_type == NodeType::Directory();
+      if self.ignore.is_ignored(&path, is_dir) {
+        debug!("Ignoring {}", path);
+        continue;
+      }
+      let included = included || self.filter.includes(&entry.name, &path, is_dir);
+      let selected = || included && self.filter.selects_file(&entry.name, &entry.meta, Some(&self.root.join(&relative)));
+      if self.filter.excludes(&path, is_dir) || (!is_dir && !selected()) {
+        debug!("Filtering out {}", path);
+        continue;
+      }
+
+      let default_path = self.root.join(&relative).to_string_lossy().into_owned();
+      let data = NodeData {
+        length: default_path.len() as u64,
+        default_path,
+      };
+      let depth = relative.components().count() as u64;
+
+      let mut child = match entry.node_type {
+        NodeType::File() => Node::new_file(data, depth, path, entry.name),
+        NodeType::Directory() => Node::new_directory(data, depth, path, entry.name),
+      };
+      child.meta = entry.meta;
+      node.children.push(child);
+      includes.push(included);
+    }
+    node.invalidate_stats();
+    on_entry(node);
+
+    let mut kept = Vec::new();
+    for (child, included) in node.children[scanned..].iter_mut().zip(includes) {
+      match child.node_type {
+        NodeType::File() => on_entry(child),
+        NodeType::Directory() if self.device.is_some_and(|device| child.meta.inode.is_some_and(|(dev, _)| dev != device)) => {
+          debug!("Not reading {}, on another device", child.path);
+          on_entry(child);
+        },
+        NodeType::Directory() => {
+          let relative = relative.join(&child.name);
+          self.scan_recursive(&relative, child, included, on_entry)?;
+        },
+      }
+      kept.push(child.node_type == NodeType::File() || !child.children.is_empty() || self.filter.keeps_empty_directory(included));
+    }
+
+    // The directories left without anything the filter keeps below them.
+    if kept.contains(&false) {
+      let mut kept = (0..scanned).map(|_| true).chain(kept);
+      node.children.retain(|_| kept.next().unwrap_or(true));
+      node.invalidate_stats();
+    }
+    Ok(())
+  }
+}
+
+impl Tree {
+  /// Gets the entries of the tree, including mounted trees, which could not
+  /// be read, in pre-order.
+  ///
+  /// # Returns
+  ///
+  /// The paths of the entries in their tree, with their errors.
+  pub fn errors(&self) -> Vec<(String, String)> {
+    let mut errors = Vec::new();
+    self.walk(|_, node, _| {
+      if let Some(e) = &node.meta.error {
+        errors.push((node.path.clone(), e.clone()));
+      }
+      true
+    });
+    errors
+  }
+}
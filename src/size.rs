@@ -0,0 +1,63 @@
+/// The binary units sizes are parsed and formatted with.
+const UNITS: [char; 6] = ['K', 'M', 'G', 'T', 'P', 'E'];
+
+/// Parses a size in bytes, optionally followed by a binary unit like `K`,
+/// `M` or `G`, with an optional `B` or `iB` suffix.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::parse_size;
+///
+/// assert_eq!(parse_size("512"), Ok(512));
+/// assert_eq!(parse_size("5G"), Ok(5 << 30));
+/// assert_eq!(parse_size("1.5KiB"), Ok(1536));
+/// assert!(parse_size("big").is_err());
+/// ```
+pub fn parse_size(text: &str) -> Result<u64, String> {
+  let trimmed = text.trim();
+  let upper = trimmed.to_ascii_uppercase();
+  let number = upper.trim_end_matches("IB").trim_end_matches('B');
+  let (number, multiplier) = match number.chars().last() {
+    Some(unit) if unit.is_ascii_alphabetic() => {
+      match UNITS.iter().position(|u| *u == unit) {
+        Some(i) => (&number[..number.len() - 1], 1u64 << (10 * (i + 1))),
+        None => return Err(format!("{}: unknown size unit", text)),
+      }
+    },
+    _ => (number, 1),
+  };
+
+  match number.parse::<f64>() {
+    Ok(value) if value >= 0.0 && value.is_finite() => Ok((value * multiplier as f64).round() as u64),
+    _ => Err(format!("{}: invalid size", text)),
+  }
+}
+
+/// Formats a size in bytes with a binary unit, like `du -h`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::format_size;
+///
+/// assert_eq!(format_size(512), "512");
+/// assert_eq!(format_size(1536), "1.5K");
+/// assert_eq!(format_size(5 << 30), "5.0G");
+/// ```
+pub fn format_size(size: u64) -> String {
+  if size < 1024 {
+    return size.to_string();
+  }
+
+  let mut value = size as f64;
+  let mut unit = UNITS[0];
+  for u in UNITS {
+    value /= 1024.0;
+    unit = u;
+    if value < 1024.0 {
+      break;
+    }
+  }
+  format!("{:.1}{}", value, unit)
+}
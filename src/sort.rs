@@ -0,0 +1,201 @@
+//! Ordering the entries of every directory of a tree.
+//!
+//! Scans list entries by byte order of their names and path lists keep the
+//! order of their lines, `Tree::sort_by` reorders them afterwards.
+
+use std::cmp::Ordering;
+
+use crate::node::*;
+
+/// Represents an order of the entries of a directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+  /// By byte order of the names.
+  Name,
+  /// By names with their numbers compared by value, like `ls -v`, see
+  /// `natural_cmp`.
+  Version,
+  /// By names ignoring case and accents first, see `collate_cmp`.
+  Collate,
+}
+
+impl SortOrder {
+  /// Parses the name of an order: `name`, `version` or `collate`.
+  pub fn parse(name: &str) -> Result<SortOrder, String> {
+    match name {
+      "name" => Ok(SortOrder::Name),
+      "version" => Ok(SortOrder::Version),
+      "collate" => Ok(SortOrder::Collate),
+      _ => Err(format!("unknown sort order '{}', expected name, version or collate", name)),
+    }
+  }
+
+  /// Compares two entries in this order.
+  pub fn compare(&self, a: &Node, b: &Node) -> Ordering {
+    match self {
+      SortOrder::Name => a.name.cmp(&b.name),
+      SortOrder::Version => natural_cmp(&a.name, &b.name),
+      SortOrder::Collate => collate_cmp(&a.name, &b.name),
+    }
+  }
+}
+
+/// Compares two names with their runs of digits compared by value, so that
+/// `file2.txt` comes before `file10.txt` and `v1.9` before `v1.10`.
+///
+/// Numbers with the same value are ordered by their leading zeros, so only
+/// equal names are equal.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use rust_tree::sort::natural_cmp;
+///
+/// assert_eq!(natural_cmp("file2.txt", "file10.txt"), Ordering::Less);
+/// assert_eq!(natural_cmp("v1.10", "v1.9"), Ordering::Greater);
+/// assert_eq!(natural_cmp("a01", "a1"), Ordering::Less);
+/// assert_eq!(natural_cmp("b", "a10"), Ordering::Greater);
+/// ```
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+  let (mut a, mut b) = (a, b);
+  // The order of the first numbers with the same value but not the same
+  // digits, used if nothing else differs.
+  let mut zeros = Ordering::Equal;
+  loop {
+    let (x, y) = match (a.chars().next(), b.chars().next()) {
+      (None, None) => return zeros,
+      (None, Some(_)) => return Ordering::Less,
+      (Some(_), None) => return Ordering::Greater,
+      (Some(x), Some(y)) => (x, y),
+    };
+
+    if x.is_ascii_digit() && y.is_ascii_digit() {
+      let (x_digits, x_rest) = a.split_at(a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len()));
+      let (y_digits, y_rest) = b.split_at(b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len()));
+      let (x_value, y_value) = (x_digits.trim_start_matches('0'), y_digits.trim_start_matches('0'));
+      let order = x_value.len().cmp(&y_value.len()).then_with(|| x_value.cmp(y_value));
+      if order != Ordering::Equal {
+        return order;
+      }
+      if zeros == Ordering::Equal {
+        zeros = y_digits.len().cmp(&x_digits.len());
+      }
+      (a, b) = (x_rest, y_rest);
+    } else {
+      if x != y {
+        return x.cmp(&y);
+      }
+      (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+    }
+  }
+}
+
+/// The base letters of the accented Latin letters, which sort with them.
+const ACCENTED: [(&str, &str); 14] = [
+  ("àáâãäåāăą", "a"),
+  ("çćĉċč", "c"),
+  ("ďđ", "d"),
+  ("èéêëēĕėęě", "e"),
+  ("ĝğġģ", "g"),
+  ("ĥħ", "h"),
+  ("ìíîïĩīĭįı", "i"),
+  ("ĵ", "j"),
+  ("ķĺļľŀł", "klllll"),
+  ("ñńņňŉ", "n"),
+  ("òóôõöøōŏő", "o"),
+  ("ŕŗřśŝşšţťŧ", "rrrssssttt"),
+  ("ùúûüũūŭůűų", "u"),
+  ("ýÿŷźżž", "yyyzzz"),
+];
+
+/// Appends the letters `c` sorts as to `key`: its lowercase form without
+/// accents, ligatures being spelled out.
+fn push_base(key: &mut String, c: char) {
+  for lower in c.to_lowercase() {
+    match lower {
+      'ß' => key.push_str("ss"),
+      'æ' => key.push_str("ae"),
+      'œ' => key.push_str("oe"),
+      'þ' => key.push_str("th"),
+      lower => {
+        let base = ACCENTED.iter().find_map(|(accented, bases)| {
+          let i = accented.chars().position(|accented| accented == lower)?;
+          // A single base letter stands for every accented one.
+          bases.chars().nth(i).or_else(|| bases.chars().next())
+        });
+        key.push(base.unwrap_or(lower));
+      },
+    }
+  }
+}
+
+/// Compares two names like a dictionary: ignoring case and accents first,
+/// then with accents, then lowercase before uppercase, so that `Eagle`,
+/// `éclair` and `zebra` come in this order.
+///
+/// The accents and ligatures of Latin scripts are known, other scripts
+/// are only compared without case.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use rust_tree::sort::collate_cmp;
+///
+/// assert_eq!(collate_cmp("Zebra", "apple"), Ordering::Greater);
+/// assert_eq!(collate_cmp("élan", "ema"), Ordering::Less);
+/// assert_eq!(collate_cmp("resume", "résumé"), Ordering::Less);
+/// assert_eq!(collate_cmp("straße", "strasse"), Ordering::Greater);
+/// assert_eq!(collate_cmp("a", "A"), Ordering::Less);
+/// ```
+pub fn collate_cmp(a: &str, b: &str) -> Ordering {
+  let key = |name: &str| {
+    let mut key = String::with_capacity(name.len());
+    name.chars().for_each(|c| push_base(&mut key, c));
+    key
+  };
+  key(a).cmp(&key(b))
+    .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+    // Names differing only by case, lowercase letters having higher codes.
+    .then_with(|| b.cmp(a))
+}
+
+impl Tree {
+  /// Sorts the entries of every directory of the tree, including mounted
+  /// trees, with `compare`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  /// use rust_tree::sort::SortOrder;
+  ///
+  /// let mut tree = Tree::new();
+  /// for path in ["file10.txt", "file2.txt", "file1.txt"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  ///
+  /// tree.sort_by(|a, b| SortOrder::Version.compare(a, b));
+  /// let names: Vec<String> = tree.root.as_ref().unwrap().children.iter().map(Node::name).collect();
+  /// assert_eq!(names, ["file1.txt", "file2.txt", "file10.txt"]);
+  /// ```
+  pub fn sort_by<F: FnMut(&Node, &Node) -> Ordering>(&mut self, mut compare: F) {
+    self.sort_nodes(&mut compare);
+  }
+
+  fn sort_nodes<F: FnMut(&Node, &Node) -> Ordering>(&mut self, compare: &mut F) {
+    // The routes of the index go through the positions of the children.
+    self.invalidate_index();
+    if let Some(root) = &mut self.root {
+      let mut stack = vec![root];
+      while let Some(node) = stack.pop() {
+        node.children.sort_by(|a, b| compare(a, b));
+        stack.extend(node.children.iter_mut());
+      }
+    }
+    for mounted in self.mounts.values_mut() {
+      mounted.sort_nodes(compare);
+    }
+  }
+}
@@ -0,0 +1,166 @@
+//! Reading trees printed by `tree`, with its glyphs or with its ASCII ones,
+//! so the trees pasted in documentation or issues can be loaded again.
+
+use crate::node::*;
+
+/// Represents an entry line of a printed tree.
+struct TextEntry<'a> {
+  depth: usize,
+  name: &'a str,
+  is_dir: bool,
+  target: Option<&'a str>,
+}
+
+/// Parses a line drawn below the root, like `│   ├── main.rs`.
+///
+/// # Returns
+///
+/// The entry, or `None` if the line is not drawn like one.
+fn parse_entry(line: &str) -> Option<TextEntry<'_>> {
+  let mut indent = 0usize;
+  let mut rest = line;
+  // The columns of the ancestors, four characters each as drawn by tree,
+  // which writes non-breaking spaces after the bars with UTF-8.
+  loop {
+    match rest.chars().next() {
+      Some('│' | '|' | ' ' | '\u{a0}') => {
+        if let Some(entry) = rest.strip_prefix("|-- ") {
+          rest = entry;
+          break;
+        }
+        indent += 1;
+        rest = &rest[rest.chars().next()?.len_utf8()..];
+      },
+      Some('├' | '└' | '`') => {
+        rest = ["├── ", "└── ", "`-- "].iter().find_map(|connector| rest.strip_prefix(connector))?;
+        break;
+      },
+      _ => return None,
+    }
+  }
+  if !indent.is_multiple_of(4) {
+    return None;
+  }
+
+  // The bracketed fields of options like -s or -p.
+  if let Some((_, name)) = rest.strip_prefix('[').and_then(|fields| fields.split_once("]  ")) {
+    rest = name;
+  }
+  let (name, target) = match rest.split_once(" -> ") {
+    Some((name, target)) => (name, Some(target)),
+    None => (rest, None),
+  };
+  let is_dir = name.ends_with('/');
+  let name = name.trim_end_matches('/');
+  // The full paths of -f.
+  let name = name.rsplit('/').next().unwrap_or(name);
+  match name.is_empty() {
+    true => None,
+    false => Some(TextEntry { depth: indent / 4 + 1, name, is_dir, target }),
+  }
+}
+
+/// Checks whether `line` is the report ending the output of tree, like
+/// `3 directories, 5 files`.
+fn is_report(line: &str) -> bool {
+  line.split(", ").all(|count| match count.split_once(' ') {
+    Some((number, what)) => {
+      number.parse::<u64>().is_ok() && matches!(what, "directory" | "directories" | "file" | "files")
+    },
+    None => false,
+  })
+}
+
+/// Checks whether `text` looks like the output of `tree`, with at least
+/// one line drawn below the root, rather than like a path list.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::is_tree_text;
+///
+/// assert!(is_tree_text(".\n└── main.rs\n"));
+/// assert!(is_tree_text(".\n`-- main.rs\n"));
+/// assert!(!is_tree_text("src/\nsrc/main.rs\n"));
+/// ```
+pub fn is_tree_text(text: &str) -> bool {
+  text.lines().skip(1).any(|line| parse_entry(line).is_some())
+}
+
+impl Tree {
+  /// Builds a tree from the output of `tree`, drawn with its glyphs like
+  /// `├──` and `│` or with its ASCII ones like `|--` and `` `-- ``. The
+  /// first line names the root, the entries with entries below them or
+  /// ending with a slash are directories and the others are files, with
+  /// the targets of symbolic links after `->`. The report ending the
+  /// output is skipped, and so are the fields of options like `-s`.
+  ///
+  /// # Returns
+  ///
+  /// The tree, or a description of the first line which cannot be read,
+  /// like a line indented deeper than the line before it.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let text = "\
+  /// project
+  /// ├── src
+  /// │   ├── lib.rs
+  /// │   └── main.rs
+  /// ├── docs/
+  /// ├── latest -> src
+  /// └── README.md
+  ///
+  /// 2 directories, 4 files
+  /// ";
+  /// let tree = Tree::from_text(text).unwrap();
+  /// assert_eq!(tree.root.as_ref().unwrap().name(), "project");
+  /// assert_eq!(tree.find_by_path("src/main.rs").unwrap().node_type, NodeType::File());
+  /// assert_eq!(tree.find_by_path("docs").unwrap().node_type, NodeType::Directory());
+  /// assert_eq!(tree.find_by_path("latest").unwrap().meta.link.as_ref().unwrap().target, "src");
+  /// assert_eq!(tree.stats().files, 4);
+  ///
+  /// assert!(Tree::from_text("project\n│       └── main.rs\n").is_err());
+  /// ```
+  pub fn from_text(text: &str) -> Result<Tree, String> {
+    let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let mut tree = match lines.next() {
+      Some((_, root)) => Tree::with_root(root.trim_end().trim_end_matches('/'), None),
+      None => return Ok(Tree::new()),
+    };
+
+    let mut entries = Vec::new();
+    for (number, line) in lines {
+      let line = line.trim_end();
+      match parse_entry(line) {
+        Some(entry) if entry.depth > entries.last().map_or(0, |last: &TextEntry| last.depth) + 1 => {
+          return Err(format!("line {}: '{}' is deeper than the line before it", number + 1, line));
+        },
+        Some(entry) => entries.push(entry),
+        None if is_report(line) => {},
+        None => return Err(format!("line {}: '{}' is not an entry of the tree", number + 1, line)),
+      }
+    }
+
+    // The names of the directories leading to the current entry.
+    let mut names: Vec<&str> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+      names.truncate(entry.depth - 1);
+      let is_dir = entry.is_dir || entries.get(i + 1).is_some_and(|next| next.depth > entry.depth);
+      let path = match names.is_empty() {
+        true => entry.name.to_string(),
+        false => format!("{}/{}", names.join("/"), entry.name),
+      };
+      let line = if is_dir { format!("{}/", path) } else { path };
+      if let Some(mut node) = Node::from_path(&line) {
+        node.meta.link = entry.target.map(|target| Link { target: target.to_string(), broken: false });
+        tree.insert(node);
+      }
+      names.push(entry.name);
+    }
+    Ok(tree)
+  }
+}
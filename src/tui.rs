@@ -0,0 +1,398 @@
+//! An interactive browser of trees in the terminal.
+//!
+//! The state of the browser and its drawing are independent from the
+//! terminal, `browse` runs it on the terminal of the process.
+//!
+//! ```text
+//! > /srv/data/
+//!     src/
+//!       main.rs
+//!     Cargo.toml
+//!
+//! /main  j/k move  l/h expand/collapse  / filter  enter select  q quit
+//! ```
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use crate::node::*;
+
+/// Represents an entry of the browsed tree.
+#[derive(Clone, Debug)]
+struct Entry {
+  /// The path of the entry starting with the name of the root.
+  path: String,
+  name: String,
+  depth: u64,
+  is_dir: bool,
+}
+
+/// Represents a key pressed in the browser.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+  Up,
+  Down,
+  Left,
+  Right,
+  Enter,
+  Escape,
+  Backspace,
+  Char(char),
+}
+
+/// Represents what the browser does after a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+  /// Keep browsing.
+  Continue,
+  /// Stop browsing, with the selected path if any.
+  Quit(Option<String>),
+}
+
+/// Represents the state of an interactive browser: which directories are
+/// expanded, the filter and the selected entry.
+#[derive(Clone, Debug)]
+pub struct Browser {
+  /// The entries of the tree in pre-order, mounted trees included.
+  entries: Vec<Entry>,
+  /// The indices of the expanded directories.
+  expanded: HashSet<usize>,
+  /// The text the paths of the shown entries contain, ignoring case.
+  filter: String,
+  /// Whether the keys are typed into the filter.
+  filtering: bool,
+  /// The indices of the shown entries.
+  rows: Vec<usize>,
+  /// The position of the selected entry in `rows`.
+  cursor: usize,
+  /// The first row shown.
+  scroll: usize,
+}
+
+impl Browser {
+  /// Creates a browser of `tree` with only the root expanded.
+  pub fn new(tree: &Tree) -> Browser {
+    let mut entries: Vec<Entry> = Vec::new();
+    // The indices of the directories leading to the current node.
+    let mut parents: Vec<usize> = Vec::new();
+    tree.walk(|_, node, offset| {
+      let depth = node.depth + offset;
+      parents.truncate(depth as usize);
+      let path = match parents.last() {
+        Some(&parent) => format!("{}/{}", entries[parent].path.trim_end_matches('/'), node.name),
+        None => node.name.clone(),
+      };
+      let is_dir = node.node_type == NodeType::Directory();
+      if is_dir {
+        parents.push(entries.len());
+      }
+      entries.push(Entry { path, name: node.name.clone(), depth, is_dir });
+      true
+    });
+
+    let mut browser = Browser {
+      entries,
+      expanded: HashSet::from([0]),
+      filter: String::new(),
+      filtering: false,
+      rows: Vec::new(),
+      cursor: 0,
+      scroll: 0,
+    };
+    browser.update_rows();
+    browser
+  }
+
+  /// Gets the path of the selected entry, starting with the name of the
+  /// root.
+  pub fn selected(&self) -> Option<&str> {
+    self.rows.get(self.cursor).map(|&i| self.entries[i].path.as_str())
+  }
+
+  /// Updates the browser after `key`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  /// use rust_tree::tui::{Action, Browser, Key};
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.set_base(None);
+  /// for path in ["src/", "src/main.rs", "Cargo.toml"] {
+  ///   tree.insert(Node::from_path(path).unwrap());
+  /// }
+  ///
+  /// let mut browser = Browser::new(&tree);
+  /// browser.handle(Key::Down);
+  /// browser.handle(Key::Right);
+  /// browser.handle(Key::Down);
+  /// assert_eq!(browser.selected(), Some("root/src/main.rs"));
+  ///
+  /// for key in [Key::Char('/'), Key::Char('c'), Key::Char('a'), Key::Char('r'), Key::Enter] {
+  ///   browser.handle(key);
+  /// }
+  /// assert_eq!(browser.handle(Key::Enter), Action::Quit(Some("root/Cargo.toml".to_string())));
+  /// ```
+  pub fn handle(&mut self, key: Key) -> Action {
+    if self.filtering {
+      match key {
+        Key::Enter => self.filtering = false,
+        Key::Escape => {
+          self.filtering = false;
+          self.filter.clear();
+        },
+        Key::Backspace => {
+          self.filter.pop();
+        },
+        Key::Char(c) => self.filter.push(c),
+        Key::Up | Key::Down | Key::Left | Key::Right => return self.navigate(key),
+      }
+      self.update_rows();
+      // Select the first match rather than its directories.
+      if let Some(cursor) = self.rows.iter().position(|&i| self.matches(i)) {
+        self.cursor = cursor;
+      }
+      return Action::Continue;
+    }
+
+    match key {
+      Key::Enter => Action::Quit(self.selected().map(String::from)),
+      Key::Char('q') | Key::Escape => Action::Quit(None),
+      Key::Char('/') => {
+        self.filtering = true;
+        Action::Continue
+      },
+      Key::Char('k') => self.navigate(Key::Up),
+      Key::Char('j') => self.navigate(Key::Down),
+      Key::Char('h') => self.navigate(Key::Left),
+      Key::Char('l') => self.navigate(Key::Right),
+      Key::Char(' ') => {
+        if let Some(&i) = self.rows.get(self.cursor) {
+          if !self.expanded.remove(&i) {
+            self.expanded.insert(i);
+          }
+          self.update_rows();
+        }
+        Action::Continue
+      },
+      key => self.navigate(key),
+    }
+  }
+
+  /// Moves the cursor, or expands or collapses the selected directory.
+  fn navigate(&mut self, key: Key) -> Action {
+    let Some(&i) = self.rows.get(self.cursor) else {
+      return Action::Continue;
+    };
+    match key {
+      Key::Up => self.cursor = self.cursor.saturating_sub(1),
+      Key::Down => self.cursor = (self.cursor + 1).min(self.rows.len() - 1),
+      Key::Right if self.entries[i].is_dir => {
+        self.expanded.insert(i);
+        self.update_rows();
+      },
+      // A collapsed directory or a file selects its parent.
+      Key::Left if !self.expanded.remove(&i) || !self.entries[i].is_dir => {
+        let depth = self.entries[i].depth;
+        if let Some(parent) = self.rows[..self.cursor].iter().rposition(|&row| self.entries[row].depth < depth) {
+          self.cursor = parent;
+        }
+      },
+      Key::Left => self.update_rows(),
+      _ => {},
+    }
+    Action::Continue
+  }
+
+  fn matches(&self, i: usize) -> bool {
+    !self.filter.is_empty() && self.entries[i].path.to_lowercase().contains(&self.filter.to_lowercase())
+  }
+
+  /// Computes the shown entries, keeping the selected one selected if it is
+  /// still shown.
+  fn update_rows(&mut self) {
+    let selected = self.rows.get(self.cursor).copied();
+    self.rows.clear();
+    if self.filter.is_empty() {
+      // The depth below which the entries are in a collapsed directory.
+      let mut hidden: Option<u64> = None;
+      for (i, entry) in self.entries.iter().enumerate() {
+        if hidden.is_some_and(|depth| entry.depth <= depth) {
+          hidden = None;
+        }
+        if hidden.is_none() {
+          self.rows.push(i);
+          if !self.expanded.contains(&i) {
+            hidden = Some(entry.depth);
+          }
+        }
+      }
+    } else {
+      // The matches are shown with the directories leading to them.
+      let mut kept = vec![false; self.entries.len()];
+      let mut parents: Vec<usize> = Vec::new();
+      for (i, entry) in self.entries.iter().enumerate() {
+        parents.truncate(entry.depth as usize);
+        if self.matches(i) || i == 0 {
+          kept[i] = true;
+          parents.iter().for_each(|&parent| kept[parent] = true);
+        }
+        parents.push(i);
+      }
+      self.rows = (0..self.entries.len()).filter(|&i| kept[i]).collect();
+    }
+    self.cursor = selected
+      .and_then(|selected| self.rows.iter().position(|&i| i == selected))
+      .unwrap_or(self.cursor.min(self.rows.len().saturating_sub(1)));
+  }
+
+  /// Draws the browser to `out` within `height` lines, the last one being
+  /// the status line.
+  pub fn draw<W: Write>(&mut self, out: &mut W, height: usize) -> io::Result<()> {
+    let shown = height.saturating_sub(2).max(1);
+    if self.cursor < self.scroll {
+      self.scroll = self.cursor;
+    } else if self.cursor >= self.scroll + shown {
+      self.scroll = self.cursor + 1 - shown;
+    }
+
+    write!(out, "\x1b[H\x1b[2J")?;
+    for (row, &i) in self.rows.iter().enumerate().skip(self.scroll).take(shown) {
+      let entry = &self.entries[i];
+      let marker = if row == self.cursor { ">" } else { " " };
+      let slash = if entry.is_dir && !entry.name.ends_with('/') { "/" } else { "" };
+      let line = format!("{} {}{}{}", marker, "  ".repeat(entry.depth as usize), entry.name, slash);
+      if row == self.cursor {
+        write!(out, "\x1b[7m{}\x1b[0m\r\n", line)?;
+      } else {
+        write!(out, "{}\r\n", line)?;
+      }
+    }
+
+    write!(out, "\x1b[{};1H", height)?;
+    if self.filtering || !self.filter.is_empty() {
+      write!(out, "/{}  ", self.filter)?;
+    }
+    write!(out, "j/k move  l/h expand/collapse  / filter  enter select  q quit")?;
+    out.flush()
+  }
+}
+
+/// Reads the keys typed on a terminal in raw mode.
+#[cfg(unix)]
+struct Keys<R: io::Read> {
+  input: R,
+}
+
+#[cfg(unix)]
+impl<R: io::Read> Keys<R> {
+  /// Reads a byte, `None` if none comes within the terminal timeout.
+  fn byte(&mut self) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    match self.input.read(&mut byte)? {
+      0 => Ok(None),
+      _ => Ok(Some(byte[0])),
+    }
+  }
+
+  /// Waits for the next key, `None` for the keys the browser ignores.
+  fn next(&mut self) -> io::Result<Option<Key>> {
+    let byte = loop {
+      if let Some(byte) = self.byte()? {
+        break byte;
+      }
+    };
+    let key = match byte {
+      b'\r' | b'\n' => Some(Key::Enter),
+      0x7f | 0x08 => Some(Key::Backspace),
+      // Ctrl-C, as the raw mode does not send signals.
+      0x03 => Some(Key::Char('q')),
+      0x1b => match self.byte()? {
+        None => Some(Key::Escape),
+        Some(b'[') | Some(b'O') => match self.byte()? {
+          Some(b'A') => Some(Key::Up),
+          Some(b'B') => Some(Key::Down),
+          Some(b'C') => Some(Key::Right),
+          Some(b'D') => Some(Key::Left),
+          _ => None,
+        },
+        Some(_) => None,
+      },
+      byte if byte.is_ascii() && !byte.is_ascii_control() => Some(Key::Char(byte as char)),
+      // The first byte of a UTF-8 character.
+      byte => {
+        let len = match byte {
+          0xc0..=0xdf => 2,
+          0xe0..=0xef => 3,
+          0xf0..=0xf7 => 4,
+          _ => return Ok(None),
+        };
+        let mut bytes = vec![byte];
+        for _ in 1..len {
+          bytes.extend(self.byte()?);
+        }
+        String::from_utf8(bytes).ok().and_then(|text| text.chars().next()).map(Key::Char)
+      },
+    };
+    Ok(key)
+  }
+}
+
+/// Runs `stty` with `args` on the terminal `tty`.
+#[cfg(unix)]
+fn stty(tty: &std::fs::File, args: &[&str]) -> io::Result<String> {
+  let output = std::process::Command::new("stty").args(args).stdin(tty.try_clone()?).output()?;
+  if !output.status.success() {
+    return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Browses `tree` on the terminal of the process until an entry is selected
+/// or the browser is quit, the terminal being restored afterwards.
+///
+/// # Returns
+///
+/// The path of the selected entry, starting with the name of the root, or
+/// `None` if the browser was quit.
+#[cfg(unix)]
+pub fn browse(tree: &Tree) -> io::Result<Option<String>> {
+  use std::fs::OpenOptions;
+
+  let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+  let saved = stty(&tty, &["-g"])?;
+  // Reads wait a tenth of a second at most, to tell escape from arrows.
+  stty(&tty, &["raw", "-echo", "min", "0", "time", "1"])?;
+
+  let mut out = tty.try_clone()?;
+  let mut keys = Keys { input: tty.try_clone()? };
+  let height = stty(&tty, &["size"]).ok()
+    .and_then(|size| size.split_whitespace().next()?.parse().ok())
+    .unwrap_or(24);
+
+  let mut browser = Browser::new(tree);
+  let result = (|| {
+    write!(out, "\x1b[?1049h\x1b[?25l")?;
+    loop {
+      browser.draw(&mut out, height)?;
+      if let Some(key) = keys.next()? {
+        if let Action::Quit(selected) = browser.handle(key) {
+          return Ok(selected);
+        }
+      }
+    }
+  })();
+
+  write!(out, "\x1b[?25h\x1b[?1049l")?;
+  out.flush()?;
+  stty(&tty, &[&saved])?;
+  result
+}
+
+/// Browses `tree` on the terminal of the process, which is only supported
+/// on Unix.
+#[cfg(not(unix))]
+pub fn browse(_tree: &Tree) -> io::Result<Option<String>> {
+  Err(io::Error::new(io::ErrorKind::Unsupported, "the interactive mode needs a Unix terminal"))
+}
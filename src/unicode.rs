@@ -0,0 +1,118 @@
+//! Normalizing names to their composed form (NFC), as macOS stores them
+//! decomposed (NFD): `e` followed by a combining acute accent rather than
+//! `é`, so the same name typed on Linux would not match otherwise.
+//!
+//! Only the compositions of the Latin, Greek and Cyrillic letters with
+//! their accents and of the Hangul syllables are known, which are the
+//! decomposed names met in practice, so the tables stay small. The marks are composed in the order they come in.
+
+use std::borrow::Cow;
+
+use crate::node::*;
+
+/// The letters composed with each combining mark, the letters of the
+/// first string becoming those of the second.
+const COMPOSED: [(char, &str, &str); 25] = [
+  ('\u{300}', "AEIOUaeiouÜüNnЕИеиĒēŌōWwÂâĂăÊêÔôƠơƯưYy", "ÀÈÌÒÙàèìòùǛǜǸǹЀЍѐѝḔḕṐṑẀẁẦầẰằỀềỒồỜờỪừỲỳ"),
+  ('\u{301}', "AEIOUYaeiouyCcLlNnRrSsZzÜüGgÅåÆæØø¨ΑΕΗΙΟΥΩϊαεηιϋουωϒГКгкÇçĒēÏïKkMmÕõŌōPpŨũWwÂâĂăÊêÔôƠơƯư", "ÁÉÍÓÚÝáéíóúýĆćĹĺŃńŔŕŚśŹźǗǘǴǵǺǻǼǽǾǿ΅ΆΈΉΊΌΎΏΐάέήίΰόύώϓЃЌѓќḈḉḖḗḮḯḰḱḾḿṌṍṒṓṔṕṸṹẂẃẤấẮắẾếỐốỚớỨứ"),
+  ('\u{302}', "AEIOUaeiouCcGgHhJjSsWwYyZzẠạẸẹỌọ", "ÂÊÎÔÛâêîôûĈĉĜĝĤĥĴĵŜŝŴŵŶŷẐẑẬậỆệỘộ"),
+  ('\u{303}', "ANOanoIiUuVvÂâĂăEeÊêÔôƠơƯưYy", "ÃÑÕãñõĨĩŨũṼṽẪẫẴẵẼẽỄễỖỗỠỡỮữỸỹ"),
+  ('\u{304}', "AaEeIiOoUuÜüÄäȦȧÆæǪǫÖöÕõȮȯYyИиУуGgḶḷṚṛ", "ĀāĒēĪīŌōŪūǕǖǞǟǠǡǢǣǬǭȪȫȬȭȰȱȲȳӢӣӮӯḠḡḸḹṜṝ"),
+  ('\u{306}', "AaEeGgIiOoUuУИиуЖжАаЕеȨȩẠạ", "ĂăĔĕĞğĬĭŎŏŬŭЎЙйўӁӂӐӑӖӗḜḝẶặ"),
+  ('\u{307}', "CcEeGgIZzAaOoBbDdFfHhMmNnPpRrSsŚśŠšṢṣTtWwXxYyſ", "ĊċĖėĠġİŻżȦȧȮȯḂḃḊḋḞḟḢḣṀṁṄṅṖṗṘṙṠṡṤṥṦṧṨṩṪṫẆẇẊẋẎẏẛ"),
+  ('\u{308}', "AEIOUaeiouyYΙΥιυϒЕІеіАаӘәЖжЗзИиОоӨөЭэУуЧчЫыHhÕõŪūWwXxt", "ÄËÏÖÜäëïöüÿŸΪΫϊϋϔЁЇёїӒӓӚӛӜӝӞӟӤӥӦӧӪӫӬӭӰӱӴӵӸӹḦḧṎṏṺṻẄẅẌẍẗ"),
+  ('\u{309}', "AaÂâĂăEeÊêIiOoÔôƠơUuƯưYy", "ẢảẨẩẲẳẺẻỂểỈỉỎỏỔổỞởỦủỬửỶỷ"),
+  ('\u{30a}', "AaUuwy", "ÅåŮůẘẙ"),
+  ('\u{30b}', "OoUuУу", "ŐőŰűӲӳ"),
+  ('\u{30c}', "CcDdEeLlNnRrSsTtZzAaIiOoUuÜüGgKkƷʒjHh", "ČčĎďĚěĽľŇňŘřŠšŤťŽžǍǎǏǐǑǒǓǔǙǚǦǧǨǩǮǯǰȞȟ"),
+  ('\u{30f}', "AaEeIiOoRrUuѴѵ", "ȀȁȄȅȈȉȌȍȐȑȔȕѶѷ"),
+  ('\u{311}', "AaEeIiOoRrUu", "ȂȃȆȇȊȋȎȏȒȓȖȗ"),
+  ('\u{31b}', "OoUu", "ƠơƯư"),
+  ('\u{323}', "BbDdHhKkLlMmNnRrSsTtVvWwZzAaEeIiOoƠơUuƯưYy", "ḄḅḌḍḤḥḲḳḶḷṂṃṆṇṚṛṢṣṬṭṾṿẈẉẒẓẠạẸẹỊịỌọỢợỤụỰựỴỵ"),
+  ('\u{324}', "Uu", "Ṳṳ"),
+  ('\u{325}', "Aa", "Ḁḁ"),
+  ('\u{326}', "SsTt", "ȘșȚț"),
+  ('\u{327}', "CcGgKkLlNnRrSsTtEeDdHh", "ÇçĢģĶķĻļŅņŖŗŞşŢţȨȩḐḑḨḩ"),
+  ('\u{328}', "AaEeIiUuOo", "ĄąĘęĮįŲųǪǫ"),
+  ('\u{32d}', "DdEeLlNnTtUu", "ḒḓḘḙḼḽṊṋṰṱṶṷ"),
+  ('\u{32e}', "Hh", "Ḫḫ"),
+  ('\u{330}', "EeIiUu", "ḚḛḬḭṴṵ"),
+  ('\u{331}', "BbDdKkLlNnRrTtZzh", "ḆḇḎḏḴḵḺḻṈṉṞṟṮṯẔẕẖ"),
+];
+
+/// The first jamo and the numbers of jamo of the Hangul syllables.
+const HANGUL_SYLLABLES: u32 = 0xac00;
+const LEADING: u32 = 0x1100;
+const VOWELS: u32 = 0x1161;
+const TRAILING: u32 = 0x11a7;
+const LEADING_COUNT: u32 = 19;
+const VOWEL_COUNT: u32 = 21;
+const TRAILING_COUNT: u32 = 28;
+
+/// Checks whether `c` may compose with the character before it.
+fn is_combining(c: char) -> bool {
+  matches!(c, '\u{300}'..='\u{36f}' | '\u{1161}'..='\u{11ff}')
+}
+
+/// Composes `first` and `second` into one character, `None` if they do not
+/// compose.
+fn compose(first: char, second: char) -> Option<char> {
+  let (a, b) = (first as u32, second as u32);
+  if (LEADING..LEADING + LEADING_COUNT).contains(&a) && (VOWELS..VOWELS + VOWEL_COUNT).contains(&b) {
+    let syllable = HANGUL_SYLLABLES + ((a - LEADING) * VOWEL_COUNT + b - VOWELS) * TRAILING_COUNT;
+    return char::from_u32(syllable);
+  }
+  let syllables = HANGUL_SYLLABLES..HANGUL_SYLLABLES + LEADING_COUNT * VOWEL_COUNT * TRAILING_COUNT;
+  if syllables.contains(&a) && (a - HANGUL_SYLLABLES).is_multiple_of(TRAILING_COUNT) && (TRAILING + 1..TRAILING + TRAILING_COUNT).contains(&b) {
+    return char::from_u32(a + b - TRAILING);
+  }
+  let (_, letters, composed) = COMPOSED.iter().find(|(mark, _, _)| *mark == second)?;
+  let i = letters.chars().position(|letter| letter == first)?;
+  composed.chars().nth(i)
+}
+
+/// Gets the composed form (NFC) of `text`, borrowed when nothing composes,
+/// see `Tree::find_by_normalized_name`.
+pub fn to_nfc(text: &str) -> Cow<'_, str> {
+  if !text.chars().any(is_combining) {
+    return Cow::Borrowed(text);
+  }
+  let mut normalized = String::with_capacity(text.len());
+  // The last character, which may still compose with the next one.
+  let mut last: Option<char> = None;
+  for c in text.chars() {
+    match last.and_then(|last| compose(last, c)) {
+      Some(composed) => last = Some(composed),
+      None => {
+        normalized.extend(last);
+        last = Some(c);
+      },
+    }
+  }
+  normalized.extend(last);
+  Cow::Owned(normalized)
+}
+
+impl Tree {
+  /// Finds the first node whose name is `name` once both are composed,
+  /// see `to_nfc`.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("cafe\u{301}.txt").unwrap());
+  /// tree.insert(Node::from_path("Vie\u{323}\u{302}t/").unwrap());
+  /// tree.insert(Node::from_path("\u{1112}\u{1161}\u{11ab}.md").unwrap());
+  /// assert!(tree.find_by_name("café.txt").is_none());
+  /// assert!(tree.find_by_normalized_name("café.txt").is_some());
+  /// assert!(tree.find_by_normalized_name("Việt").is_some());
+  /// assert!(tree.find_by_normalized_name("한.md").is_some());
+  /// ```
+  pub fn find_by_normalized_name(&self, name: &str) -> Option<&Node> {
+    let name = to_nfc(name);
+    self.find(|node| to_nfc(&node.name) == name)
+  }
+}
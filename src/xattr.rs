@@ -0,0 +1,140 @@
+//! Reading the extended attributes and the access control lists of the
+//! entries of a scanned tree with the `getfattr` command, run once for the
+//! whole tree rather than once per entry.
+//!
+//! POSIX ACLs are the `system.posix_acl_access` and
+//! `system.posix_acl_default` attributes, so both are listed the same way.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::log::warning;
+use crate::node::*;
+
+/// Checks whether the attribute named `name` holds an access control list.
+pub fn is_acl(name: &str) -> bool {
+  name.starts_with("system.posix_acl_")
+}
+
+/// Gets the mark of an entry with the attributes `names`, like `ls -l`:
+/// `+` for an access control list, `@` for other attributes, `None` for
+/// none.
+///
+/// # Examples
+///
+/// ```
+/// use rust_tree::xattr::marker;
+///
+/// assert_eq!(marker(&["user.origin".to_string()]), Some('@'));
+/// assert_eq!(marker(&["user.origin".to_string(), "system.posix_acl_access".to_string()]), Some('+'));
+/// assert_eq!(marker(&[]), None);
+/// ```
+pub fn marker(names: &[String]) -> Option<char> {
+  match names {
+    [] => None,
+    names if names.iter().any(|name| is_acl(name)) => Some('+'),
+    _ => Some('@'),
+  }
+}
+
+/// Parses the output of `getfattr --absolute-names -m -`, made of blocks
+/// of attribute names under the escaped path of their entry.
+///
+/// # Returns
+///
+/// The paths of the entries with attributes, with the names of these.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use rust_tree::xattr::parse_getfattr;
+///
+/// let output = "# file: /srv/a\\040b.txt\nuser.origin\nsecurity.selinux\n\n# file: /srv/c\nsystem.posix_acl_access\n\n";
+/// let entries = parse_getfattr(output);
+/// assert_eq!(entries[0].0, PathBuf::from("/srv/a b.txt"));
+/// assert_eq!(entries[0].1, ["user.origin", "security.selinux"]);
+/// assert_eq!(entries[1].1, ["system.posix_acl_access"]);
+/// ```
+pub fn parse_getfattr(output: &str) -> Vec<(PathBuf, Vec<String>)> {
+  let mut entries: Vec<(PathBuf, Vec<String>)> = Vec::new();
+  for line in output.lines() {
+    match line.strip_prefix("# file: ") {
+      Some(path) => entries.push((PathBuf::from(unescape(path)), Vec::new())),
+      None if line.is_empty() => {},
+      None => {
+        if let Some((_, names)) = entries.last_mut() {
+          // The values are only printed with -d.
+          let name = line.split_once('=').map_or(line, |(name, _)| name);
+          names.push(unescape(name));
+        }
+      },
+    }
+  }
+  entries
+}
+
+/// Undoes the escapes of `getfattr`, which writes backslashes, spaces and
+/// control characters as octal escapes like `\040`.
+fn unescape(text: &str) -> String {
+  let bytes = text.as_bytes();
+  let mut unescaped = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let octal = bytes.get(i + 1..i + 4)
+      .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|digit| (b'0'..=b'7').contains(digit)))
+      .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+    match octal {
+      Some(byte) => {
+        unescaped.push(byte);
+        i += 4;
+      },
+      None => {
+        unescaped.push(bytes[i]);
+        i += 1;
+      },
+    }
+  }
+  String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+impl Tree {
+  /// Lists the extended attributes of the entries of the tree, including
+  /// mounted trees, under the base of their tree with `getfattr`, and
+  /// stores their names in the metadata of the entries. Symbolic links
+  /// are not followed.
+  ///
+  /// The entries which cannot be read are left without attributes with a
+  /// warning.
+  pub fn load_xattrs(&mut self) -> Result<(), String> {
+    if let Some(base) = self.base.clone() {
+      let output = Command::new("getfattr")
+        .args(["--recursive", "--physical", "--absolute-names", "--match=-", "--"])
+        .arg(&base)
+        .output()
+        .map_err(|e| format!("getfattr: {}", e))?;
+      let errors = String::from_utf8_lossy(&output.stderr);
+      if !output.status.success() {
+        if output.stdout.is_empty() {
+          return Err(format!("getfattr: {}", errors.trim()));
+        }
+        errors.lines().for_each(|line| warning!("{}", line));
+      }
+
+      for (path, names) in parse_getfattr(&String::from_utf8_lossy(&output.stdout)) {
+        let node = match path.strip_prefix(&base).map(Path::to_string_lossy) {
+          Ok(relative) if relative.is_empty() => self.root.as_mut(),
+          Ok(relative) => self.find_by_path_mut(&relative),
+          Err(_) => None,
+        };
+        if let Some(node) = node {
+          node.meta.xattrs = names;
+        }
+      }
+    }
+    for mounted in self.mounts.values_mut() {
+      mounted.load_xattrs()?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,120 @@
+//! Writing trees as XML, in the structure of `tree -X`.
+//!
+//! ```text
+//! <?xml version="1.0" encoding="UTF-8"?>
+//! <tree>
+//!   <directory name="/srv/data">
+//!     <directory name="src">
+//!       <file name="main.rs"></file>
+//!     </directory>
+//!   </directory>
+//!   <report>
+//!     <directories>1</directories>
+//!     <files>1</files>
+//!   </report>
+//! </tree>
+//! ```
+
+use std::io::{self, Write};
+
+use crate::node::*;
+
+/// Escapes the characters of `text` that are special in XML attributes.
+pub(crate) fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// The state of an XML writer.
+struct XmlWriter<'a, W: Write> {
+  out: &'a mut W,
+  /// The depths of the directories whose element is still open.
+  open: Vec<u64>,
+  files: u64,
+  directories: u64,
+}
+
+impl<W: Write> XmlWriter<'_, W> {
+  /// Closes the elements of the open directories at or below `depth`.
+  fn close_to(&mut self, depth: u64) -> io::Result<()> {
+    while let Some(open) = self.open.pop_if(|open| *open >= depth) {
+      writeln!(self.out, "{}</directory>", "  ".repeat(open as usize + 1))?;
+    }
+    Ok(())
+  }
+
+  /// Writes the element of `node`, `depth` levels deep in the output.
+  fn write_node(&mut self, node: &Node, depth: u64) -> io::Result<()> {
+    self.close_to(depth)?;
+    let indent = "  ".repeat(depth as usize + 1);
+    match node.node_type {
+      NodeType::File() => {
+        self.files += 1;
+        writeln!(self.out, "{}<file name=\"{}\"></file>", indent, escape(&node.name))
+      },
+      NodeType::Directory() => {
+        if depth > 0 {
+          self.directories += 1;
+        }
+        self.open.push(depth);
+        writeln!(self.out, "{}<directory name=\"{}\">", indent, escape(&node.name))
+      },
+    }
+  }
+}
+
+impl Tree {
+  /// Writes the tree, including mounted trees, to `out` as XML in the
+  /// structure of `tree -X`, the report not counting the root.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::prelude::*;
+  ///
+  /// let mut tree = Tree::new();
+  /// tree.insert(Node::from_path("src/").unwrap());
+  /// tree.insert(Node::from_path("src/a&b.rs").unwrap());
+  ///
+  /// let mut out = Vec::new();
+  /// tree.write_xml(&mut out).unwrap();
+  /// let xml = String::from_utf8(out).unwrap();
+  /// assert!(xml.contains("    <directory name=\"src\">\n      <file name=\"a&amp;b.rs\"></file>\n    </directory>\n"));
+  /// assert!(xml.contains("<directories>1</directories>"));
+  /// ```
+  pub fn write_xml<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out, "<tree>")?;
+
+    let mut writer = XmlWriter {
+      out: &mut *out,
+      open: Vec::new(),
+      files: 0,
+      directories: 0,
+    };
+    let mut result = Ok(());
+    self.walk(|_, node, offset| {
+      result = writer.write_node(node, node.depth + offset);
+      result.is_ok()
+    });
+    result?;
+    writer.close_to(0)?;
+
+    let (files, directories) = (writer.files, writer.directories);
+    writeln!(out, "  <report>")?;
+    writeln!(out, "    <directories>{}</directories>", directories)?;
+    writeln!(out, "    <files>{}</files>", files)?;
+    writeln!(out, "  </report>")?;
+    writeln!(out, "</tree>")
+  }
+}
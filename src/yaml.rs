@@ -0,0 +1,132 @@
+//! Writing trees as YAML, with the structure of the JSON format.
+//!
+//! ```text
+//! base: /srv/data
+//! root:
+//!   name: /srv/data
+//!   type: directory
+//!   children:
+//!     - name: main.rs
+//!       type: file
+//!       size: 42
+//!       children: []
+//! mounts: {}
+//! ```
+
+use std::io::{self, Write};
+
+use crate::json::{write_string, Json};
+use crate::node::*;
+
+/// The plain scalars YAML parsers read as something else than a string.
+const RESERVED: [&str; 12] = ["true", "false", "null", "yes", "no", "on", "off", "y", "n", "~", ".inf", ".nan"];
+
+/// Writes `s` as a plain scalar when it is read back as the same string,
+/// double-quoted with the JSON escapes otherwise.
+fn write_scalar<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+  let plain = !s.is_empty()
+    && s.chars().all(|c| c.is_alphanumeric() || " _./-+()".contains(c))
+    && !s.starts_with(['-', ' ', '.'])
+    && !s.ends_with(' ')
+    && !RESERVED.contains(&s.to_ascii_lowercase().as_str())
+    && !s.starts_with(|c: char| c.is_ascii_digit() || c == '+');
+  if plain {
+    write!(out, "{}", s)
+  } else {
+    write_string(out, s)
+  }
+}
+
+impl Json {
+  /// Writes the value as a YAML document, in block style.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rust_tree::json::Json;
+  ///
+  /// let json = Json::parse(r#"{ "name": "src", "tags": ["a", "yes"], "size": 42, "meta": {} }"#).unwrap();
+  /// let mut out = Vec::new();
+  /// json.write_yaml(&mut out).unwrap();
+  /// assert_eq!(String::from_utf8(out).unwrap(), "name: src\ntags:\n  - a\n  - \"yes\"\nsize: 42\nmeta: {}\n");
+  /// ```
+  pub fn write_yaml<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    match self {
+      Json::Object(members) if !members.is_empty() => self.write_members(out, members, 0, false),
+      Json::Array(items) if !items.is_empty() => Json::write_items(out, items, 0),
+      value => value.write_after_key(out, 0),
+    }
+  }
+
+  /// Writes `members` one per line at `indent`, the first one on the
+  /// current line if `inline`.
+  fn write_members<W: Write>(&self, out: &mut W, members: &[(String, Json)], indent: usize, inline: bool) -> io::Result<()> {
+    for (i, (key, value)) in members.iter().enumerate() {
+      if i > 0 || !inline {
+        write!(out, "{:width$}", "", width = indent)?;
+      }
+      write_scalar(out, key)?;
+      write!(out, ":")?;
+      match value {
+        Json::Object(_) | Json::Array(_) if !value.is_empty_collection() => {
+          writeln!(out)?;
+        },
+        _ => {
+          write!(out, " ")?;
+        },
+      }
+      value.write_after_key(out, indent + 2)?;
+    }
+    Ok(())
+  }
+
+  /// Writes `items` one per line at `indent`, as a block sequence.
+  fn write_items<W: Write>(out: &mut W, items: &[Json], indent: usize) -> io::Result<()> {
+    for item in items {
+      write!(out, "{:width$}- ", "", width = indent)?;
+      match item {
+        Json::Object(members) if !members.is_empty() => item.write_members(out, members, indent + 2, true)?,
+        Json::Array(items) if !items.is_empty() => {
+          writeln!(out)?;
+          Json::write_items(out, items, indent + 2)?;
+        },
+        _ => item.write_after_key(out, indent + 2)?,
+      }
+    }
+    Ok(())
+  }
+
+  /// Writes the value after its key or its dash, nested collections going
+  /// on the next lines at `indent`.
+  fn write_after_key<W: Write>(&self, out: &mut W, indent: usize) -> io::Result<()> {
+    match self {
+      Json::Null => writeln!(out, "null"),
+      Json::Bool(b) => writeln!(out, "{}", b),
+      Json::Number(n) => writeln!(out, "{}", n),
+      Json::String(s) => {
+        write_scalar(out, s)?;
+        writeln!(out)
+      },
+      Json::Array(items) if items.is_empty() => writeln!(out, "[]"),
+      Json::Array(items) => Json::write_items(out, items, indent),
+      Json::Object(members) if members.is_empty() => writeln!(out, "{{}}"),
+      Json::Object(members) => self.write_members(out, members, indent, false),
+    }
+  }
+
+  fn is_empty_collection(&self) -> bool {
+    match self {
+      Json::Array(items) => items.is_empty(),
+      Json::Object(members) => members.is_empty(),
+      _ => false,
+    }
+  }
+}
+
+impl Tree {
+  /// Writes the tree to `out` as YAML, with the structure of
+  /// `Tree::write_json`.
+  pub fn write_yaml<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    self.to_json().write_yaml(out)
+  }
+}
@@ -0,0 +1,210 @@
+//! Runs the program on small directories and path lists to check how its
+//! arguments are read, what it prints and its exit codes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Creates an empty directory for the test `name` in the temporary
+/// directory.
+fn scratch(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("rust-tree-cli-{}-{}", name, std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Runs the program with `args`, without the configuration of the user.
+fn run(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_rust-tree"))
+    .args(args)
+    .env("RUST_TREE_CONFIG", "/nonexistent/config.toml")
+    .env("NO_COLOR", "1")
+    .output()
+    .unwrap()
+}
+
+#[test]
+fn only_takes_a_single_value() {
+  let dir = scratch("only");
+  for path in ["a/kept.txt", "b/left.txt", "c/other.txt"] {
+    fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+    fs::write(dir.join(path), "").unwrap();
+  }
+  let root = dir.to_str().unwrap();
+
+  let output = run(&["--only", "a", root]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+  assert!(stdout.contains("kept.txt"));
+  assert!(!stdout.contains("left.txt"));
+
+  let output = run(&["--only", "a,b", "--only", "c", root]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(output.status.success());
+  assert!(["kept.txt", "left.txt", "other.txt"].iter().all(|name| stdout.contains(name)));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Writes `text` to the file `name` of `dir`, and returns its path.
+fn write(dir: &Path, name: &str, text: &str) -> String {
+  let path = dir.join(name);
+  fs::write(&path, text).unwrap();
+  path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn exit_codes() {
+  let dir = scratch("exit");
+  fs::create_dir(dir.join("project")).unwrap();
+  fs::write(dir.join("project/a.txt"), "abc").unwrap();
+  let project = dir.join("project");
+  let project = project.to_str().unwrap();
+
+  assert_eq!(run(&[project]).status.code(), Some(0));
+  let json = r#"{"root": {"name": "r", "type": "directory", "children": [
+    {"name": "locked", "type": "directory", "error": "Permission denied", "children": []}
+  ]}, "mounts": {}}"#;
+  let output = run(&[&write(&dir, "tree.json", json)]);
+  assert_eq!(output.status.code(), Some(1));
+  assert!(String::from_utf8_lossy(&output.stdout).contains("locked"));
+  assert_eq!(run(&["--bogus", project]).status.code(), Some(2));
+  let output = run(&["--budget", "a.txt=1", project]);
+  assert_eq!(output.status.code(), Some(3));
+  assert!(String::from_utf8_lossy(&output.stdout).contains("Over budget: a.txt is 3 for a budget of 1"));
+  assert_eq!(run(&[dir.join("missing").to_str().unwrap()]).status.code(), Some(4));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn closed_stderr_does_not_panic() {
+  let dir = scratch("stderr");
+  let list = write(&dir, "paths.txt", "a.txt\n");
+  let mut child = Command::new(env!("CARGO_BIN_EXE_rust-tree"))
+    .args(["--budget", "missing=1", &list])
+    .env("RUST_TREE_CONFIG", "/nonexistent/config.toml")
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  // The warning about the missing budget path goes to a closed pipe.
+  drop(child.stderr.take());
+  assert_eq!(child.wait().unwrap().code(), Some(0));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn scaffold_creates_the_entries() {
+  let dir = scratch("scaffold");
+  let list = write(&dir, "template.txt", "src/\nsrc/main.rs\ndocs/\n");
+  let out = dir.join("out");
+  fs::create_dir(&out).unwrap();
+
+  let output = run(&["scaffold", &list, out.to_str().unwrap()]);
+  assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+  assert!(output.stdout.is_empty());
+  assert!(out.join("src/main.rs").is_file());
+  assert!(out.join("docs").is_dir());
+
+  let evil = write(&dir, "evil.txt", "../evil\n");
+  let output = run(&["scaffold", &evil, out.to_str().unwrap()]);
+  assert_eq!(output.status.code(), Some(4));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("'..': invalid name"));
+  assert!(!dir.join("evil").exists());
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn diff_marks_the_changes() {
+  let dir = scratch("diff");
+  let old = write(&dir, "old.txt", "src/\nsrc/main.rs\nREADME.md\n");
+  let new = write(&dir, "new.txt", "src/\nsrc/main.rs\nsrc/lib.rs\ndocs/\n");
+
+  let output = run(&["diff", &old, &new]);
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "- README.md\n+ docs/\n+ src/lib.rs\n");
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn names_are_escaped() {
+  let dir = scratch("escape");
+  let list = write(&dir, "paths.txt", "bad\x1b[2Jdir/\nbad\x1b[2Jdir/file\n");
+
+  let output = run(&["--hide-control-chars", "--virtual-root", &list]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(stdout.contains("Directory: bad?[2Jdir\n"));
+  assert!(!stdout.contains('\x1b'));
+
+  let output = run(&["-Q", "--virtual-root", &list]);
+  assert!(String::from_utf8_lossy(&output.stdout).contains("File: \"file\"\n"));
+  assert!(String::from_utf8_lossy(&output.stdout).contains("Path: \"bad\\033[2Jdir/file\"\n"));
+
+  for report in [&["--du"][..], &["--top", "5"], &["--leaves"]] {
+    let output = run(&[report, &["--hide-control-chars", &list]].concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("bad?[2Jdir"), "{:?}: {}", report, stdout);
+    assert!(!stdout.contains('\x1b'), "{:?}", report);
+  }
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn min_depth_compact_and_filelimit() {
+  let dir = scratch("views");
+  let list = write(&dir, "paths.txt", "a/\na/b/\na/b/c.txt\nbig/\nbig/1\nbig/2\nbig/3\n");
+
+  let output = run(&["--min-depth", "2", "--virtual-root", &list]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(output.status.success());
+  assert!(stdout.starts_with("Directory: a/b\n"));
+  assert!(stdout.contains("\nFile: big/1\n"));
+  assert!(!stdout.contains("Directory: big\n"));
+
+  let output = run(&["--compact", "--virtual-root", &list]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(stdout.contains("  Directory: a/b\n   Children: 1\n"));
+  assert!(!stdout.contains("Directory: a\n"));
+
+  let output = run(&["--filelimit", "2", "--virtual-root", &list]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(stdout.contains("[3 entries exceeds filelimit]"));
+  assert!(!stdout.contains("big/1"));
+  assert!(stdout.contains("a/b/c.txt"));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn filters_apply_while_loading() {
+  let dir = scratch("filters");
+  let list = write(&dir, "paths.txt", "a/\na/b/\na/b/c.txt\na/d/\nbig/\nbig/1\n");
+
+  let output = run(&["--exclude", "big/", "--budget", "big=1", "--virtual-root", &list]);
+  assert_eq!(output.status.code(), Some(0));
+  assert!(!String::from_utf8_lossy(&output.stdout).contains("big"));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("Budget path big is not in the tree"));
+
+  let output = run(&["--include", "*.txt", "--prune", "--virtual-root", &list]);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(stdout.contains("Path: a/b/c.txt\n"));
+  assert!(!stdout.contains("a/d") && !stdout.contains("big"));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unknown_environment_variables_are_ignored() {
+  let dir = scratch("environment");
+  let list = write(&dir, "paths.txt", "b.txt\na.txt\n");
+  let output = Command::new(env!("CARGO_BIN_EXE_rust-tree"))
+    .args(["--leaves", &list])
+    .env("RUST_TREE_CONFIG", "/nonexistent/config.toml")
+    .env("RUST_TREE_NO_SUCH_OPTION", "1")
+    .env("RUST_TREE_SORT", "name")
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("RUST_TREE_NO_SUCH_OPTION is not an option"));
+  let leaves: Vec<String> = String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect();
+  assert!(leaves[0].ends_with("a.txt") && leaves[1].ends_with("b.txt"));
+  fs::remove_dir_all(&dir).unwrap();
+}